@@ -0,0 +1,15 @@
+-- Create analysis_runs table, one row per `raidme analyze` invocation
+CREATE TABLE IF NOT EXISTS analysis_runs (
+    id TEXT PRIMARY KEY,
+    profile TEXT,
+    provider TEXT NOT NULL,
+    model TEXT NOT NULL,
+    status TEXT NOT NULL,
+    started_at TEXT NOT NULL,
+    completed_at TEXT
+);
+
+-- Link steps to the run that executed them
+ALTER TABLE analysis_steps ADD COLUMN run_id TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_analysis_runs_started_at ON analysis_runs(started_at);
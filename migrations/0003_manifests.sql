@@ -0,0 +1,8 @@
+-- Create manifests table, facts parsed from the build manifests of the repository
+CREATE TABLE IF NOT EXISTS manifests (
+    path TEXT PRIMARY KEY,
+    ecosystem TEXT,
+    name TEXT,
+    facts TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
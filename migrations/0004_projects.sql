@@ -0,0 +1,5 @@
+-- Sub-project of a monorepo an entry or step belongs to, NULL for the whole repository
+ALTER TABLE knowledge_entries ADD COLUMN project TEXT;
+ALTER TABLE analysis_steps ADD COLUMN project TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_knowledge_entries_project ON knowledge_entries(project);
@@ -0,0 +1,14 @@
+-- Create symbols table, public declarations extracted from the source files
+CREATE TABLE IF NOT EXISTS symbols (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    file TEXT NOT NULL,
+    name TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    parent TEXT,
+    signature TEXT NOT NULL,
+    doc TEXT,
+    line INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_symbols_file ON symbols(file);
+CREATE INDEX IF NOT EXISTS idx_symbols_name ON symbols(name);
@@ -0,0 +1,12 @@
+-- Create imports table, the import graph between the source files
+CREATE TABLE IF NOT EXISTS imports (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    file TEXT NOT NULL,
+    specifier TEXT NOT NULL,
+    target TEXT,
+    package TEXT,
+    line INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_imports_file ON imports(file);
+CREATE INDEX IF NOT EXISTS idx_imports_target ON imports(target);
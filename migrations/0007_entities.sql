@@ -0,0 +1,9 @@
+-- Create entities table, the data models extracted from SQL, ORM and API schemas
+CREATE TABLE IF NOT EXISTS entities (
+    file TEXT NOT NULL,
+    name TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    facts TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    PRIMARY KEY (file, name)
+);
@@ -1,15 +1,33 @@
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::fs;
 use anyhow::{Result, Context};
 use serde::{Deserialize, Serialize};
-use sqlx::{SqlitePool};
-use tokio::time::{sleep, Duration};
+use sqlx::{Row, SqlitePool};
 
 use crate::{
-    config::{Config},
-    llm::{LlmClient,LlmContext},
+    apis::{self, ApiDefinition},
+    cargo_workspace::CargoWorkspace,
+    config::{AnalysisConfig, Config, DEFAULT_MAX_CONTEXT_TOKENS},
+    diagrams::{self, Annotations, Diagram},
+    discovery::{Discovery, Entry},
+    imports::{self, Import, ModuleGraph},
+    tree::{self, TreeRenderer},
+    llm::{ContentItem, LlmClient, LlmContext},
+    manifests,
+    projects::{self, Project},
+    runs,
+    schema::{self, Entity},
+    symbols::{self, Symbol},
+    user_context,
 };
 
+/// Tree entries described per request of the file agent
+const DESCRIBE_BATCH: usize = 60;
+
+/// Lines sent for a file without extracted symbols
+const EXCERPT_LINES: usize = 12;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnalysisStep {
     pub id: String,
@@ -25,9 +43,11 @@ pub struct AnalysisStep {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum StepType {
     Basic,
+    Workspace,
     Readme,
     Documentation,
     Package,
+    Files,
     Coding,
     Architecture,
     FinalConsolidation,
@@ -49,30 +69,77 @@ pub struct KnowledgeEntry {
     pub title: String,
     pub content: String,
     pub relevance_score: f64,
+    /// Sub-project the entry describes, `None` for the whole repository
+    pub project: Option<String>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// Part of the repository analyzed on its own: the whole repository or one sub-project
+struct Scope {
+    project: Option<Project>,
+    root: PathBuf,
+    discovery: Discovery,
+    /// Entries of the discovery, walked once for every step of the scope
+    entries: Vec<Entry>,
+}
+
+impl Scope {
+    fn new(root: PathBuf, project: Option<Project>, analysis: &AnalysisConfig) -> Result<Self> {
+        let discovery = Discovery::new(&root, analysis)?;
+        let entries = discovery.entries()?;
+        Ok(Self { project, root, discovery, entries })
+    }
+
+    fn project_name(&self) -> Option<&str> {
+        self.project.as_ref().map(|project| project.name.as_str())
+    }
+
+    fn label(&self) -> &str {
+        self.project_name().unwrap_or("repository")
+    }
+}
+
 pub struct RepositoryAnalyzer {
     config: Config,
     db: SqlitePool,
     llm_client: LlmClient,
     repo_path: PathBuf,
+    user_context: Option<String>,
+    run_id: String,
+    scope: Scope,
 }
 
 impl RepositoryAnalyzer {
     pub async fn new(config: Config, db: SqlitePool, llm_client: LlmClient, repo_path: PathBuf) -> Result<Self> {
+        let user_context = user_context::load(&db).await?;
+        let scope = Scope::new(repo_path.clone(), None, &config.analysis)?;
+
         Ok(Self {
             config,
             db,
             llm_client,
             repo_path,
+            user_context,
+            run_id: uuid::Uuid::new_v4().to_string(),
+            scope,
         })
     }
 
     pub async fn analyze(&self) -> Result<()> {
         println!("Starting repository analysis...");
+        if let Some(profile) = &self.config.profile {
+            println!("Using profile: {}", profile);
+        }
 
+        runs::start(&self.db, &self.run_id, &self.config).await?;
+        let result = self.run_steps().await;
+        let status = if result.is_ok() { StepStatus::Completed } else { StepStatus::Failed };
+        runs::finish(&self.db, &self.run_id, status).await?;
+        result
+    }
+
+    async fn run_steps(&self) -> Result<()> {
         // Check if analysis is resuming or starting fresh
         let last_step = self.get_last_completed_step().await?;
 
@@ -93,10 +160,25 @@ impl RepositoryAnalyzer {
 
     async fn run_full_analysis(&self) -> Result<()> {
         // Step 1: Gather basic information
-        self.analyze_basic().await?;
+        self.analyze_basic(&self.scope).await?;
+
+        // Step 2: Describe the crates of Cargo workspaces
+        self.analyze_workspaces().await?;
+
+        // Step 3: Resolve imports and document the packages and modules
+        self.analyze_packages(&self.scope).await?;
 
-        // Step 6: Generate final README.ai.md
-        self.generate_final_consolidation().await?;
+        // Step 4: Describe the files and directories of the project tree
+        self.describe_files(&self.scope).await?;
+
+        // Step 5: Document the architecture
+        self.analyze_architecture(&self.scope).await?;
+
+        // Step 6: Analyze each sub-project of a monorepo on its own
+        self.analyze_projects().await?;
+
+        // Step 7: Generate final README.ai.md
+        self.generate_final_consolidation(&self.scope).await?;
 
         Ok(())
     }
@@ -104,52 +186,114 @@ impl RepositoryAnalyzer {
     async fn resume_analysis(&self, last_step: AnalysisStep) -> Result<()> {
         match last_step.step_type {
             StepType::Basic => {
-                self.generate_final_readme().await?;
+                self.analyze_workspaces().await?;
+                self.analyze_packages(&self.scope).await?;
+                self.describe_files(&self.scope).await?;
+                self.analyze_architecture(&self.scope).await?;
+                self.analyze_projects().await?;
+                self.generate_final_consolidation(&self.scope).await?;
+            }
+            StepType::Workspace => {
+                self.analyze_packages(&self.scope).await?;
+                self.describe_files(&self.scope).await?;
+                self.analyze_architecture(&self.scope).await?;
+                self.analyze_projects().await?;
+                self.generate_final_consolidation(&self.scope).await?;
             }
             StepType::Readme => {
             }
             StepType::Documentation => {
             }
             StepType::Package => {
+                self.describe_files(&self.scope).await?;
+                self.analyze_architecture(&self.scope).await?;
+                self.analyze_projects().await?;
+                self.generate_final_consolidation(&self.scope).await?;
+            }
+            StepType::Files => {
+                self.analyze_architecture(&self.scope).await?;
+                self.analyze_projects().await?;
+                self.generate_final_consolidation(&self.scope).await?;
             }
             StepType::Coding => {
             }
             StepType::Architecture => {
+                self.analyze_projects().await?;
+                self.generate_final_consolidation(&self.scope).await?;
             }
             StepType::FinalConsolidation => {
-                self.generate_final_consolidation().await?;
+                self.generate_final_consolidation(&self.scope).await?;
                 println!("Analysis already completed!");
             }
         }
         Ok(())
     }
 
-    async fn analyze_basic(&self) -> Result<()> {
-        println!("Analyzing basic repository information...");
+    async fn analyze_basic(&self, scope: &Scope) -> Result<()> {
+        println!("Analyzing basic {} information...", scope.label());
 
         let step_id = uuid::Uuid::new_v4().to_string();
-        self.create_analysis_step(&step_id, StepType::GlobalInfo, "Basic repository analysis").await?;
+        self.create_analysis_step(&step_id, StepType::Basic, "Basic repository analysis", scope.project_name()).await?;
+
+        // Only the repository-wide discovery is stored, sub-projects see their own manifests
+        let manifests = manifests::discover(&scope.entries);
+        if scope.project.is_none() {
+            manifests::store(&self.db, &manifests).await?;
+            println!("📦 Found {} manifest(s)", manifests.len());
+            if self.config.analysis.symbols {
+                self.extract_symbols().await?;
+            }
+            if self.config.analysis.data_models {
+                let project_dirs: Vec<String> = self.projects().await?.into_iter().map(|project| project.dir).collect();
+                let entities = schema::discover(&scope.entries, &project_dirs);
+                schema::store(&self.db, &entities).await?;
+                println!("🗄️ Found {} data model(s)", entities.len());
+            }
+        }
+        let api_outline = self.api_outline(scope).await?;
+        let data_models = self.data_models(scope).await?;
+        let api_definitions = match self.config.analysis.apis {
+            true => apis::discover(&scope.entries),
+            false => Vec::new(),
+        };
+        if !api_definitions.is_empty() {
+            println!("🔌 Found {} API definition(s)", api_definitions.len());
+        }
 
-        let mut context = LlmContext::new(self.config.max_context_tokens);
-        let analysis = self.llm_client.architecture_analysis(|| {
-            let mut context = LlmContext::new(self.config.max_context_tokens);
+        let analysis = self.llm_client.basic_analysis(|| {
+            let mut context = self.new_context();
 
-            // Add package files with high priority
-            for config_file in &["Cargo.toml", "package.json", "pyproject.toml"] {
-                if let Ok(content) = std::fs::read_to_string(config_file) {
-                    context.add_content_simple(content, 90, config_file.to_string());
-                }
+            // Add manifest summaries with high priority
+            for manifest in &manifests {
+                context.add_content(ContentItem::from_file(manifest.summary(), 90, manifest.path.clone()));
             }
 
-            // Add directory structure with medium priority
-            if let Ok(dir_structure) = self.get_directory_structure() {
-                context.add_content_simple(dir_structure, 70, "Directory Structure".to_string());
+            // Add the public API outline, more accurate than guessing it from source files
+            if let Some(outline) = &api_outline {
+                context.add_content_simple(outline.clone(), 80, "Public API Outline".to_string());
+            }
+
+            // Add the endpoints and RPCs of the API definitions
+            for definition in &api_definitions {
+                context.add_content(ContentItem::from_file(
+                    format!("{}\n\n{}", definition.heading(), definition.render()),
+                    80,
+                    definition.file.clone(),
+                ));
+            }
+
+            // Add the entities extracted from the schemas
+            if !data_models.is_empty() {
+                context.add_content_simple(schema::summary(&data_models), 75, "Data Models".to_string());
             }
 
+            // Add directory structure with medium priority
+            context.add_content_simple(self.get_directory_structure(scope), 70, "Directory Structure".to_string());
+
             // Add main source files with lower priority
-            if let Ok(main_files) = self.get_main_source_files() {
+            if let Ok(main_files) = self.get_main_source_files(scope) {
                 for (file_path, content) in main_files {
-                    context.add_content_simple(content, 50, file_path);
+                    context.add_content(ContentItem::from_file(content, 50, file_path));
                 }
             }
 
@@ -162,14 +306,51 @@ impl RepositoryAnalyzer {
             id: uuid::Uuid::new_v4().to_string(),
             category: "basic".to_string(),
             subcategory: None,
-            title: "Repository Basic Overview".to_string(),
+            title: match &scope.project {
+                Some(project) => format!("{} Basic Overview", project.name),
+                None => "Repository Basic Overview".to_string(),
+            },
             content: analysis.clone(),
             relevance_score: 1.0,
+            project: scope.project_name().map(str::to_string),
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
         };
 
         self.store_knowledge_entry(&knowledge_entry).await?;
+
+        self.delete_knowledge_entries("api", scope).await?;
+        if let Some(outline) = api_outline {
+            self.store_knowledge_entry(&KnowledgeEntry {
+                id: uuid::Uuid::new_v4().to_string(),
+                category: "api".to_string(),
+                subcategory: None,
+                title: "Public API".to_string(),
+                content: outline,
+                relevance_score: 0.9,
+                project: scope.project_name().map(str::to_string),
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            }).await?;
+        }
+
+        self.delete_knowledge_entries("data-models", scope).await?;
+        if !data_models.is_empty() {
+            self.store_knowledge_entry(&KnowledgeEntry {
+                id: uuid::Uuid::new_v4().to_string(),
+                category: "data-models".to_string(),
+                subcategory: None,
+                title: "Data Models".to_string(),
+                content: schema::summary(&data_models),
+                relevance_score: 0.9,
+                project: scope.project_name().map(str::to_string),
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            }).await?;
+        }
+
+        self.store_api_definitions(scope, &api_definitions).await?;
+
         self.complete_analysis_step(&step_id, &analysis).await?;
 
         println!("Basic analysis completed");
@@ -319,125 +500,596 @@ impl RepositoryAnalyzer {
     //     Ok(())
     // }
 
-    async fn generate_final_consolidation(&self) -> Result<()> {
-        println!("Generating final README.ai.md...");
+    /// Parse the analyzed source files and store their public symbols
+    async fn extract_symbols(&self) -> Result<()> {
+        let mut symbols = Vec::new();
+        for entry in self.scope.discovery.files(&self.scope.entries) {
+            let relative = entry.relative.to_string_lossy();
+            match symbols::extract_file(&entry.path, &relative) {
+                Ok(Some(file_symbols)) => symbols.extend(file_symbols),
+                Ok(None) => {}
+                Err(e) => println!("⚠️ Skipping symbols of {}: {}", relative, e),
+            }
+        }
+        symbols::store(&self.db, &symbols).await?;
+        println!("🔎 Extracted {} public symbol(s)", symbols.len());
+        Ok(())
+    }
+
+    /// Outline of the stored symbols of a scope, `None` when there are none
+    async fn api_outline(&self, scope: &Scope) -> Result<Option<String>> {
+        if !self.config.analysis.symbols {
+            return Ok(None);
+        }
+        let dir = scope.project.as_ref().map(|project| project.dir.as_str());
+        let symbols = symbols::load(&self.db, dir).await?;
+        Ok((!symbols.is_empty()).then(|| symbols::outline(&symbols)))
+    }
+
+    /// Replace the API definitions of a scope, one knowledge entry per source file
+    async fn store_api_definitions(&self, scope: &Scope, definitions: &[ApiDefinition]) -> Result<()> {
+        self.delete_knowledge_entries(apis::CATEGORY, scope).await?;
+
+        // Keyed by repository path, like the file descriptions
+        let prefix = scope.project.as_ref().map(|project| format!("{}/", project.dir)).unwrap_or_default();
+        for definition in definitions {
+            self.store_knowledge_entry(&KnowledgeEntry {
+                id: uuid::Uuid::new_v4().to_string(),
+                category: apis::CATEGORY.to_string(),
+                subcategory: Some(format!("{}{}", prefix, definition.file)),
+                title: definition.heading(),
+                content: definition.render(),
+                relevance_score: 0.9,
+                project: scope.project_name().map(str::to_string),
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            }).await?;
+        }
+        Ok(())
+    }
+
+    /// APIs and Interfaces section of a scope, from the stored API definitions
+    async fn apis_section(&self, scope: &Scope) -> Result<String> {
+        let rows = sqlx::query(
+            "SELECT subcategory, title, content FROM knowledge_entries WHERE category = $1 AND project IS $2 \
+             AND subcategory IS NOT NULL ORDER BY created_at ASC"
+        )
+        .bind(apis::CATEGORY)
+        .bind(scope.project_name())
+        .fetch_all(&self.db)
+        .await?;
+
+        let prefix = scope.project.as_ref().map(|project| format!("{}/", project.dir)).unwrap_or_default();
+        let mut definitions = BTreeMap::new();
+        for row in rows {
+            let path: String = row.try_get("subcategory")?;
+            if let Some(path) = path.strip_prefix(&prefix) {
+                definitions.insert(path.to_string(), (row.try_get("title")?, row.try_get("content")?));
+            }
+        }
+        Ok(apis::section(&definitions, &self.config.output_path))
+    }
+
+    /// Stored entities defined in a scope, none when disabled
+    async fn data_models(&self, scope: &Scope) -> Result<Vec<Entity>> {
+        if !self.config.analysis.data_models {
+            return Ok(Vec::new());
+        }
+        let dir = scope.project.as_ref().map(|project| project.dir.as_str());
+        Ok(schema::load(&self.db, dir).await?)
+    }
+
+    async fn analyze_workspaces(&self) -> Result<()> {
+        self.delete_knowledge_entries("crate", &self.scope).await?;
+        self.delete_knowledge_entries("workspace", &self.scope).await?;
+        let workspaces = CargoWorkspace::from_manifests(&manifests::load(&self.db).await?);
+        if workspaces.is_empty() {
+            return Ok(());
+        }
+        println!("Analyzing Cargo workspaces...");
+
+        let step_id = uuid::Uuid::new_v4().to_string();
+        self.create_analysis_step(&step_id, StepType::Workspace, "Cargo workspace analysis", None).await?;
+
+        for workspace in &workspaces {
+            println!("🦀 Workspace {}: {} crate(s)", workspace.root, workspace.crates.len());
+            for krate in &workspace.crates {
+                self.store_knowledge_entry(&KnowledgeEntry {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    category: "crate".to_string(),
+                    subcategory: Some(krate.dir.clone()),
+                    title: format!("Crate {}", krate.name),
+                    content: workspace.crate_section(krate),
+                    relevance_score: 0.8,
+                    project: None,
+                    created_at: chrono::Utc::now(),
+                    updated_at: chrono::Utc::now(),
+                }).await?;
+            }
+
+            self.store_knowledge_entry(&KnowledgeEntry {
+                id: uuid::Uuid::new_v4().to_string(),
+                category: "workspace".to_string(),
+                subcategory: Some(workspace.root.clone()),
+                title: format!("Crate dependencies of {}", workspace.root),
+                content: workspace.summary(),
+                relevance_score: 0.9,
+                project: None,
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            }).await?;
+        }
+
+        self.complete_analysis_step(&step_id, &format!("{} workspace(s) analyzed", workspaces.len())).await?;
+        println!("Workspace analysis completed");
+        Ok(())
+    }
+
+    async fn analyze_packages(&self, scope: &Scope) -> Result<()> {
+        println!("Analyzing packages and module dependencies of {}...", scope.label());
+
+        let step_id = uuid::Uuid::new_v4().to_string();
+        self.create_analysis_step(&step_id, StepType::Package, "Package and module dependency analysis", scope.project_name()).await?;
+
+        let graph = if self.config.analysis.imports {
+            // Imports are resolved once for the whole repository
+            if scope.project.is_none() {
+                self.extract_imports().await?;
+            }
+            self.module_graph(scope).await?
+        } else {
+            None
+        };
+        self.delete_knowledge_entries("modules", scope).await?;
+        if let Some(graph) = &graph {
+            let cycles = graph.cycles();
+            if !cycles.is_empty() {
+                println!("🔁 {} import cycle(s) between modules", cycles.len());
+            }
+            self.store_knowledge_entry(&KnowledgeEntry {
+                id: uuid::Uuid::new_v4().to_string(),
+                category: "modules".to_string(),
+                subcategory: None,
+                title: "Module dependencies".to_string(),
+                content: graph.summary(),
+                relevance_score: 0.9,
+                project: scope.project_name().map(str::to_string),
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            }).await?;
+        }
+
+        let api_outline = self.api_outline(scope).await?;
+        let analysis = self.llm_client.package_analysis(|| {
+            let mut context = self.new_context();
+            if let Some(graph) = &graph {
+                context.add_content_simple(graph.summary(), 90, "Import/Dependency Relationships".to_string());
+            }
+            context.add_content_simple(self.get_directory_structure(scope), 70, "Directory Structure".to_string());
+            if let Some(outline) = &api_outline {
+                context.add_content_simple(outline.clone(), 60, "Public API Outline".to_string());
+            }
+            Ok(context)
+        }).await?;
+
+        self.store_knowledge_entry(&KnowledgeEntry {
+            id: uuid::Uuid::new_v4().to_string(),
+            category: "package".to_string(),
+            subcategory: None,
+            title: "Package Structure".to_string(),
+            content: analysis.clone(),
+            relevance_score: 0.8,
+            project: scope.project_name().map(str::to_string),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }).await?;
+
+        self.complete_analysis_step(&step_id, &analysis).await?;
+        println!("Package analysis of {} completed", scope.label());
+        Ok(())
+    }
+
+    /// Ask the file agent for a one-line description of every entry of the project tree
+    async fn describe_files(&self, scope: &Scope) -> Result<()> {
+        if !self.config.analysis.tree_descriptions {
+            return Ok(());
+        }
+        println!("Describing {} files and directories...", scope.label());
 
         let step_id = uuid::Uuid::new_v4().to_string();
-        self.create_analysis_step(&step_id, StepType::FinalGeneration, "Final README generation").await?;
+        self.create_analysis_step(&step_id, StepType::Files, "File and directory descriptions", scope.project_name()).await?;
+
+        // Descriptions are keyed by repository path, the entries by path within the scope
+        let prefix = scope.project.as_ref().map(|project| format!("{}/", project.dir)).unwrap_or_default();
+        let mut outlines: BTreeMap<String, Vec<Symbol>> = BTreeMap::new();
+        if self.config.analysis.symbols {
+            let dir = scope.project.as_ref().map(|project| project.dir.as_str());
+            for symbol in symbols::load(&self.db, dir).await? {
+                let file = symbol.file.strip_prefix(&prefix).unwrap_or(&symbol.file).to_string();
+                outlines.entry(file).or_default().push(symbol);
+            }
+        }
+
+        // Vendored and generated entries are listed in the tree but not described
+        let entries: Vec<&Entry> = scope.entries.iter().filter(|entry| entry.class.is_none()).collect();
+        let mut descriptions = Annotations::new();
+        for batch in entries.chunks(DESCRIBE_BATCH) {
+            let paths: Vec<String> = batch.iter().map(|entry| entry.relative.to_string_lossy().to_string()).collect();
+            let listing: Vec<String> = batch
+                .iter()
+                .zip(&paths)
+                .map(|(entry, path)| if entry.is_dir { format!("- {}/", path) } else { format!("- {}", path) })
+                .collect();
+            let excerpts: Vec<(String, String)> = batch
+                .iter()
+                .zip(&paths)
+                .filter(|(entry, _)| !entry.is_dir)
+                .filter_map(|(entry, path)| match outlines.get(path) {
+                    Some(symbols) => Some((path.clone(), symbols::outline(symbols))),
+                    None => self.file_excerpt(&entry.path).map(|excerpt| (path.clone(), excerpt)),
+                })
+                .collect();
+
+            let answer = self.llm_client.file_analysis(|| {
+                let mut context = self.new_context();
+                context.add_content(ContentItem::new_non_summarizable(listing.join("\n"), 95, "Paths to Describe".to_string()));
+                for (path, excerpt) in &excerpts {
+                    context.add_content(ContentItem::from_file(excerpt.clone(), 50, path.clone()));
+                }
+                Ok(context)
+            }).await?;
+            descriptions.extend(diagrams::parse_annotations(&answer, paths.iter().map(String::as_str)).1);
+        }
+
+        self.delete_knowledge_entries(tree::DESCRIPTION_CATEGORY, scope).await?;
+        for (path, description) in &descriptions {
+            self.store_knowledge_entry(&KnowledgeEntry {
+                id: uuid::Uuid::new_v4().to_string(),
+                category: tree::DESCRIPTION_CATEGORY.to_string(),
+                subcategory: Some(format!("{}{}", prefix, path)),
+                title: format!("Description of {}", path),
+                content: description.clone(),
+                relevance_score: 0.4,
+                project: scope.project_name().map(str::to_string),
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            }).await?;
+        }
+
+        let summary = format!("Described {} of {} entries", descriptions.len(), entries.len());
+        self.complete_analysis_step(&step_id, &summary).await?;
+        println!("🏷️ {}", summary);
+        Ok(())
+    }
+
+    /// First lines of a file, `None` when it is too large or not text
+    fn file_excerpt(&self, path: &std::path::Path) -> Option<String> {
+        if fs::metadata(path).ok()?.len() as usize > self.config.analysis.max_file_size {
+            return None;
+        }
+        let content = fs::read_to_string(path).ok()?;
+        let excerpt: Vec<&str> = content.lines().take(EXCERPT_LINES).collect();
+        (!excerpt.is_empty()).then(|| excerpt.join("\n"))
+    }
+
+    async fn analyze_architecture(&self, scope: &Scope) -> Result<()> {
+        println!("Analyzing architecture of {}...", scope.label());
+
+        let step_id = uuid::Uuid::new_v4().to_string();
+        self.create_analysis_step(&step_id, StepType::Architecture, "Architecture analysis", scope.project_name()).await?;
+
+        // Diagrams are drawn from the extracted structure, the agent only annotates them
+        let diagrams = self.diagrams(scope).await?;
+        let graph = self.module_graph(scope).await?;
+        let current_knowledge = self.get_current_knowledge(scope).await?;
+        let analysis = self.llm_client.architecture_analysis(|| {
+            let mut context = self.new_context();
+            if !diagrams.is_empty() {
+                context.add_content(ContentItem::new_non_summarizable(
+                    diagrams::section(&diagrams, &Annotations::new()),
+                    95,
+                    "Generated Diagrams".to_string(),
+                ));
+            }
+            if let Some(graph) = &graph {
+                context.add_content_simple(graph.summary(), 90, "Module Dependencies".to_string());
+            }
+            context.add_content_simple(current_knowledge.clone(), 80, "Current Knowledge".to_string());
+            Ok(context)
+        }).await?;
+
+        let (prose, annotations) = diagrams::parse_annotations(&analysis, diagrams.iter().flat_map(Diagram::paths));
+        self.delete_knowledge_entries(diagrams::ROLE_CATEGORY, scope).await?;
+        for (path, role) in &annotations {
+            self.store_knowledge_entry(&KnowledgeEntry {
+                id: uuid::Uuid::new_v4().to_string(),
+                category: diagrams::ROLE_CATEGORY.to_string(),
+                subcategory: Some(path.clone()),
+                title: format!("Role of {}", path),
+                content: role.clone(),
+                relevance_score: 0.5,
+                project: scope.project_name().map(str::to_string),
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            }).await?;
+        }
+
+        self.store_knowledge_entry(&KnowledgeEntry {
+            id: uuid::Uuid::new_v4().to_string(),
+            category: "architecture".to_string(),
+            subcategory: None,
+            title: "Architecture".to_string(),
+            content: prose,
+            relevance_score: 0.9,
+            project: scope.project_name().map(str::to_string),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }).await?;
+
+        self.complete_analysis_step(&step_id, &analysis).await?;
+        println!("Architecture analysis completed ({} diagram(s), {} node(s) annotated)", diagrams.len(), annotations.len());
+        Ok(())
+    }
+
+    /// Diagrams of a scope, drawn from the stored imports and manifests
+    async fn diagrams(&self, scope: &Scope) -> Result<Vec<Diagram>> {
+        let mut diagrams = Vec::new();
+
+        if self.config.analysis.imports {
+            let imports = self.scope_imports(scope).await?;
+            diagrams.extend(Diagram::modules(&ModuleGraph::new(&imports)));
+            if scope.project.is_none() {
+                diagrams.extend(Diagram::layers(&imports));
+            }
+        }
+
+        if scope.project.is_none() {
+            let workspaces = CargoWorkspace::from_manifests(&manifests::load(&self.db).await?);
+            diagrams.extend(workspaces.iter().filter(|workspace| workspace.crates.len() > 1).map(Diagram::crates));
+        }
+        Ok(diagrams)
+    }
+
+    /// Roles of the diagram nodes of a scope given by the architecture agent, the latest winning
+    async fn annotations(&self, scope: &Scope) -> Result<Annotations> {
+        let rows = sqlx::query(
+            "SELECT subcategory, content FROM knowledge_entries WHERE category = $1 AND project IS $2 \
+             AND subcategory IS NOT NULL ORDER BY created_at ASC"
+        )
+        .bind(diagrams::ROLE_CATEGORY)
+        .bind(scope.project_name())
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut annotations = Annotations::new();
+        for row in rows {
+            annotations.insert(row.try_get("subcategory")?, row.try_get("content")?);
+        }
+        Ok(annotations)
+    }
+
+    /// Resolve and store the imports of the analyzed source files
+    async fn extract_imports(&self) -> Result<()> {
+        let paths = self.scope.entries
+            .iter()
+            .filter(|entry| !entry.is_dir)
+            .map(|entry| entry.relative.to_string_lossy().to_string());
+        let resolver = imports::Resolver::new(paths, &manifests::load(&self.db).await?);
+
+        let mut found = Vec::new();
+        for entry in self.scope.discovery.files(&self.scope.entries) {
+            let relative = entry.relative.to_string_lossy();
+            match imports::extract_file(&entry.path, &relative, &resolver) {
+                Ok(Some(file_imports)) => found.extend(file_imports),
+                Ok(None) => {}
+                Err(e) => println!("⚠️ Skipping imports of {}: {}", relative, e),
+            }
+        }
+        imports::store(&self.db, &found).await?;
+        println!("🔗 Resolved {} import(s)", found.len());
+        Ok(())
+    }
+
+    /// Stored imports made by the files of a scope
+    async fn scope_imports(&self, scope: &Scope) -> Result<Vec<Import>> {
+        let mut imports = imports::load(&self.db).await?;
+        if let Some(project) = &scope.project {
+            let prefix = format!("{}/", project.dir);
+            imports.retain(|import| import.file.starts_with(&prefix));
+        }
+        Ok(imports)
+    }
+
+    /// Module graph of the stored imports of a scope, `None` when disabled or without internal imports
+    async fn module_graph(&self, scope: &Scope) -> Result<Option<ModuleGraph>> {
+        if !self.config.analysis.imports {
+            return Ok(None);
+        }
+        let graph = ModuleGraph::new(&self.scope_imports(scope).await?);
+        Ok((!graph.is_empty()).then_some(graph))
+    }
+
+    /// Sub-projects of a monorepo, empty when disabled or for single-package repositories
+    async fn projects(&self) -> Result<Vec<Project>> {
+        if !self.config.analysis.sub_projects {
+            return Ok(Vec::new());
+        }
+        Ok(projects::detect(&manifests::load(&self.db).await?))
+    }
 
+    async fn analyze_projects(&self) -> Result<()> {
+        for project in self.projects().await? {
+            if self.project_completed(&project.name).await? {
+                println!("Sub-project {} already analyzed, skipping", project.name);
+                continue;
+            }
 
+            let scope = Scope::new(self.repo_path.join(&project.dir), Some(project), &self.config.analysis)?;
+            self.analyze_basic(&scope).await?;
+            self.analyze_packages(&scope).await?;
+            self.describe_files(&scope).await?;
+            self.analyze_architecture(&scope).await?;
+            self.generate_final_consolidation(&scope).await?;
+        }
+        Ok(())
+    }
 
-        let mut context = LlmContext::new(self.config.max_context_tokens);
+    /// Whether a sub-project got its knowledge file since the repository was last analyzed
+    async fn project_completed(&self, project: &str) -> Result<bool> {
+        let completed = serde_json::to_string(&StepStatus::Completed)?;
+        let row = sqlx::query(
+            "SELECT COUNT(*) AS count FROM analysis_steps WHERE project = $1 AND step_type = $2 AND status = $3 \
+             AND completed_at >= (SELECT MAX(completed_at) FROM analysis_steps WHERE project IS NULL AND step_type = $4 AND status = $3)"
+        )
+        .bind(project)
+        .bind(serde_json::to_string(&StepType::FinalConsolidation)?)
+        .bind(&completed)
+        .bind(serde_json::to_string(&StepType::Basic)?)
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(row.try_get::<i64, _>("count")? > 0)
+    }
+
+    async fn generate_final_consolidation(&self, scope: &Scope) -> Result<()> {
+        println!("Generating final README.ai.md for {}...", scope.label());
+
+        let step_id = uuid::Uuid::new_v4().to_string();
+        self.create_analysis_step(&step_id, StepType::FinalConsolidation, "Final README generation", scope.project_name()).await?;
+
+        let all_knowledge = self.get_current_knowledge(scope).await?;
         let consolidation = self.llm_client.final_consolidation(|| {
-            let mut context = LlmContext::new(self.config.max_context_tokens);
-            let all_knowledge = self.get_current_knowledge().await?;
-            context.add_content_simple(all_knowledge, 90, config_file.to_string());
+            let mut context = self.new_context();
+            context.add_content_simple(all_knowledge.clone(), 90, "Current Knowledge".to_string());
             Ok(context)
         }).await?;
 
 
-        // Write to file
-        fs::write(&self.config.output_path, &consolidation)
+        // The project tree is rendered by raidme rather than reproduced by the LLM
+        let mut consolidation = tree::embed(&consolidation, &self.project_structure_section(scope).await?);
+
+        // So are the diagrams, annotated by the architecture agent
+        let diagrams = diagrams::section(&self.diagrams(scope).await?, &self.annotations(scope).await?);
+        consolidation = diagrams::embed(&consolidation, &diagrams);
+
+        // And the data models, drawn from the schemas
+        consolidation = schema::embed(&consolidation, &schema::section(&self.data_models(scope).await?));
+
+        // And the endpoints of the API definitions
+        consolidation = apis::embed(&consolidation, &self.apis_section(scope).await?);
+
+        // Knowledge files of a monorepo link to each other
+        match &scope.project {
+            Some(project) => {
+                consolidation.push_str(&format!("\n---\n\n{}", projects::parent_link(project, &self.config.output_path)));
+            }
+            None => {
+                let projects = self.projects().await?;
+                if !projects.is_empty() {
+                    consolidation.push_str(&format!("\n{}", projects::index_section(&projects, &self.config.output_path)));
+                }
+            }
+        }
+
+        // Write to file, relative paths being resolved against the repository (or sub-project)
+        let output_path = scope.root.join(&self.config.output_path);
+        if let Some(parent) = output_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&output_path, &consolidation)
             .context("Failed to write README.ai.md")?;
 
         self.complete_analysis_step(&step_id, "README.ai.md generated successfully").await?;
 
-        println!("Final README.ai.md generated at {:?}", self.config.output_path);
+        println!("Final README.ai.md generated at {:?}", output_path);
         Ok(())
     }
-    // fn get_directory_structure(&self, path: &Path, max_depth: usize) -> Result<String> {
-    //     let mut result = String::new();
-    //     self.build_tree_string(path, &mut result, "", max_depth, 0)?;
-    //     Ok(result)
-    // }
 
-    // fn build_tree_string(&self, path: &Path, result: &mut String, prefix: &str, max_depth: usize, current_depth: usize) -> Result<()> {
-    //     if current_depth >= max_depth {
-    //         return Ok(());
-    //     }
+    fn max_context_tokens(&self) -> usize {
+        self.config.llm.max_context_tokens.unwrap_or(DEFAULT_MAX_CONTEXT_TOKENS)
+    }
 
-    //     let mut entries: Vec<_> = fs::read_dir(path)?
-    //         .filter_map(|entry| entry.ok())
-    //         .filter(|entry| {
-    //             if let Some(name) = entry.file_name().to_str() {
-    //                 !self.should_ignore(name)
-    //             } else {
-    //                 false
-    //             }
-    //         })
-    //         .collect();
-
-    //     entries.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
-
-    //     for (i, entry) in entries.iter().enumerate() {
-    //         let is_last = i == entries.len() - 1;
-    //         let entry_prefix = if is_last { "└── " } else { "├── " };
-    //         let next_prefix = if is_last { "    " } else { "│   " };
-
-    //         result.push_str(&format!("{}{}{}\n", prefix, entry_prefix, entry.file_name().to_string_lossy()));
-
-    //         if entry.path().is_dir() {
-    //             self.build_tree_string(
-    //                 &entry.path(),
-    //                 result,
-    //                 &format!("{}{}", prefix, next_prefix),
-    //                 max_depth,
-    //                 current_depth + 1
-    //             )?;
-    //         }
-    //     }
+    /// Create an LLM context with the user context pinned at the top
+    fn new_context(&self) -> LlmContext {
+        let mut context = LlmContext::new(self.max_context_tokens());
+        if let Some(user_context) = &self.user_context {
+            context.add_content(ContentItem::new_non_summarizable(
+                user_context.clone(),
+                user_context::PRIORITY,
+                user_context::TITLE.to_string(),
+            ));
+        }
+        context
+    }
 
-    //     Ok(())
-    // }
+    fn get_directory_structure(&self, scope: &Scope) -> String {
+        TreeRenderer::new(&scope.entries, self.config.analysis.tree_max_entries).render()
+    }
 
-    // fn should_ignore(&self, name: &str) -> bool {
-    //     self.config.ignore_patterns.iter().any(|pattern| {
-    //         if pattern.contains('*') {
-    //             // Simple glob matching
-    //             let pattern = pattern.replace("*", "");
-    //             name.contains(&pattern)
-    //         } else {
-    //             name == pattern
-    //         }
-    //     })
-    // }
+    /// Project Structure section of the knowledge file, annotated with the file analyses
+    async fn project_structure_section(&self, scope: &Scope) -> Result<String> {
+        let rows = sqlx::query(
+            "SELECT subcategory, content FROM knowledge_entries WHERE category = $1 AND project IS $2 \
+             AND subcategory IS NOT NULL ORDER BY created_at ASC"
+        )
+        .bind(tree::DESCRIPTION_CATEGORY)
+        .bind(scope.project_name())
+        .fetch_all(&self.db)
+        .await?;
 
-    // fn identify_key_files(&self) -> Result<Vec<PathBuf>> {
-    //     let mut key_files = Vec::new();
+        // Descriptions are keyed by repository path, the tree by path within the scope
+        let prefix = scope.project.as_ref().map(|project| format!("{}/", project.dir)).unwrap_or_default();
+        let mut descriptions = BTreeMap::new();
+        for row in rows {
+            let path: String = row.try_get("subcategory")?;
+            if let Some(path) = path.strip_prefix(&prefix) {
+                descriptions.insert(path.to_string(), row.try_get("content")?);
+            }
+        }
 
-    //     // Common important files
-    //     let important_patterns = vec![
-    //         "main.rs", "lib.rs", "mod.rs",
-    //         "main.py", "__init__.py",
-    //         "index.js", "app.js", "server.js",
-    //         "Main.java", "Application.java",
-    //         "main.go",
-    //         "Dockerfile", "docker-compose.yml",
-    //         "Makefile", "CMakeLists.txt",
-    //     ];
+        Ok(TreeRenderer::new(&scope.entries, self.config.analysis.tree_max_entries)
+            .with_descriptions(descriptions)
+            .render_section())
+    }
 
-    //     self.find_files_recursive(&self.config.repository_path, &important_patterns, &mut key_files)?;
+    fn get_main_source_files(&self, scope: &Scope) -> Result<Vec<(String, String)>> {
+        let mut files = Vec::new();
 
-    //     Ok(key_files)
-    // }
+        for entry in self.identify_key_files(scope) {
+            let Ok(metadata) = fs::metadata(&entry.path) else { continue };
+            if metadata.len() as usize > self.config.analysis.max_file_size {
+                continue;
+            }
 
-    // fn find_files_recursive(&self, dir: &Path, patterns: &[&str], results: &mut Vec<PathBuf>) -> Result<()> {
-    //     for entry in fs::read_dir(dir)? {
-    //         let entry = entry?;
-    //         let path = entry.path();
+            if let Ok(content) = fs::read_to_string(&entry.path) {
+                files.push((entry.relative.to_string_lossy().to_string(), content));
+            }
+        }
 
-    //         if path.is_dir() {
-    //             if !self.should_ignore(&entry.file_name().to_string_lossy()) {
-    //                 self.find_files_recursive(&path, patterns, results)?;
-    //             }
-    //         } else if let Some(filename) = path.file_name().and_then(|n| n.to_str()) {
-    //             if patterns.iter().any(|&pattern| filename.contains(pattern)) {
-    //                 results.push(path);
-    //             }
-    //         }
-    //     }
-    //     Ok(())
-    // }
+        Ok(files)
+    }
+
+    fn identify_key_files<'a>(&self, scope: &'a Scope) -> Vec<&'a Entry> {
+        // Common important files
+        let important_patterns = [
+            "main.rs", "lib.rs", "mod.rs",
+            "main.py", "__init__.py",
+            "index.js", "app.js", "server.js",
+            "Main.java", "Application.java",
+            "main.go",
+            "Dockerfile", "docker-compose.yml",
+            "Makefile", "CMakeLists.txt",
+        ];
+
+        scope.entries
+            .iter()
+            .filter(|entry| !entry.is_dir && entry.class.is_none())
+            .filter(|entry| {
+                let name = entry.file_name();
+                important_patterns.iter().any(|pattern| name.contains(pattern))
+            })
+            .collect()
+    }
 
     // fn read_documentation_recursive(&self, dir: &Path) -> Result<String> {
     //     let mut content = String::new();
@@ -463,18 +1115,20 @@ impl RepositoryAnalyzer {
 
     // Database operations
 
-    async fn create_analysis_step(&self, id: &str, step_type: StepType, input_data: &str) -> Result<()> {
+    async fn create_analysis_step(&self, id: &str, step_type: StepType, input_data: &str, project: Option<&str>) -> Result<()> {
         let step_type_str = serde_json::to_string(&step_type)?;
         let status_str = serde_json::to_string(&StepStatus::InProgress)?;
 
         sqlx::query(
-            "INSERT INTO analysis_steps (id, step_type, status, input_data, created_at) VALUES ($1, $2, $3, $4, $5)"
+            "INSERT INTO analysis_steps (id, step_type, status, input_data, created_at, run_id, project) VALUES ($1, $2, $3, $4, $5, $6, $7)"
         )
         .bind(id)
         .bind(step_type_str)
         .bind(status_str)
         .bind(input_data)
         .bind(chrono::Utc::now())
+        .bind(&self.run_id)
+        .bind(project)
         .execute(&self.db)
         .await?;
 
@@ -499,7 +1153,7 @@ impl RepositoryAnalyzer {
 
     async fn get_last_completed_step(&self) -> Result<Option<AnalysisStep>> {
         let row = sqlx::query(
-            "SELECT * FROM analysis_steps WHERE status = $1 ORDER BY created_at DESC LIMIT 1"
+            "SELECT * FROM analysis_steps WHERE status = $1 AND project IS NULL ORDER BY created_at DESC LIMIT 1"
         )
         .bind(serde_json::to_string(&StepStatus::Completed)?)
         .fetch_optional(&self.db)
@@ -507,14 +1161,14 @@ impl RepositoryAnalyzer {
 
         if let Some(row) = row {
             Ok(Some(AnalysisStep {
-                id: row.id,
-                step_type: serde_json::from_str(&row.step_type)?,
-                status: serde_json::from_str(&row.status)?,
-                input_data: row.input_data,
-                output_data: row.output_data,
-                error_message: row.error_message,
-                created_at: row.created_at,
-                completed_at: row.completed_at,
+                id: row.try_get("id")?,
+                step_type: serde_json::from_str(row.try_get("step_type")?)?,
+                status: serde_json::from_str(row.try_get("status")?)?,
+                input_data: row.try_get("input_data")?,
+                output_data: row.try_get("output_data")?,
+                error_message: row.try_get("error_message")?,
+                created_at: row.try_get("created_at")?,
+                completed_at: row.try_get("completed_at")?,
             }))
         } else {
             Ok(None)
@@ -523,7 +1177,7 @@ impl RepositoryAnalyzer {
 
     async fn store_knowledge_entry(&self, entry: &KnowledgeEntry) -> Result<()> {
         sqlx::query(
-            "INSERT INTO knowledge_entries (id, category, subcategory, title, content, relevance_score, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"
+            "INSERT INTO knowledge_entries (id, category, subcategory, title, content, relevance_score, created_at, updated_at, project) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"
         )
         .bind(&entry.id)
         .bind(&entry.category)
@@ -533,131 +1187,46 @@ impl RepositoryAnalyzer {
         .bind(entry.relevance_score)
         .bind(entry.created_at)
         .bind(entry.updated_at)
+        .bind(&entry.project)
         .execute(&self.db)
         .await?;
 
         Ok(())
     }
 
-    async fn get_current_knowledge(&self) -> Result<String> {
+    /// Remove the entries of a category stored for a scope, before they are produced again
+    async fn delete_knowledge_entries(&self, category: &str, scope: &Scope) -> Result<()> {
+        sqlx::query("DELETE FROM knowledge_entries WHERE category = $1 AND project IS $2")
+            .bind(category)
+            .bind(scope.project_name())
+            .execute(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Knowledge of a scope; a sub-project also sees the description of its crate
+    async fn get_current_knowledge(&self, scope: &Scope) -> Result<String> {
         let rows = sqlx::query(
-            "SELECT category, title, content FROM knowledge_entries ORDER BY relevance_score DESC, created_at ASC"
+            "SELECT category, title, content FROM knowledge_entries WHERE category != $1 AND category != $4 \
+             AND category != $5 AND (project IS $2 OR (category = 'crate' AND subcategory = $3)) \
+             ORDER BY relevance_score DESC, created_at ASC"
         )
+        .bind(user_context::CATEGORY)
+        .bind(scope.project_name())
+        .bind(scope.project.as_ref().map(|project| project.dir.as_str()))
+        .bind(diagrams::ROLE_CATEGORY)
+        .bind(tree::DESCRIPTION_CATEGORY)
         .fetch_all(&self.db)
         .await?;
 
         let mut knowledge = String::new();
         for row in rows {
-            knowledge.push_str(&format!("## {} - {}\n{}\n\n", row.category, row.title, row.content));
+            let category: String = row.try_get("category")?;
+            let title: String = row.try_get("title")?;
+            let content: String = row.try_get("content")?;
+            knowledge.push_str(&format!("## {} - {}\n{}\n\n", category, title, content));
         }
 
         Ok(knowledge)
     }
-
-    // Prompt creation methods
-
-    fn create_global_analysis_prompt(&self) -> String {
-        r#"You are an expert software architect analyzing a git repository. Your task is to provide a comprehensive overview of the repository based on the provided information.
-
-Please analyze and provide:
-
-1. **Project Purpose**: What does this project do? What problem does it solve?
-2. **Technology Stack**: What languages, frameworks, and tools are used?
-3. **Architecture Overview**: High-level architecture and design patterns
-4. **Key Components**: Main modules, services, or components
-5. **Dependencies**: Important external dependencies and their purposes
-6. **Build/Deployment**: How is the project built and deployed?
-
-Focus on information that would help an AI coding assistant understand the project structure and make better code suggestions. Be concise but comprehensive."#.to_string()
-    }
-
-    fn create_documentation_analysis_prompt(&self) -> String {
-        r#"You are analyzing project documentation to extract architectural and implementation details.
-
-Based on the documentation provided, please identify and extract:
-
-1. **Architectural Decisions**: Design patterns, architectural styles, key decisions
-2. **API Specifications**: Endpoints, interfaces, contracts
-3. **Configuration**: Important configuration options and their purposes
-4. **Workflows**: Key processes, business logic flows
-5. **Integration Points**: External systems, services, databases
-6. **Development Guidelines**: Coding standards, best practices mentioned
-
-Combine this with the existing knowledge to create a more complete understanding. Avoid duplicating information already well-covered in the existing knowledge."#.to_string()
-    }
-
-    fn create_directory_analysis_prompt(&self) -> String {
-        r#"You are analyzing the directory structure of a software project to understand its organization and architecture.
-
-Based on the directory structure provided, please analyze:
-
-1. **Project Organization**: How is the code organized? What patterns are used?
-2. **Module Boundaries**: How are different concerns separated?
-3. **Layer Architecture**: Are there clear layers (presentation, business, data)?
-4. **Package Structure**: What do the directory names suggest about functionality?
-5. **Configuration and Assets**: Where are configs, resources, and static files?
-6. **Testing Structure**: How are tests organized?
-
-Focus on insights that help understand the codebase structure and inform better development decisions."#.to_string()
-    }
-
-    fn create_file_analysis_prompt(&self, file_path: &str) -> String {
-        format!(r#"You are analyzing a specific source code file to understand its role in the project architecture.
-
-File: {}
-
-Please analyze this file and provide:
-
-1. **Purpose**: What is this file's main responsibility?
-2. **Key Functions/Classes**: Main components and their roles
-3. **Dependencies**: What other parts of the system does it depend on?
-4. **Interfaces**: What APIs, contracts, or interfaces does it define/implement?
-5. **Patterns**: What design patterns or architectural patterns are used?
-6. **Integration Points**: How does it connect to other system components?
-
-Focus on architectural insights rather than implementation details. Consider how this file fits into the broader system design."#, file_path)
-    }
-
-    fn create_architecture_prompt(&self) -> String {
-        r#"You are creating architecture diagrams and documentation based on your analysis of a software project.
-
-Based on all the knowledge gathered, please create:
-
-1. **System Architecture Diagram**: A high-level view of major components and their relationships (use ASCII art or Mermaid syntax)
-2. **Component Interaction Flow**: How do the main components communicate?
-3. **Data Flow**: How does data move through the system?
-4. **Deployment Architecture**: How is the system deployed and what are the runtime components?
-5. **Technology Stack Diagram**: Visual representation of the technology layers
-
-Use Mermaid diagram syntax where possible for clear, readable diagrams. Focus on helping developers understand the system's architecture quickly."#.to_string()
-    }
-
-    fn create_final_readme_prompt(&self) -> String {
-        r#"You are creating a comprehensive README.ai.md file that will serve as an architecture knowledge base for AI coding assistants.
-
-Based on all the analyzed knowledge, create a well-structured README.ai.md that includes:
-
-# Structure Requirements:
-1. **Overview**: Project purpose and key capabilities
-2. **Architecture**: High-level system design and patterns
-3. **Project Structure**: Directory layout and organization
-4. **Key Components**: Major modules and their responsibilities
-5. **Technology Stack**: Languages, frameworks, tools used
-6. **APIs and Interfaces**: Key contracts and endpoints
-7. **Data Models**: Important data structures and schemas
-8. **Configuration**: Key configuration options and their purposes
-9. **Development Workflow**: Build, test, deploy processes
-10. **Integration Points**: External dependencies and services
-11. **Diagrams**: Architecture and flow diagrams
-
-# Format Requirements:
-- Use clear Markdown formatting
-- Include code examples where relevant
-- Use Mermaid diagrams for visual representations
-- Structure information hierarchically
-- Focus on information that helps AI assistants make better code suggestions
-- Be comprehensive but avoid unnecessary verbosity
-
-The goal is to create documentation that enables any AI coding assistant to understand the project deeply and provide contextually appropriate suggestions and modifications."#.to_string()
-    }
 }
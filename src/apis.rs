@@ -0,0 +1,433 @@
+//! API surface extracted from interface definitions.
+//!
+//! OpenAPI / Swagger documents (YAML or JSON), protobuf services and GraphQL
+//! SDL are reduced to their endpoints, RPCs and root fields with parameters,
+//! request and response types. Each definition is stored as a knowledge
+//! entry keyed by its source file, and rendered by raidme as the APIs and
+//! Interfaces section of the knowledge file, inserted at [`APIS_MARKER`].
+
+use crate::diagrams;
+use crate::discovery::Entry;
+use crate::error::Result;
+use crate::projects;
+use crate::schema;
+use regex::Regex;
+use serde_json::Value as Json;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+use std::sync::LazyLock;
+
+/// Line of the final consolidation replaced by the APIs and Interfaces section
+pub const APIS_MARKER: &str = "<!-- raidme:apis -->";
+
+/// Heading of the APIs and Interfaces section
+pub const APIS_HEADING: &str = "## APIs and Interfaces";
+
+/// Knowledge category of the API definitions, keyed by source file
+pub const CATEGORY: &str = "interface";
+
+/// Operations listed per definition before eliding the rest
+const MAX_OPERATIONS: usize = 200;
+
+/// HTTP methods of an OpenAPI path item, in display order
+const HTTP_METHODS: &[&str] = &["get", "post", "put", "patch", "delete", "head", "options", "trace"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiKind {
+    OpenApi,
+    Protobuf,
+    GraphQl,
+}
+
+impl fmt::Display for ApiKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ApiKind::OpenApi => "OpenAPI",
+            ApiKind::Protobuf => "gRPC",
+            ApiKind::GraphQl => "GraphQL",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Param {
+    pub name: String,
+    pub ty: String,
+    /// Where an HTTP parameter goes: path, query, header, cookie
+    pub location: Option<String>,
+    pub required: bool,
+}
+
+/// An endpoint, RPC or root field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Operation {
+    /// HTTP method, `rpc`, `query`, `mutation` or `subscription`
+    pub method: String,
+    /// URL path, `Service.Method` or field name
+    pub name: String,
+    pub params: Vec<Param>,
+    pub request: Option<String>,
+    pub response: Option<String>,
+    pub summary: Option<String>,
+}
+
+/// The operations of one definition file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiDefinition {
+    /// Path relative to the discovery root
+    pub file: String,
+    pub kind: ApiKind,
+    pub title: String,
+    pub operations: Vec<Operation>,
+}
+
+impl ApiDefinition {
+    /// Title of the knowledge entry, e.g. `OpenAPI: Petstore`
+    pub fn heading(&self) -> String {
+        format!("{}: {}", self.kind, self.title)
+    }
+
+    /// Markdown table of the operations, the content of the knowledge entry
+    pub fn render(&self) -> String {
+        let mut table = String::from("| Method | Endpoint | Parameters | Request | Response | Description |\n|---|---|---|---|---|---|\n");
+        for operation in self.operations.iter().take(MAX_OPERATIONS) {
+            let params: Vec<String> = operation
+                .params
+                .iter()
+                .map(|param| {
+                    let optional = if param.required { "" } else { "?" };
+                    let location = param.location.as_ref().map(|l| format!(" ({})", l)).unwrap_or_default();
+                    format!("`{}{}`: {}{}", param.name, optional, param.ty, location)
+                })
+                .collect();
+            let cells = [
+                operation.method.clone(),
+                format!("`{}`", operation.name),
+                params.join(", "),
+                operation.request.as_ref().map(|ty| format!("`{}`", ty)).unwrap_or_default(),
+                operation.response.as_ref().map(|ty| format!("`{}`", ty)).unwrap_or_default(),
+                operation.summary.clone().unwrap_or_default(),
+            ];
+            let cells: Vec<String> = cells.iter().map(|cell| cell.replace('|', "\\|").replace('\n', " ")).collect();
+            table.push_str(&format!("| {} |\n", cells.join(" | ")));
+        }
+        if self.operations.len() > MAX_OPERATIONS {
+            table.push_str(&format!("\n_… {} more operations._\n", self.operations.len() - MAX_OPERATIONS));
+        }
+        table
+    }
+}
+
+/// Extract the API definitions found among the discovered entries, by path
+pub fn discover(entries: &[Entry]) -> Vec<ApiDefinition> {
+    let mut files: Vec<&Entry> = entries.iter().filter(|e| !e.is_dir && e.class.is_none()).collect();
+    files.sort_by(|a, b| a.relative.cmp(&b.relative));
+
+    let mut definitions = Vec::new();
+    for entry in files {
+        let relative = entry.relative.to_string_lossy().replace('\\', "/");
+        let extension = entry.path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+        if !matches!(extension.as_str(), "json" | "yaml" | "yml" | "proto" | "graphql" | "graphqls" | "gql") {
+            continue;
+        }
+        let Ok(content) = std::fs::read_to_string(&entry.path) else { continue };
+
+        let parsed = match extension.as_str() {
+            "proto" => Ok(parse_proto(&content, &relative)),
+            "graphql" | "graphqls" | "gql" => Ok(parse_graphql(&content, &relative)),
+            _ => parse_openapi(&entry.path, &content, &relative),
+        };
+        match parsed {
+            Ok(Some(definition)) if !definition.operations.is_empty() => definitions.push(definition),
+            Ok(_) => {}
+            Err(e) => println!("⚠️ Skipping API definition {}: {}", relative, e),
+        }
+    }
+    definitions
+}
+
+/// Endpoints of an OpenAPI 3 or Swagger 2 document, `None` for other JSON and YAML files
+fn parse_openapi(path: &Path, content: &str, file: &str) -> Result<Option<ApiDefinition>> {
+    if !content.contains("openapi") && !content.contains("swagger") {
+        return Ok(None);
+    }
+    let document = schema::parse_document(path, content)?;
+    if document.get("openapi").is_none() && document.get("swagger").is_none() {
+        return Ok(None);
+    }
+    let resolve = |value: &'_ Json| -> Json {
+        value
+            .get("$ref")
+            .and_then(Json::as_str)
+            .and_then(|reference| document.pointer(reference.trim_start_matches('#')))
+            .unwrap_or(value)
+            .clone()
+    };
+
+    let title = document.pointer("/info/title").and_then(Json::as_str).map(str::to_string);
+    let mut definition = ApiDefinition {
+        file: file.to_string(),
+        kind: ApiKind::OpenApi,
+        title: title.unwrap_or_else(|| file_stem(file)),
+        operations: Vec::new(),
+    };
+
+    let paths = document.get("paths").and_then(Json::as_object).cloned().unwrap_or_default();
+    for (route, item) in &paths {
+        let shared: Vec<Json> = item.get("parameters").and_then(Json::as_array).cloned().unwrap_or_default();
+        for method in HTTP_METHODS {
+            let Some(operation) = item.get(*method) else { continue };
+            let mut params = Vec::new();
+            let mut request = None;
+            let own = operation.get("parameters").and_then(Json::as_array).cloned().unwrap_or_default();
+            for param in shared.iter().chain(&own).map(&resolve) {
+                let Some(name) = param.get("name").and_then(Json::as_str) else { continue };
+                let location = param.get("in").and_then(Json::as_str).unwrap_or_default();
+                let schema = param.get("schema").unwrap_or(&param);
+                // Swagger 2 carries the request body as a parameter
+                if location == "body" {
+                    request = Some(type_name(schema));
+                    continue;
+                }
+                params.push(Param {
+                    name: name.to_string(),
+                    ty: type_name(schema),
+                    location: Some(location.to_string()),
+                    required: param.get("required").and_then(Json::as_bool).unwrap_or(location == "path"),
+                });
+            }
+            if let Some(body) = operation.get("requestBody").map(&resolve) {
+                request = media_schema(&body).map(type_name).or(request);
+            }
+
+            let responses = operation.get("responses").and_then(Json::as_object).cloned().unwrap_or_default();
+            let success = responses.iter().find(|(status, _)| status.starts_with('2')).or_else(|| responses.iter().find(|(status, _)| *status == "default"));
+            let response = success.map(|(status, response)| {
+                let response = resolve(response);
+                media_schema(&response).or_else(|| response.get("schema")).map(type_name).unwrap_or_else(|| status.clone())
+            });
+
+            let summary = ["summary", "operationId"].iter().find_map(|key| operation.get(*key)?.as_str()).map(str::to_string);
+            definition.operations.push(Operation {
+                method: method.to_uppercase(),
+                name: route.clone(),
+                params,
+                request,
+                response,
+                summary,
+            });
+        }
+    }
+    Ok(Some(definition))
+}
+
+/// Schema of the first media type of an OpenAPI 3 request body or response
+fn media_schema(value: &Json) -> Option<&Json> {
+    value.get("content")?.as_object()?.values().find_map(|media| media.get("schema"))
+}
+
+/// Short name of an OpenAPI schema: the referenced name, `T[]` for arrays, else the type
+fn type_name(schema: &Json) -> String {
+    if let Some(reference) = schema.get("$ref").and_then(Json::as_str) {
+        return reference.rsplit('/').next().unwrap_or(reference).to_string();
+    }
+    if let Some(items) = schema.get("items") {
+        return format!("{}[]", type_name(items));
+    }
+    for (key, separator) in [("oneOf", " | "), ("anyOf", " | "), ("allOf", " & ")] {
+        if let Some(variants) = schema.get(key).and_then(Json::as_array) {
+            return variants.iter().map(type_name).collect::<Vec<_>>().join(separator);
+        }
+    }
+    ["format", "type"]
+        .iter()
+        .find_map(|key| schema.get(*key)?.as_str())
+        .unwrap_or("object")
+        .to_string()
+}
+
+static PROTO_PACKAGE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?m)^\s*package\s+([\w.]+)\s*;").expect("valid package pattern"));
+
+static PROTO_SERVICE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\bservice\s+(\w+)\s*\{").expect("valid service pattern"));
+
+static PROTO_MESSAGE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\bmessage\s+(\w+)\s*\{").expect("valid message pattern"));
+
+static PROTO_RPC: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)((?:[ \t]*//[^\n]*\n)*)\s*rpc\s+(\w+)\s*\(\s*(stream\s+)?([\w.]+)\s*\)\s*returns\s*\(\s*(stream\s+)?([\w.]+)\s*\)")
+        .expect("valid rpc pattern")
+});
+
+static PROTO_FIELD: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*(optional\s+|repeated\s+|required\s+)?(map\s*<[^>]+>|[\w.]+)\s+(\w+)\s*=\s*\d+").expect("valid field pattern")
+});
+
+/// RPCs of the services of a `.proto` file, their parameters being the fields of the request message
+fn parse_proto(content: &str, file: &str) -> Option<ApiDefinition> {
+    let messages: HashMap<String, Vec<Param>> = PROTO_MESSAGE
+        .captures_iter(content)
+        .filter_map(|captures| {
+            let body = braced(content, captures.get(0)?.end())?;
+            let fields = top_level_lines(body)
+                .filter_map(|line| PROTO_FIELD.captures(line))
+                .map(|field| Param {
+                    name: field[3].to_string(),
+                    ty: match field.get(1).map(|m| m.as_str().trim()) {
+                        Some("repeated") => format!("{}[]", &field[2]),
+                        _ => field[2].to_string(),
+                    },
+                    location: None,
+                    required: field.get(1).is_none_or(|m| m.as_str().trim() != "optional"),
+                })
+                .collect();
+            Some((captures[1].to_string(), fields))
+        })
+        .collect();
+
+    let mut operations = Vec::new();
+    for service in PROTO_SERVICE.captures_iter(content) {
+        let Some(body) = service.get(0).and_then(|m| braced(content, m.end())) else { continue };
+        for rpc in PROTO_RPC.captures_iter(body) {
+            let stream = |i: usize| if rpc.get(i).is_some() { "stream " } else { "" };
+            let comment: Vec<&str> = rpc[1].lines().map(|line| line.trim().trim_start_matches('/').trim()).filter(|l| !l.is_empty()).collect();
+            let request = &rpc[4];
+            operations.push(Operation {
+                method: "rpc".to_string(),
+                name: format!("{}.{}", &service[1], &rpc[2]),
+                params: messages.get(request.rsplit('.').next().unwrap_or(request)).cloned().unwrap_or_default(),
+                request: Some(format!("{}{}", stream(3), request)),
+                response: Some(format!("{}{}", stream(5), &rpc[6])),
+                summary: (!comment.is_empty()).then(|| comment.join(" ")),
+            });
+        }
+    }
+
+    let title = PROTO_PACKAGE.captures(content).map(|c| c[1].to_string()).unwrap_or_else(|| file_stem(file));
+    Some(ApiDefinition { file: file.to_string(), kind: ApiKind::Protobuf, title, operations })
+}
+
+static GRAPHQL_SCHEMA: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\bschema\s*(?:@\w+\s*)*\{").expect("valid schema pattern"));
+
+static GRAPHQL_ROOT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b(query|mutation|subscription)\s*:\s*(\w+)").expect("valid root pattern"));
+
+static GRAPHQL_TYPE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b(?:extend\s+)?type\s+(\w+)[^{]*\{").expect("valid type pattern"));
+
+static GRAPHQL_FIELD: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)(\w+)\s*(?:\(([^)]*)\))?\s*:\s*([\[\]\w!]+)").expect("valid field pattern")
+});
+
+static GRAPHQL_ARGUMENT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(\w+)\s*:\s*([\[\]\w!]+)").expect("valid argument pattern"));
+
+static GRAPHQL_NOISE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?s)"""(.*?)"""|"(?:[^"\\]|\\.)*"|#[^\n]*|@\w+(?:\([^)]*\))?"#).expect("valid noise pattern")
+});
+
+/// Root fields (queries, mutations, subscriptions) of a GraphQL schema
+fn parse_graphql(content: &str, file: &str) -> Option<ApiDefinition> {
+    // Descriptions, comments and directives are dropped, the description above a field kept as its summary
+    let mut summaries = HashMap::new();
+    let mut stripped = String::with_capacity(content.len());
+    let mut last = 0;
+    for noise in GRAPHQL_NOISE.captures_iter(content) {
+        let whole = noise.get(0)?;
+        stripped.push_str(&content[last..whole.start()]);
+        stripped.push(' ');
+        last = whole.end();
+        let description = noise.get(1).map(|m| m.as_str()).or_else(|| whole.as_str().strip_prefix('"')?.strip_suffix('"'));
+        let field = GRAPHQL_FIELD.captures(content[whole.end()..].trim_start()).filter(|field| field.get(0).is_some_and(|m| m.start() == 0));
+        if let (Some(description), Some(field)) = (description, field) {
+            summaries.insert(field[1].to_string(), description.split_whitespace().collect::<Vec<_>>().join(" "));
+        }
+    }
+    stripped.push_str(&content[last..]);
+
+    let mut roots: BTreeMap<String, String> =
+        ["query", "mutation", "subscription"].iter().map(|kind| (capitalize(kind), kind.to_string())).collect();
+    if let Some(body) = GRAPHQL_SCHEMA.find(&stripped).and_then(|m| braced(&stripped, m.end())) {
+        roots = GRAPHQL_ROOT.captures_iter(body).map(|c| (c[2].to_string(), c[1].to_string())).collect();
+    }
+
+    let mut operations = Vec::new();
+    for root in GRAPHQL_TYPE.captures_iter(&stripped) {
+        let Some(kind) = roots.get(&root[1]) else { continue };
+        let Some(body) = root.get(0).and_then(|m| braced(&stripped, m.end())) else { continue };
+        for field in GRAPHQL_FIELD.captures_iter(body) {
+            let params = field
+                .get(2)
+                .map(|args| {
+                    GRAPHQL_ARGUMENT
+                        .captures_iter(args.as_str())
+                        .map(|arg| Param { name: arg[1].to_string(), ty: arg[2].to_string(), location: None, required: arg[2].ends_with('!') })
+                        .collect()
+                })
+                .unwrap_or_default();
+            operations.push(Operation {
+                method: kind.clone(),
+                name: field[1].to_string(),
+                params,
+                request: None,
+                response: Some(field[3].to_string()),
+                summary: summaries.get(&field[1]).cloned(),
+            });
+        }
+    }
+
+    Some(ApiDefinition { file: file.to_string(), kind: ApiKind::GraphQl, title: file_stem(file), operations })
+}
+
+/// Text between the brace opened just before `start` and its closing brace
+fn braced(text: &str, start: usize) -> Option<&str> {
+    let mut depth = 1;
+    for (i, c) in text[start..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&text[start..start + i]);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Lines of a block outside of its nested blocks
+fn top_level_lines(body: &str) -> impl Iterator<Item = &str> {
+    let mut depth = 0i32;
+    body.lines().filter(move |line| {
+        let top = depth == 0;
+        depth += line.matches('{').count() as i32 - line.matches('}').count() as i32;
+        top && depth == 0
+    })
+}
+
+fn file_stem(file: &str) -> String {
+    Path::new(file).file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_else(|| file.to_string())
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    chars.next().map(|c| c.to_uppercase().chain(chars).collect()).unwrap_or_default()
+}
+
+/// APIs and Interfaces section of the knowledge file from the stored definitions,
+/// `(title, table)` by path relative to the knowledge file's root; empty without any
+pub fn section(definitions: &BTreeMap<String, (String, String)>, output_path: &str) -> String {
+    if definitions.is_empty() {
+        return String::new();
+    }
+    let mut section = format!("{}\n", APIS_HEADING);
+    for (file, (title, table)) in definitions {
+        let link = projects::relative_link(output_path, file);
+        section.push_str(&format!("\n### {}\n\nDefined in [`{}`]({}).\n\n{}", title, file, link, table));
+    }
+    section
+}
+
+/// Insert the APIs and Interfaces section into a generated document: at the marker, else at the end
+pub fn embed(document: &str, section: &str) -> String {
+    diagrams::embed_section(document, APIS_MARKER, APIS_HEADING, section)
+}
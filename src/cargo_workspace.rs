@@ -0,0 +1,136 @@
+//! Cargo workspaces reconstructed from the parsed manifests.
+//!
+//! Gives the same picture as `cargo metadata` without running cargo: the
+//! member crates of each workspace, their targets and features, and the
+//! dependency edges between them, drawn by [`crate::diagrams`].
+
+use crate::manifests::{DependencyKind, Ecosystem, Manifest, Target};
+use crate::projects;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// A package of a workspace
+#[derive(Debug, Clone)]
+pub struct Crate {
+    pub name: String,
+    /// Directory relative to the repository root, empty for the root package
+    pub dir: String,
+    pub version: Option<String>,
+    pub targets: Vec<Target>,
+    pub features: Vec<String>,
+    /// Summary of the manifest, external dependencies included
+    pub summary: String,
+}
+
+/// Dependency of a crate on another crate of the same workspace
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+    pub kind: DependencyKind,
+}
+
+#[derive(Debug, Clone)]
+pub struct CargoWorkspace {
+    /// Path of the root manifest
+    pub root: String,
+    pub crates: Vec<Crate>,
+    pub edges: Vec<Edge>,
+}
+
+impl CargoWorkspace {
+    /// Workspaces declared by the manifests, i.e. Cargo.toml files with `workspace.members`
+    pub fn from_manifests(manifests: &[Manifest]) -> Vec<Self> {
+        let packages: Vec<&Manifest> = manifests
+            .iter()
+            .filter(|m| m.ecosystem == Some(Ecosystem::Cargo) && m.name.is_some())
+            .collect();
+
+        manifests
+            .iter()
+            .filter(|m| m.ecosystem == Some(Ecosystem::Cargo) && !m.members.is_empty())
+            .map(|root| {
+                let root_dir = manifest_dir(&root.path);
+                let members: Vec<globset::GlobMatcher> = root
+                    .members
+                    .iter()
+                    .filter_map(|member| projects::member_matcher(&root_dir, member))
+                    .collect();
+
+                let crates: Vec<Crate> = packages
+                    .iter()
+                    .filter(|package| {
+                        let dir = manifest_dir(&package.path);
+                        package.path == root.path || members.iter().any(|member| member.is_match(&dir))
+                    })
+                    .map(|package| Crate {
+                        name: package.name.clone().unwrap_or_default(),
+                        dir: manifest_dir(&package.path),
+                        version: package.version.clone(),
+                        targets: package.targets.clone(),
+                        features: package.features.clone(),
+                        summary: package.summary(),
+                    })
+                    .collect();
+
+                let names: BTreeSet<&str> = crates.iter().map(|c| c.name.as_str()).collect();
+                let mut edges = BTreeSet::new();
+                for package in &packages {
+                    let Some(from) = package.name.as_deref().filter(|name| names.contains(name)) else { continue };
+                    if !crates.iter().any(|c| c.name == from && c.dir == manifest_dir(&package.path)) {
+                        continue;
+                    }
+                    for dependency in &package.dependencies {
+                        if dependency.name != from && names.contains(dependency.name.as_str()) {
+                            edges.insert(Edge { from: from.to_string(), to: dependency.name.clone(), kind: dependency.kind });
+                        }
+                    }
+                }
+
+                CargoWorkspace { root: root.path.clone(), crates, edges: edges.into_iter().collect() }
+            })
+            .filter(|workspace| !workspace.crates.is_empty())
+            .collect()
+    }
+
+    /// Crates of the workspace depending on `name`
+    pub fn dependents(&self, name: &str) -> Vec<&str> {
+        self.edges.iter().filter(|e| e.to == name).map(|e| e.from.as_str()).collect()
+    }
+
+    /// Crates of the workspace `name` depends on
+    pub fn dependencies(&self, name: &str) -> Vec<&str> {
+        self.edges.iter().filter(|e| e.from == name).map(|e| e.to.as_str()).collect()
+    }
+
+    /// Knowledge section of one crate
+    pub fn crate_section(&self, krate: &Crate) -> String {
+        let mut section = krate.summary.clone();
+        let dependencies = self.dependencies(&krate.name);
+        if !dependencies.is_empty() {
+            section.push_str(&format!("Workspace crates used: {}\n", dependencies.join(", ")));
+        }
+        let dependents = self.dependents(&krate.name);
+        if !dependents.is_empty() {
+            section.push_str(&format!("Used by: {}\n", dependents.join(", ")));
+        }
+        section
+    }
+
+    /// Text list of the dependencies between the crates
+    pub fn summary(&self) -> String {
+        let mut summary = format!("Workspace {} with {} crate(s)\n", self.root, self.crates.len());
+        for edge in &self.edges {
+            match edge.kind {
+                DependencyKind::Normal => summary.push_str(&format!("{} -> {}\n", edge.from, edge.to)),
+                kind => summary.push_str(&format!("{} -> {} ({})\n", edge.from, edge.to, format!("{:?}", kind).to_lowercase())),
+            }
+        }
+        summary
+    }
+}
+
+/// Directory of a manifest, relative to the repository root
+fn manifest_dir(path: &str) -> String {
+    Path::new(path).parent().map(|dir| dir.to_string_lossy().to_string()).unwrap_or_default()
+}
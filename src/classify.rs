@@ -0,0 +1,319 @@
+//! Classification of discovered files whose content is not worth analyzing.
+//!
+//! Binary files are recognised by a NUL byte in their first bytes, generated
+//! files by their name (lockfiles, protobuf output, minified assets) or a
+//! header marker such as `Code generated ... DO NOT EDIT`, and vendored code
+//! by its directory. `.gitattributes` entries setting `linguist-vendored`,
+//! `linguist-generated` or `binary` take precedence, as they do on GitHub.
+
+use crate::error::{Error, Result};
+use globset::{Glob, GlobMatcher};
+use regex::Regex;
+use std::fmt;
+use std::io::Read;
+use std::path::Path;
+use std::sync::LazyLock;
+
+/// Bytes read from the start of a file to sniff its content
+const SNIFF_LEN: usize = 8 * 1024;
+
+/// Lines of the header searched for a generated-code marker
+const HEADER_LINES: usize = 10;
+
+/// Header markers of generated files: the Go convention, `@generated`, .NET's
+/// `<auto-generated>` and the usual generator sentence; a bare "do not edit" is not enough
+static GENERATED_MARKER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"Code generated .* DO NOT EDIT|@generated\b|<auto-generated\b|(?i:this file (?:was|is|has been) (?:auto[- ]?|automatically )?generated)",
+    )
+    .expect("valid generated marker pattern")
+});
+
+/// File names of lockfiles and other generated manifests
+const GENERATED_FILES: &[&str] = &[
+    "Cargo.lock", "package-lock.json", "yarn.lock", "pnpm-lock.yaml", "bun.lockb",
+    "poetry.lock", "Pipfile.lock", "uv.lock", "Gemfile.lock", "composer.lock",
+    "go.sum", "flake.lock", "packages.lock.json",
+];
+
+/// File name suffixes of generated sources and assets
+const GENERATED_SUFFIXES: &[&str] = &[
+    ".min.js", ".min.css", ".map", ".pb.go", ".pb.cc", ".pb.h", "_pb2.py", "_pb2_grpc.py",
+    ".pb.swift", "_pb.js", "_pb.d.ts", ".g.dart", ".freezed.dart", ".designer.cs", ".generated.ts",
+];
+
+/// Extensions checked for minification
+const MINIFIABLE_EXTENSIONS: &[&str] = &["js", "mjs", "cjs", "css"];
+
+/// Average line length above which a script or stylesheet is considered minified
+const MINIFIED_LINE_LENGTH: usize = 110;
+
+/// Why a discovered file is listed but not analyzed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FileClass {
+    Binary,
+    Generated,
+    Vendored,
+}
+
+impl FileClass {
+    /// Tag shown next to the path in the directory tree
+    pub fn tag(&self) -> &'static str {
+        match self {
+            FileClass::Binary => "binary",
+            FileClass::Generated => "generated",
+            FileClass::Vendored => "vendored",
+        }
+    }
+}
+
+impl fmt::Display for FileClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}]", self.tag())
+    }
+}
+
+/// One `.gitattributes` line setting (or unsetting) a class
+struct AttributeRule {
+    matcher: GlobMatcher,
+    class: FileClass,
+    set: bool,
+}
+
+/// Classifier for the files of one repository
+pub struct Classifier {
+    vendored_dirs: Vec<String>,
+    attributes: Vec<AttributeRule>,
+}
+
+impl Classifier {
+    pub fn new(vendored_dirs: &[String]) -> Self {
+        Self { vendored_dirs: vendored_dirs.to_vec(), attributes: Vec::new() }
+    }
+
+    /// Load the rules of a `.gitattributes` file found at `relative`; later files win over earlier ones
+    pub fn add_attributes(&mut self, relative: &Path, path: &Path) -> Result<()> {
+        let Ok(content) = std::fs::read_to_string(path) else {
+            return Ok(());
+        };
+        let base = relative.parent().unwrap_or(Path::new(""));
+
+        for line in content.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let Some(pattern) = fields.next() else { continue };
+
+            let rules: Vec<(FileClass, bool)> = fields.filter_map(parse_attribute).collect();
+            if rules.is_empty() {
+                continue;
+            }
+
+            // Patterns without a slash match at any depth, like in .gitignore
+            let pattern = pattern.trim_end_matches('/');
+            let anchored = match pattern.strip_prefix('/') {
+                Some(rooted) => base.join(rooted),
+                None if pattern.contains('/') => base.join(pattern),
+                None => base.join("**").join(pattern),
+            };
+            let glob = Glob::new(&anchored.to_string_lossy())
+                .map_err(|e| Error::ConfigError(format!("Invalid pattern '{}' in {}: {}", pattern, path.display(), e)))?;
+
+            for (class, set) in rules {
+                self.attributes.push(AttributeRule { matcher: glob.compile_matcher(), class, set });
+            }
+        }
+        Ok(())
+    }
+
+    /// Class of a discovered path, `None` when its content should be analyzed
+    pub fn classify(&self, relative: &Path, path: &Path, is_dir: bool) -> Option<FileClass> {
+        let attribute = |class: FileClass| {
+            let mut set = None;
+            for ancestor in relative.ancestors().filter(|p| !p.as_os_str().is_empty()) {
+                // The last matching line wins; a directory rule covers its contents
+                if let Some(rule) = self.attributes.iter().rev().find(|r| r.class == class && r.matcher.is_match(ancestor)) {
+                    set = Some(rule.set);
+                    break;
+                }
+            }
+            set
+        };
+
+        match attribute(FileClass::Vendored) {
+            Some(true) => return Some(FileClass::Vendored),
+            Some(false) => {}
+            None if self.in_vendored_dir(relative, is_dir) => return Some(FileClass::Vendored),
+            None => {}
+        }
+        if is_dir {
+            return None;
+        }
+
+        let generated = attribute(FileClass::Generated);
+        if generated == Some(true) {
+            return Some(FileClass::Generated);
+        }
+        let binary = attribute(FileClass::Binary);
+        if binary == Some(true) {
+            return Some(FileClass::Binary);
+        }
+
+        let name = relative.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+        if generated.is_none()
+            && (GENERATED_FILES.contains(&name.as_ref()) || GENERATED_SUFFIXES.iter().any(|s| name.ends_with(s)))
+        {
+            return Some(FileClass::Generated);
+        }
+
+        let head = read_head(path)?;
+        if binary.is_none() && head.contains(&0) {
+            return Some(FileClass::Binary);
+        }
+        if generated.is_none() && (has_generated_marker(&head) || is_minified(relative, &head)) {
+            return Some(FileClass::Generated);
+        }
+        None
+    }
+
+    fn in_vendored_dir(&self, relative: &Path, is_dir: bool) -> bool {
+        let dirs = if is_dir { relative } else { relative.parent().unwrap_or(Path::new("")) };
+        dirs.components()
+            .any(|c| self.vendored_dirs.iter().any(|dir| c.as_os_str() == dir.as_str()))
+    }
+}
+
+/// Class set by a `.gitattributes` attribute: `name`, `name=true`, `-name` or `name=false`
+fn parse_attribute(attribute: &str) -> Option<(FileClass, bool)> {
+    let (name, set) = match attribute.strip_prefix('-') {
+        Some(name) => (name, false),
+        None => match attribute.split_once('=') {
+            Some((name, value)) => (name, !matches!(value, "false" | "0")),
+            None => (attribute, true),
+        },
+    };
+    match name {
+        "linguist-vendored" => Some((FileClass::Vendored, set)),
+        "linguist-generated" => Some((FileClass::Generated, set)),
+        "binary" => Some((FileClass::Binary, set)),
+        _ => None,
+    }
+}
+
+fn read_head(path: &Path) -> Option<Vec<u8>> {
+    let file = std::fs::File::open(path).ok()?;
+    let mut head = Vec::with_capacity(SNIFF_LEN);
+    file.take(SNIFF_LEN as u64).read_to_end(&mut head).ok()?;
+    Some(head)
+}
+
+fn has_generated_marker(head: &[u8]) -> bool {
+    String::from_utf8_lossy(head)
+        .lines()
+        .take(HEADER_LINES)
+        .any(|line| GENERATED_MARKER.is_match(line))
+}
+
+fn is_minified(relative: &Path, head: &[u8]) -> bool {
+    let extension = relative.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+    if !MINIFIABLE_EXTENSIONS.contains(&extension.as_str()) {
+        return false;
+    }
+    let lines = head.split(|b| *b == b'\n').count();
+    head.len() / lines > MINIFIED_LINE_LENGTH
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generated(header: &str) -> bool {
+        has_generated_marker(header.as_bytes())
+    }
+
+    #[test]
+    fn detects_generated_headers() {
+        for header in [
+            "// Code generated by protoc-gen-go. DO NOT EDIT.\npackage api",
+            "# Code generated by sqlc. DO NOT EDIT.\n",
+            "/**\n * @generated SignedSource<<abc>>\n */",
+            "// <auto-generated>\n//     This code was generated by a tool.\n// </auto-generated>",
+            "# This file was automatically generated by SWIG",
+            "/* This file was auto-generated from schema.json */",
+            "// This file is autogenerated, run `make gen` to update",
+            "-- This file was generated by pg_dump",
+        ] {
+            assert!(generated(header), "not generated: {}", header);
+        }
+    }
+
+    #[test]
+    fn keeps_hand_written_headers() {
+        for header in [
+            "// Do not edit the constants below without updating the docs\nconst LIMIT: u32 = 3;",
+            "# Please DO NOT EDIT production settings by hand",
+            "/* Handles auto-generated IDs for new users */",
+            "// Parses the code generated by the template engine",
+            "fn main() {}",
+        ] {
+            assert!(!generated(header), "generated: {}", header);
+        }
+        // Markers are only looked for in the header
+        let late = format!("{}// Code generated by mockgen. DO NOT EDIT.\n", "fn f() {}\n".repeat(HEADER_LINES));
+        assert!(!generated(&late));
+    }
+
+    #[test]
+    fn classifies_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = [
+            ("Cargo.lock", "# lockfile".to_string()),
+            ("api/user.pb.go", "package api".to_string()),
+            ("src/schema.rs", "// @generated by diesel\npub mod users {}".to_string()),
+            ("src/main.rs", "// Do not edit by hand without a review\nfn main() {}".to_string()),
+            ("assets/logo.png", "\u{89}PNG\r\n\u{1a}\n\0\0\0".to_string()),
+            ("static/app.js", format!("var a={};", "x".repeat(500))),
+            ("vendor/lib/util.go", "package util".to_string()),
+        ];
+        for (path, content) in &files {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+
+        let classifier = Classifier::new(&["vendor".to_string()]);
+        let class = |relative: &str| classifier.classify(Path::new(relative), &dir.path().join(relative), false);
+        assert_eq!(class("Cargo.lock"), Some(FileClass::Generated));
+        assert_eq!(class("api/user.pb.go"), Some(FileClass::Generated));
+        assert_eq!(class("src/schema.rs"), Some(FileClass::Generated));
+        assert_eq!(class("src/main.rs"), None);
+        assert_eq!(class("assets/logo.png"), Some(FileClass::Binary));
+        assert_eq!(class("static/app.js"), Some(FileClass::Generated));
+        assert_eq!(class("vendor/lib/util.go"), Some(FileClass::Vendored));
+        assert_eq!(classifier.classify(Path::new("vendor"), &dir.path().join("vendor"), true), Some(FileClass::Vendored));
+    }
+
+    #[test]
+    fn gitattributes_take_precedence() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = [
+            (".gitattributes", "src/schema.rs -linguist-generated\nvendor/** -linguist-vendored\ndocs/api.md linguist-generated\n"),
+            ("src/schema.rs", "// @generated by diesel\npub mod users {}"),
+            ("vendor/lib/util.go", "package util"),
+            ("docs/api.md", "# API"),
+        ];
+        for (path, content) in files {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+
+        let mut classifier = Classifier::new(&["vendor".to_string()]);
+        classifier.add_attributes(Path::new(".gitattributes"), &dir.path().join(".gitattributes")).unwrap();
+        let class = |relative: &str| classifier.classify(Path::new(relative), &dir.path().join(relative), false);
+        assert_eq!(class("src/schema.rs"), None);
+        assert_eq!(class("vendor/lib/util.go"), None);
+        assert_eq!(class("docs/api.md"), Some(FileClass::Generated));
+    }
+}
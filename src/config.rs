@@ -1,15 +1,26 @@
 use crate::error::{Error, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path,PathBuf};
 
+/// Current schema version of config files, see `config_migrate`
+pub const CONFIG_VERSION: u32 = 2;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
+    /// Schema version of the file
+    pub version: u32,
+
     /// LLM provider configuration
     pub llm: LlmConfig,
 
     /// Analysis configuration
     pub analysis: AnalysisConfig,
 
+    /// Redaction of secrets and personal data before content is sent to the LLM
+    pub redaction: RedactionConfig,
+
     /// Git configuration
     pub git: GitConfig,
 
@@ -18,16 +29,29 @@ pub struct Config {
 
     /// Path of the output readme.ai.md
     pub output_path: String,
+
+    /// Named presets overriding any of the sections above, selected with `--profile`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, toml::Table>,
+
+    /// Profile the configuration was loaded with
+    #[serde(skip)]
+    pub profile: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct LlmConfig {
     /// The LLM provider to use
     pub provider: LlmProvider,
 
-    /// API key for the provider
+    /// API key for the provider, never written to config files
+    #[serde(skip_serializing_if = "String::is_empty")]
     pub api_key: String,
 
+    /// Command printing the API key, run when no key is given (e.g. `pass show anthropic`)
+    pub api_key_command: Option<String>,
+
     /// Model name to use
     pub model: String,
 
@@ -37,22 +61,183 @@ pub struct LlmConfig {
     /// Maximum retries
     pub max_retries: Option<u32>,
 
+    /// Delay between retries, in seconds
+    pub retry_delay_seconds: Option<u32>,
+
+    /// Times an invalid Mermaid diagram is sent back to the agent before being dropped
+    pub mermaid_repair_attempts: Option<u32>,
+
     /// Maximum tokens per request
     pub max_tokens: Option<u32>,
 
+    /// Maximum tokens of context sent with each request
+    pub max_context_tokens: Option<usize>,
+
     /// Temperature for generation
     pub temperature: Option<f32>,
+
+    /// API version (Azure OpenAI only)
+    pub api_version: Option<String>,
+
+    /// Extra HTTP headers sent with every request (OpenAI-compatible only)
+    pub headers: Option<BTreeMap<String, String>>,
+
+    /// Whether the server accepts system prompts (OpenAI-compatible only, defaults to true)
+    pub supports_system_prompt: Option<bool>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl std::fmt::Debug for LlmConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Keep the key out of logs and debug output
+        let api_key = if self.api_key.is_empty() { "" } else { "********" };
+        f.debug_struct("LlmConfig")
+            .field("provider", &self.provider)
+            .field("api_key", &api_key)
+            .field("api_key_command", &self.api_key_command)
+            .field("model", &self.model)
+            .field("base_url", &self.base_url)
+            .field("max_retries", &self.max_retries)
+            .field("retry_delay_seconds", &self.retry_delay_seconds)
+            .field("mermaid_repair_attempts", &self.mermaid_repair_attempts)
+            .field("max_tokens", &self.max_tokens)
+            .field("max_context_tokens", &self.max_context_tokens)
+            .field("temperature", &self.temperature)
+            .field("api_version", &self.api_version)
+            .field("headers", &self.headers)
+            .field("supports_system_prompt", &self.supports_system_prompt)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum LlmProvider {
     OpenAI,
     Anthropic,
     OpenRouter,
     Ollama,
+    Gemini,
+    Mistral,
+    Groq,
+    DeepSeek,
+    XAI,
+    AzureOpenAI,
+    OpenAICompatible,
+}
+
+/// Context budget per request when none is configured
+pub const DEFAULT_MAX_CONTEXT_TOKENS: usize = 8000;
+
+/// Default Azure OpenAI API version when none is configured
+pub const AZURE_DEFAULT_API_VERSION: &str = "2024-10-21";
+
+impl LlmProvider {
+    /// Every provider, in the order they are offered to the user
+    pub const ALL: [LlmProvider; 11] = [
+        LlmProvider::Anthropic,
+        LlmProvider::OpenAI,
+        LlmProvider::OpenRouter,
+        LlmProvider::Ollama,
+        LlmProvider::Gemini,
+        LlmProvider::Mistral,
+        LlmProvider::Groq,
+        LlmProvider::DeepSeek,
+        LlmProvider::XAI,
+        LlmProvider::AzureOpenAI,
+        LlmProvider::OpenAICompatible,
+    ];
+
+    /// Name accepted by `--provider`
+    pub fn id(&self) -> &'static str {
+        match self {
+            LlmProvider::Anthropic => "anthropic",
+            LlmProvider::OpenAI => "openai",
+            LlmProvider::OpenRouter => "openrouter",
+            LlmProvider::Ollama => "ollama",
+            LlmProvider::Gemini => "gemini",
+            LlmProvider::Mistral => "mistral",
+            LlmProvider::Groq => "groq",
+            LlmProvider::DeepSeek => "deepseek",
+            LlmProvider::XAI => "xai",
+            LlmProvider::AzureOpenAI => "azure",
+            LlmProvider::OpenAICompatible => "openai-compatible",
+        }
+    }
+
+    /// Model used when neither the CLI nor the config specifies one
+    pub fn default_model(&self) -> &'static str {
+        match self {
+            LlmProvider::Anthropic => "claude-3-5-sonnet-20241022",
+            LlmProvider::OpenAI => "gpt-4-turbo-preview",
+            LlmProvider::OpenRouter => "anthropic/claude-3-sonnet",
+            // Picked among the locally installed models at startup
+            LlmProvider::Ollama => crate::providers::AUTO_MODEL,
+            LlmProvider::Gemini => "gemini-2.0-flash",
+            LlmProvider::Mistral => "mistral-large-latest",
+            LlmProvider::Groq => "llama-3.3-70b-versatile",
+            LlmProvider::DeepSeek => "deepseek-chat",
+            LlmProvider::XAI => "grok-3",
+            // Azure expects a deployment name, which is usually the model name
+            LlmProvider::AzureOpenAI => "gpt-4o",
+            LlmProvider::OpenAICompatible => crate::providers::AUTO_MODEL,
+        }
+    }
+
+    /// Environment variable holding the API key for this provider, if any
+    pub fn api_key_env_var(&self) -> Option<&'static str> {
+        match self {
+            LlmProvider::Anthropic => Some("ANTHROPIC_API_KEY"),
+            LlmProvider::OpenAI => Some("OPENAI_API_KEY"),
+            LlmProvider::OpenRouter => Some("OPENROUTER_API_KEY"),
+            LlmProvider::Ollama => None,
+            LlmProvider::Gemini => Some("GEMINI_API_KEY"),
+            LlmProvider::Mistral => Some("MISTRAL_API_KEY"),
+            LlmProvider::Groq => Some("GROQ_API_KEY"),
+            LlmProvider::DeepSeek => Some("DEEPSEEK_API_KEY"),
+            LlmProvider::XAI => Some("XAI_API_KEY"),
+            LlmProvider::AzureOpenAI => Some("AZURE_OPENAI_API_KEY"),
+            LlmProvider::OpenAICompatible => Some("OPENAI_COMPATIBLE_API_KEY"),
+        }
+    }
+
+    /// Environment variable holding the base URL for this provider, if any
+    pub fn base_url_env_var(&self) -> Option<&'static str> {
+        match self {
+            LlmProvider::Ollama => Some("OLLAMA_HOST"),
+            LlmProvider::AzureOpenAI => Some("AZURE_OPENAI_ENDPOINT"),
+            LlmProvider::OpenAICompatible => Some("OPENAI_COMPATIBLE_BASE_URL"),
+            _ => None,
+        }
+    }
+
+    /// Whether requests can be made without an API key
+    pub fn requires_api_key(&self) -> bool {
+        !matches!(self, LlmProvider::Ollama | LlmProvider::OpenAICompatible)
+    }
+}
+
+impl std::str::FromStr for LlmProvider {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "anthropic" => Ok(LlmProvider::Anthropic),
+            "openai" => Ok(LlmProvider::OpenAI),
+            "openrouter" => Ok(LlmProvider::OpenRouter),
+            "ollama" => Ok(LlmProvider::Ollama),
+            "gemini" | "google" => Ok(LlmProvider::Gemini),
+            "mistral" => Ok(LlmProvider::Mistral),
+            "groq" => Ok(LlmProvider::Groq),
+            "deepseek" => Ok(LlmProvider::DeepSeek),
+            "xai" | "grok" => Ok(LlmProvider::XAI),
+            "azure" | "azure-openai" | "azureopenai" => Ok(LlmProvider::AzureOpenAI),
+            "openai-compatible" | "openaicompatible" | "compatible" => Ok(LlmProvider::OpenAICompatible),
+            _ => Err(Error::InvalidProvider(s.to_string())),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AnalysisConfig {
     /// Maximum file size to analyze (in bytes)
     pub max_file_size: usize,
@@ -66,11 +251,77 @@ pub struct AnalysisConfig {
     /// Files to exclude from analysisp
     pub exclude_files: Vec<String>,
 
+    /// Glob patterns of files to analyze, in addition to `include_extensions`;
+    /// `*` does not cross directories, use `**/` to match at any depth
+    pub include: Vec<String>,
+
+    /// Glob patterns of paths to skip, relative to the repository (`**/` for any depth)
+    pub exclude: Vec<String>,
+
+    /// Per-path rules taking precedence over everything else, ignore files included
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub overrides: BTreeMap<String, PathRule>,
+
+    /// Honour .gitignore, .ignore and the global git excludes (`.raidmeignore` always applies)
+    pub respect_gitignore: bool,
+
+    /// List binary, generated and vendored files in the tree without analyzing their content
+    pub classify: bool,
+
+    /// Directory names holding vendored code, at any depth
+    pub vendored_dirs: Vec<String>,
+
     /// Maximum depth to traverse directories
     pub max_depth: Option<usize>,
+
+    /// Entries listed per directory in the project tree before collapsing the rest, 0 for no limit
+    pub tree_max_entries: usize,
+
+    /// Ask the LLM for a one-line description of each entry of the project tree
+    pub tree_descriptions: bool,
+
+    /// Analyze each package of a monorepo on its own, with its own knowledge file
+    pub sub_projects: bool,
+
+    /// Extract public types and functions of the source files with tree-sitter
+    pub symbols: bool,
+
+    /// Resolve the imports of the source files into a module dependency graph
+    pub imports: bool,
+
+    /// Extract the entities of SQL, ORM and API schemas into the Data Models section
+    pub data_models: bool,
+
+    /// List the endpoints and RPCs of OpenAPI, protobuf and GraphQL definitions
+    pub apis: bool,
+}
+
+/// What an `analysis.overrides` pattern does to the paths it matches
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PathRule {
+    Include,
+    Exclude,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RedactionConfig {
+    /// Replace detected secrets with placeholders before sending content
+    pub enabled: bool,
+
+    /// Glob patterns of files never sent, matched against the path and the file name
+    pub deny: Vec<String>,
+
+    /// Also redact email addresses
+    pub redact_emails: bool,
+
+    /// Shannon entropy (bits per character) above which long tokens are redacted, 0 to disable
+    pub entropy_threshold: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GitConfig {
     /// Enable automatic git commits after each step
     pub auto_commit: bool,
@@ -83,6 +334,7 @@ pub struct GitConfig {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct TemplateConfig {
     /// Custom template directory
     pub template_dir: Option<PathBuf>,
@@ -101,64 +353,235 @@ pub enum OutputFormat {
 impl Default for Config {
     fn default() -> Self {
         Self {
-            llm: LlmConfig {
-                provider: LlmProvider::Anthropic,
-                api_key: String::new(),
-                model: "claude-3-5-sonnet-20241022".to_string(),
-                base_url: None,
-                max_retries: Some(3),
-                max_tokens: Some(4096),
-                temperature: Some(0.7),
-                output_path: "README.ai.md",
-            },
-            analysis: AnalysisConfig {
-                max_file_size: 1024 * 1024, // 1MB
-                include_extensions: vec![
-                    "rs".to_string(),
-                    "py".to_string(),
-                    "js".to_string(),
-                    "ts".to_string(),
-                    "java".to_string(),
-                    "cpp".to_string(),
-                    "c".to_string(),
-                    "h".to_string(),
-                    "go".to_string(),
-                    "md".to_string(),
-                    "txt".to_string(),
-                    "toml".to_string(),
-                    "yaml".to_string(),
-                    "yml".to_string(),
-                    "json".to_string(),
-                ],
-                exclude_dirs: vec![
-                    "target".to_string(),
-                    "node_modules".to_string(),
-                    ".git".to_string(),
-                    "build".to_string(),
-                    "dist".to_string(),
-                    ".next".to_string(),
-                    "__pycache__".to_string(),
-                ],
-                exclude_files: vec![
-                    "package-lock.json".to_string(),
-                    "Cargo.lock".to_string(),
-                    "yarn.lock".to_string(),
-                ],
-                max_depth: Some(10),
-            },
-            git: GitConfig {
-                auto_commit: true,
-                author_name: "Raidme AI".to_string(),
-                author_email: "raidme@ai.local".to_string(),
-            },
-            template: TemplateConfig {
-                template_dir: None,
-                output_format: OutputFormat::Markdown,
-            },
+            version: CONFIG_VERSION,
+            llm: LlmConfig::default(),
+            analysis: AnalysisConfig::default(),
+            redaction: RedactionConfig::default(),
+            git: GitConfig::default(),
+            template: TemplateConfig::default(),
+            output_path: "README.ai.md".to_string(),
+            profiles: BTreeMap::new(),
+            profile: None,
+        }
+    }
+}
+
+impl Default for LlmConfig {
+    fn default() -> Self {
+        Self {
+            provider: LlmProvider::Anthropic,
+            api_key: String::new(),
+            api_key_command: None,
+            model: LlmProvider::Anthropic.default_model().to_string(),
+            base_url: None,
+            max_retries: Some(3),
+            retry_delay_seconds: Some(5),
+            mermaid_repair_attempts: Some(2),
+            max_tokens: Some(4096),
+            max_context_tokens: Some(DEFAULT_MAX_CONTEXT_TOKENS),
+            temperature: Some(0.7),
+            api_version: None,
+            headers: None,
+            supports_system_prompt: None,
+        }
+    }
+}
+
+impl Default for AnalysisConfig {
+    fn default() -> Self {
+        Self {
+            max_file_size: 1024 * 1024, // 1MB
+            include_extensions: vec![
+                "rs".to_string(),
+                "py".to_string(),
+                "js".to_string(),
+                "ts".to_string(),
+                "tsx".to_string(),
+                "jsx".to_string(),
+                "java".to_string(),
+                "cpp".to_string(),
+                "cc".to_string(),
+                "c".to_string(),
+                "h".to_string(),
+                "hpp".to_string(),
+                "go".to_string(),
+                "md".to_string(),
+                "txt".to_string(),
+                "toml".to_string(),
+                "yaml".to_string(),
+                "yml".to_string(),
+                "json".to_string(),
+            ],
+            exclude_dirs: vec![
+                "target".to_string(),
+                "node_modules".to_string(),
+                ".git".to_string(),
+                "build".to_string(),
+                "dist".to_string(),
+                ".next".to_string(),
+                "__pycache__".to_string(),
+            ],
+            exclude_files: vec![
+                "package-lock.json".to_string(),
+                "Cargo.lock".to_string(),
+                "yarn.lock".to_string(),
+            ],
+            include: Vec::new(),
+            exclude: Vec::new(),
+            overrides: BTreeMap::new(),
+            respect_gitignore: true,
+            classify: true,
+            vendored_dirs: vec![
+                "vendor".to_string(),
+                "third_party".to_string(),
+                "third-party".to_string(),
+                "bower_components".to_string(),
+                "Pods".to_string(),
+                "Carthage".to_string(),
+                "Godeps".to_string(),
+            ],
+            max_depth: Some(10),
+            tree_max_entries: 25,
+            tree_descriptions: true,
+            sub_projects: true,
+            symbols: true,
+            imports: true,
+            data_models: true,
+            apis: true,
         }
     }
 }
 
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            deny: vec![
+                ".env".to_string(),
+                ".env.*".to_string(),
+                "*.pem".to_string(),
+                "*.key".to_string(),
+                "*.p12".to_string(),
+                "*.pfx".to_string(),
+                "id_rsa*".to_string(),
+                "id_ed25519*".to_string(),
+                ".npmrc".to_string(),
+                ".pypirc".to_string(),
+                ".netrc".to_string(),
+                "credentials.json".to_string(),
+            ],
+            redact_emails: true,
+            entropy_threshold: 4.3,
+        }
+    }
+}
+
+impl Default for GitConfig {
+    fn default() -> Self {
+        Self {
+            auto_commit: true,
+            author_name: "Raidme AI".to_string(),
+            author_email: "raidme@ai.local".to_string(),
+        }
+    }
+}
+
+impl Default for TemplateConfig {
+    fn default() -> Self {
+        Self {
+            template_dir: None,
+            output_format: OutputFormat::Markdown,
+        }
+    }
+}
+
+/// Comments written above the keys of a config file, by `(table, key)`; an empty key annotates the table
+const FIELD_COMMENTS: &[(&str, &str, &str)] = &[
+    ("", "version", "Schema version, upgraded automatically by raidme"),
+    ("", "output_path", "Knowledge file to generate, relative to the repository"),
+    ("llm", "", "LLM backend used for every analysis step. The API key is never stored here:\n# pass --api-key or export RAIDME_API_KEY / the provider's *_API_KEY variable"),
+    ("llm", "provider", "Anthropic, OpenAI, OpenRouter, Ollama, Gemini, Mistral, Groq, DeepSeek, XAI, AzureOpenAI or OpenAICompatible"),
+    ("llm", "api_key_command", "Command printing the API key, e.g. \"pass show anthropic\""),
+    ("llm", "model", "Model name (\"auto\" picks an installed model for Ollama and OpenAI-compatible servers)"),
+    ("llm", "base_url", "Custom API endpoint"),
+    ("llm", "max_retries", "Attempts per request before a step fails"),
+    ("llm", "retry_delay_seconds", "Delay between attempts, in seconds"),
+    ("llm", "mermaid_repair_attempts", "Times an invalid Mermaid diagram is sent back to the agent for repair before being dropped"),
+    ("llm", "max_tokens", "Maximum tokens generated per request"),
+    ("llm", "max_context_tokens", "Maximum tokens of repository content sent with each request"),
+    ("llm", "temperature", "Sampling temperature"),
+    ("llm", "api_version", "Azure OpenAI API version"),
+    ("llm.headers", "", "Extra HTTP headers sent to OpenAI-compatible servers"),
+    ("llm", "supports_system_prompt", "Set to false for OpenAI-compatible servers rejecting system prompts"),
+    ("analysis", "", "Which files of the repository are analyzed"),
+    ("analysis", "max_file_size", "Files larger than this (in bytes) are skipped"),
+    ("analysis", "include_extensions", "Extensions of the files to analyze"),
+    ("analysis", "exclude_dirs", "Directory names skipped at any depth"),
+    ("analysis", "exclude_files", "File names skipped at any depth"),
+    ("analysis", "include", "Glob patterns of extra files to analyze, e.g. \"**/Dockerfile\" (* stays within a directory)"),
+    ("analysis", "exclude", "Glob patterns of paths to skip, e.g. \"docs/generated/**\""),
+    ("analysis.overrides", "", "\"<glob>\" = \"include\" | \"exclude\", applied over every other rule"),
+    ("analysis", "respect_gitignore", "Skip what .gitignore and .ignore exclude (.raidmeignore always applies)"),
+    ("analysis", "classify", "Skip the content of binary, generated and vendored files, tagging them in the tree"),
+    ("analysis", "vendored_dirs", "Directory names holding vendored code (.gitattributes linguist-vendored also applies)"),
+    ("analysis", "max_depth", "Maximum directory depth to traverse"),
+    ("analysis", "tree_max_entries", "Entries shown per directory in the project tree, the rest collapsed into \"… N more files\" (0: no limit)"),
+    ("analysis", "tree_descriptions", "Annotate the project tree with one-line descriptions written by the LLM (one request per 60 entries)"),
+    ("analysis", "sub_projects", "Give each package of a monorepo (npm workspace, Go module, crate…) its own knowledge file"),
+    ("analysis", "symbols", "Outline public types and functions (Rust, Go, Python, TypeScript, Java, C/C++) for the LLM"),
+    ("analysis", "imports", "Graph the imports between modules (Rust, Go, Python, JavaScript/TypeScript) and detect cycles"),
+    ("analysis", "data_models", "Draw an ER diagram from SQL migrations, Prisma, Diesel, SQLAlchemy, GORM and OpenAPI/JSON Schema definitions"),
+    ("analysis", "apis", "List the endpoints, RPCs and GraphQL operations of OpenAPI/Swagger, .proto and GraphQL SDL files"),
+    ("redaction", "", "Secrets and personal data removed before any content reaches the LLM"),
+    ("redaction", "enabled", "Replace API keys, tokens, private keys and passwords with placeholders"),
+    ("redaction", "deny", "Glob patterns of files never sent, e.g. \".env\" or \"secrets/**\""),
+    ("redaction", "redact_emails", "Also replace email addresses"),
+    ("redaction", "entropy_threshold", "Bits per character above which long random-looking tokens are redacted, 0 to disable"),
+    ("git", "", "Git integration"),
+    ("git", "auto_commit", "Commit the knowledge file after each step"),
+    ("git", "author_name", "Author of those commits"),
+    ("git", "author_email", "Author email of those commits"),
+    ("template", "", "Output rendering"),
+    ("template", "template_dir", "Directory with custom output templates"),
+    ("template", "output_format", "Markdown, Json or Yaml"),
+];
+
+/// Prefix each table and key of a serialized config with its comment
+pub(crate) fn annotate(content: &str) -> String {
+    let comment = |table: &str, key: &str| {
+        FIELD_COMMENTS
+            .iter()
+            .find(|(t, k, _)| *t == table && *k == key)
+            .map(|(_, _, comment)| *comment)
+    };
+
+    let mut annotated = String::from(
+        "# raidme configuration, see `raidme init`\n\
+         # Every key can be overridden with RAIDME_<TABLE>_<KEY>, e.g. RAIDME_LLM_MODEL\n\n",
+    );
+    let mut table = String::new();
+
+    for line in content.lines() {
+        let header = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']'));
+        let note = if let Some(header) = header {
+            table = header.to_string();
+            comment(&table, "")
+        } else if line.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            line.split_once(" = ").and_then(|(key, _)| comment(&table, key))
+        } else {
+            None
+        };
+
+        if let Some(note) = note {
+            annotated.push_str(&format!("# {}\n", note));
+        }
+        annotated.push_str(line);
+        annotated.push('\n');
+    }
+
+    annotated
+}
+
 impl Config {
     /// Load configuration from a TOML file
     pub fn from_file<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
@@ -167,10 +590,10 @@ impl Config {
         Ok(config)
     }
 
-    /// Save configuration to a TOML file
+    /// Save configuration to a TOML file, with a comment describing each setting
     pub fn to_file<P: AsRef<std::path::Path>>(&self, path: P) -> Result<()> {
         let content = toml::to_string_pretty(self)?;
-        std::fs::write(path, content)?;
+        std::fs::write(path, annotate(&content))?;
         Ok(())
     }
 
@@ -181,30 +604,10 @@ impl Config {
         Ok(config_dir.join("raidme").join("config.toml"))
     }
 
-    /// Load configuration from the default location or repo path. Fails if not found.
+    /// Load the configuration of a repository, merging defaults, the global
+    /// config, `.raidme.toml` and `RAIDME_*` variables (see `config_layers`)
     pub fn load<P: AsRef<Path>>(repo_path: P) -> Result<Self> {
-        let repo_path = repo_path.as_ref();
-        let repo_config_path = repo_path.join(".raidme.toml");
-
-        if repo_config_path.exists() {
-            return Self::from_file(&repo_config_path);
-        }
-
-        let config_path = Self::default_config_path()?;
-        if config_path.exists() {
-            return Self::from_file(&config_path);
-        }
-
-        Err(Error::ConfigError("No configuration file found".to_string()))
-    }
-
-    /// Load configuration if it exists, otherwise fall back to default
-    pub fn load_or_default<P: AsRef<Path>>(repo_path: P) -> Result<Self> {
-        match Self::load(repo_path) {
-            Ok(config) => Ok(config),
-            Err(e) if e.is_file_not_found() => Ok(Self::default()),
-            Err(e) => Err(e),
-        }
+        Ok(crate::config_layers::load(repo_path.as_ref())?.config)
     }
 
     /// Store the configuration to the repo-local `.raidme.toml` file,
@@ -214,13 +617,17 @@ impl Config {
         let mut clone = self.clone();
         clone.llm.api_key.clear(); // clear API key before saving
 
-        let config_path = repo_path.join(".raidme.toml");
+        let config_path = repo_path.join(crate::config_layers::REPO_CONFIG_FILE);
         clone.to_file(config_path)
     }
 
     /// Validate the configuration
     pub fn validate(&self) -> Result<()> {
-        if self.llm.api_key.is_empty() {
+        // The command and the credential store are only consulted when the client is built
+        let has_key_source = !self.llm.api_key.is_empty()
+            || self.llm.api_key_command.is_some()
+            || crate::credentials::CredentialStore::exists();
+        if !has_key_source && self.llm.provider.requires_api_key() {
             return Err(Error::ConfigError("API key is required".to_string()));
         }
 
@@ -228,6 +635,12 @@ impl Config {
             return Err(Error::ConfigError("Model name is required".to_string()));
         }
 
+        if self.llm.provider == LlmProvider::AzureOpenAI && self.llm.base_url.is_none() {
+            return Err(Error::ConfigError(
+                "Azure OpenAI requires an endpoint (base_url or AZURE_OPENAI_ENDPOINT)".to_string(),
+            ));
+        }
+
         if self.analysis.max_file_size == 0 {
             return Err(Error::ConfigError("Max file size must be greater than 0".to_string()));
         }
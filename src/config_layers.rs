@@ -0,0 +1,419 @@
+//! Layered configuration: defaults, global file, repository file, selected
+//! profile, `RAIDME_*` environment variables and CLI flags, merged field by
+//! field in that order.
+//!
+//! Layers are partial TOML tables. Each merged value remembers the layer it
+//! came from, so `raidme config show --origin` can explain the result.
+
+use crate::config::{Config, LlmProvider};
+use crate::config_migrate;
+use crate::error::{Error, Result};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use toml::{map::Map, Value};
+
+/// Name of the repository configuration file
+pub const REPO_CONFIG_FILE: &str = ".raidme.toml";
+
+/// Prefix of the environment variables overriding config keys
+const ENV_PREFIX: &str = "RAIDME_";
+
+/// Variable selecting a profile when `--profile` is not given
+pub const PROFILE_ENV_VAR: &str = "RAIDME_PROFILE";
+
+/// Keys absent from the defaults that can still be set from the environment, with a value of their type
+const OPTIONAL_KEYS: &[(&str, Value)] = &[
+    ("llm.api_key", Value::String(String::new())),
+    ("llm.api_key_command", Value::String(String::new())),
+    ("llm.base_url", Value::String(String::new())),
+    ("llm.api_version", Value::String(String::new())),
+    ("llm.supports_system_prompt", Value::Boolean(true)),
+    ("template.template_dir", Value::String(String::new())),
+];
+
+/// Where a configuration value comes from, lowest precedence first
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Origin {
+    Default,
+    Global(PathBuf),
+    Repo(PathBuf),
+    Profile(String),
+    Env(String),
+    Cli,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::Default => write!(f, "default"),
+            Origin::Global(path) | Origin::Repo(path) => write!(f, "{}", path.display()),
+            Origin::Profile(name) => write!(f, "profile {}", name),
+            Origin::Env(var) => write!(f, "env {}", var),
+            Origin::Cli => write!(f, "command line"),
+        }
+    }
+}
+
+/// Merged configuration with the origin of every value
+#[derive(Debug, Clone)]
+pub struct LayeredConfig {
+    pub config: Config,
+    merged: Map<String, Value>,
+    origins: BTreeMap<String, Origin>,
+}
+
+impl LayeredConfig {
+    /// Origin of a dotted key such as `llm.model`
+    pub fn origin(&self, key: &str) -> Option<&Origin> {
+        self.origins.get(key)
+    }
+
+    /// Every set value as `(dotted key, value, origin)`, in key order
+    pub fn entries(&self) -> Vec<(String, Value, Origin)> {
+        let mut leaves = Vec::new();
+        flatten("", &self.merged, &mut leaves);
+        leaves
+            .into_iter()
+            .map(|(key, value)| {
+                let origin = self.origins.get(&key).cloned().unwrap_or(Origin::Default);
+                (key, value, origin)
+            })
+            .collect()
+    }
+}
+
+/// Overrides collected from the command line, as dotted keys
+#[derive(Debug, Clone, Default)]
+pub struct CliOverrides {
+    values: Vec<(String, Value)>,
+    profile: Option<String>,
+}
+
+impl CliOverrides {
+    /// Select a `[profiles.<name>]` section
+    pub fn profile(&mut self, name: &str) -> &mut Self {
+        self.profile = Some(name.to_string());
+        self
+    }
+
+    pub fn set(&mut self, key: &str, value: impl Into<Value>) -> &mut Self {
+        self.values.push((key.to_string(), value.into()));
+        self
+    }
+
+    pub fn set_provider(&mut self, provider: &LlmProvider) -> Result<&mut Self> {
+        Ok(self.set("llm.provider", Value::try_from(provider)?))
+    }
+
+    fn into_table(self) -> Result<Map<String, Value>> {
+        let mut table = Map::new();
+        for (key, value) in self.values {
+            insert(&mut table, &key, value)?;
+        }
+        Ok(table)
+    }
+}
+
+/// Load the configuration of a repository without command line overrides
+pub fn load(repo_path: &Path) -> Result<LayeredConfig> {
+    load_with(repo_path, CliOverrides::default())
+}
+
+/// Load the configuration of a repository, migrating outdated files in memory
+/// only; for read-only commands such as `config validate`
+pub fn load_read_only(repo_path: &Path) -> Result<LayeredConfig> {
+    merge_layers(repo_path, CliOverrides::default(), false)
+}
+
+/// Merge every configuration layer of a repository, `cli` last
+pub fn load_with(repo_path: &Path, cli: CliOverrides) -> Result<LayeredConfig> {
+    merge_layers(repo_path, cli, true)
+}
+
+/// Merge the layers, outdated config files being rewritten when `upgrade_files`
+fn merge_layers(repo_path: &Path, cli: CliOverrides, upgrade_files: bool) -> Result<LayeredConfig> {
+    let defaults = match Value::try_from(Config::default())? {
+        Value::Table(table) => table,
+        _ => unreachable!("Config serializes to a table"),
+    };
+
+    let mut merged = defaults;
+    let mut origins = BTreeMap::new();
+    let mut leaves = Vec::new();
+    flatten("", &merged, &mut leaves);
+    for (key, _) in leaves {
+        origins.insert(key, Origin::Default);
+    }
+
+    let mut apply = |origin: Origin, mut layer: Map<String, Value>, merged: &mut Map<String, Value>| -> Result<()> {
+        reset_model_on_provider_change(merged, &mut layer)?;
+        merge(merged, layer, "", &origin, &mut origins);
+        Ok(())
+    };
+
+    if let Ok(global_path) = Config::default_config_path() {
+        if let Some(table) = read_layer(&global_path, upgrade_files)? {
+            apply(Origin::Global(global_path), table, &mut merged)?;
+        }
+    }
+    let repo_config_path = repo_path.join(REPO_CONFIG_FILE);
+    if let Some(table) = read_layer(&repo_config_path, upgrade_files)? {
+        apply(Origin::Repo(repo_config_path), table, &mut merged)?;
+    }
+
+    // Profiles may be defined in either file, so they are picked once both are merged
+    let profile = cli.profile.clone()
+        .or_else(|| std::env::var(PROFILE_ENV_VAR).ok())
+        .filter(|name| !name.is_empty());
+    if let Some(name) = &profile {
+        let table = profile_table(&merged, name)?;
+        apply(Origin::Profile(name.clone()), table, &mut merged)?;
+    }
+
+    for (var, key, value) in env_layer()? {
+        let mut table = Map::new();
+        insert(&mut table, &key, value)?;
+        apply(Origin::Env(var), table, &mut merged)?;
+    }
+    apply(Origin::Cli, cli.into_table()?, &mut merged)?;
+
+    apply_provider_env(&mut merged, &mut origins)?;
+
+    let mut config: Config = Value::Table(merged.clone()).try_into()?;
+    config.profile = profile;
+    Ok(LayeredConfig { config, merged, origins })
+}
+
+/// Overrides of a named profile
+fn profile_table(merged: &Map<String, Value>, name: &str) -> Result<Map<String, Value>> {
+    let profiles = merged.get("profiles").and_then(Value::as_table);
+    match profiles.and_then(|profiles| profiles.get(name)) {
+        Some(Value::Table(table)) => {
+            if let Some(key) = table.keys().find(|key| *key == "profiles" || *key == "version") {
+                return Err(Error::ConfigError(format!("Profile '{}' cannot set '{}'", name, key)));
+            }
+            Ok(table.clone())
+        }
+        Some(_) => Err(Error::ConfigError(format!("profiles.{} must be a table", name))),
+        None => {
+            let available: Vec<_> = profiles.map(|p| p.keys().cloned().collect()).unwrap_or_default();
+            Err(Error::ConfigError(format!(
+                "Unknown profile '{}' (available: {})",
+                name,
+                if available.is_empty() { "none".to_string() } else { available.join(", ") }
+            )))
+        }
+    }
+}
+
+/// Parse a config file, upgrading it to the current schema (on disk too when
+/// `upgrade_file`); `None` when it does not exist
+fn read_layer(path: &Path, upgrade_file: bool) -> Result<Option<Map<String, Value>>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(path)?;
+    let mut table: Map<String, Value> = toml::from_str(&content)?;
+    match upgrade_file {
+        true => config_migrate::upgrade_file(path, &mut table)?,
+        false => {
+            config_migrate::migrate(&mut table)?;
+        }
+    }
+    Ok(Some(table))
+}
+
+/// Every dotted key of the schema, with a value of its type (`llm.headers.*` and `analysis.overrides.*` excepted)
+pub fn known_keys() -> Vec<(String, Value)> {
+    let mut keys = Vec::new();
+    if let Ok(Value::Table(defaults)) = Value::try_from(Config::default()) {
+        flatten("", &defaults, &mut keys);
+    }
+    keys.extend(OPTIONAL_KEYS.iter().map(|(key, kind)| (key.to_string(), kind.clone())));
+    keys
+}
+
+/// Environment variable overriding a dotted key: `llm.max_tokens` is `RAIDME_LLM_MAX_TOKENS`
+pub fn env_var_name(key: &str) -> String {
+    format!("{}{}", ENV_PREFIX, key.replace('.', "_").to_uppercase())
+}
+
+/// `(variable, key, value)` for every `RAIDME_*` variable naming a known key
+fn env_layer() -> Result<Vec<(String, String, Value)>> {
+    let mut values = Vec::new();
+    for (key, kind) in known_keys() {
+        // The schema version is not a setting
+        if key == "version" {
+            continue;
+        }
+        // RAIDME_API_KEY predates the layering and stays the short form of RAIDME_LLM_API_KEY
+        let vars = match key.as_str() {
+            "llm.api_key" => vec![format!("{}API_KEY", ENV_PREFIX), env_var_name(&key)],
+            _ => vec![env_var_name(&key)],
+        };
+        for var in vars {
+            if let Ok(raw) = std::env::var(&var) {
+                let value = parse_env_value(&key, &raw, &kind)
+                    .map_err(|e| Error::ConfigError(format!("{}: {}", var, e)))?;
+                values.push((var, key.clone(), value));
+            }
+        }
+    }
+
+    Ok(values)
+}
+
+/// Parse a variable as the type of the value it overrides; lists are comma separated
+fn parse_env_value(key: &str, raw: &str, kind: &Value) -> std::result::Result<Value, String> {
+    let invalid = |expected: &str| format!("expected {}, got '{}'", expected, raw);
+
+    Ok(match kind {
+        _ if key == "llm.provider" => {
+            let provider = raw.parse::<LlmProvider>().map_err(|e| e.to_string())?;
+            Value::try_from(provider).map_err(|e| e.to_string())?
+        }
+        Value::Integer(_) => Value::Integer(raw.trim().parse().map_err(|_| invalid("an integer"))?),
+        Value::Float(_) => Value::Float(raw.trim().parse().map_err(|_| invalid("a number"))?),
+        Value::Boolean(_) => Value::Boolean(raw.trim().parse().map_err(|_| invalid("true or false"))?),
+        Value::Array(_) => Value::Array(
+            raw.split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| Value::String(item.to_string()))
+                .collect(),
+        ),
+        _ => Value::String(raw.to_string()),
+    })
+}
+
+/// A stored model belongs to its provider: switching provider alone restores the provider default
+fn reset_model_on_provider_change(merged: &Map<String, Value>, layer: &mut Map<String, Value>) -> Result<()> {
+    let Some(Value::Table(llm)) = layer.get_mut("llm") else {
+        return Ok(());
+    };
+    let Some(provider) = llm.get("provider") else {
+        return Ok(());
+    };
+    let current = merged.get("llm").and_then(|llm| llm.get("provider"));
+    if llm.contains_key("model") || current == Some(provider) {
+        return Ok(());
+    }
+
+    let provider: LlmProvider = provider.clone().try_into()?;
+    llm.insert("model".to_string(), Value::String(provider.default_model().to_string()));
+    Ok(())
+}
+
+/// Fill the key, base URL and API version from the provider's own variables when no layer above the files set them
+fn apply_provider_env(merged: &mut Map<String, Value>, origins: &mut BTreeMap<String, Origin>) -> Result<()> {
+    let llm = match merged.get_mut("llm") {
+        Some(Value::Table(llm)) => llm,
+        _ => return Ok(()),
+    };
+    let provider: LlmProvider = llm
+        .get("provider")
+        .cloned()
+        .ok_or_else(|| Error::ConfigError("llm.provider is missing".to_string()))?
+        .try_into()?;
+
+    let from_file = |origins: &BTreeMap<String, Origin>, key: &str| {
+        origins.get(key).is_none_or(|origin| *origin < Origin::Env(String::new()))
+    };
+
+    let mut fallbacks = vec![
+        ("api_key", provider.api_key_env_var()),
+        ("base_url", provider.base_url_env_var()),
+    ];
+    if provider == LlmProvider::AzureOpenAI {
+        fallbacks.push(("api_version", Some("AZURE_OPENAI_API_VERSION")));
+    }
+
+    for (field, var) in fallbacks {
+        let key = format!("llm.{}", field);
+        let Some(var) = var else { continue };
+        let Ok(value) = std::env::var(var) else { continue };
+        // Only the key overrides the files, the other settings just fill the gaps
+        let unset = !llm.contains_key(field);
+        if (field == "api_key" && from_file(origins, &key)) || unset {
+            llm.insert(field.to_string(), Value::String(value));
+            origins.insert(key, Origin::Env(var.to_string()));
+        }
+    }
+
+    let model_empty = llm.get("model").and_then(Value::as_str).is_none_or(str::is_empty);
+    if model_empty {
+        llm.insert("model".to_string(), Value::String(provider.default_model().to_string()));
+        origins.insert("llm.model".to_string(), Origin::Default);
+    }
+
+    Ok(())
+}
+
+/// Deep-merge `layer` into `base`, recording `origin` for every value it sets
+fn merge(
+    base: &mut Map<String, Value>,
+    layer: Map<String, Value>,
+    prefix: &str,
+    origin: &Origin,
+    origins: &mut BTreeMap<String, Origin>,
+) {
+    for (name, value) in layer {
+        let key = join(prefix, &name);
+        match (base.get_mut(&name), value) {
+            (Some(Value::Table(base_table)), Value::Table(table)) => {
+                merge(base_table, table, &key, origin, origins);
+            }
+            (_, value) => {
+                let mut leaves = Vec::new();
+                match &value {
+                    Value::Table(table) => flatten(&key, table, &mut leaves),
+                    _ => leaves.push((key.clone(), value.clone())),
+                }
+                origins.retain(|k, _| k != &key && !k.starts_with(&format!("{}.", key)));
+                for (leaf, _) in leaves {
+                    origins.insert(leaf, origin.clone());
+                }
+                base.insert(name, value);
+            }
+        }
+    }
+}
+
+/// Set a dotted key in a table, creating the intermediate tables
+fn insert(table: &mut Map<String, Value>, key: &str, value: Value) -> Result<()> {
+    match key.split_once('.') {
+        None => {
+            table.insert(key.to_string(), value);
+            Ok(())
+        }
+        Some((head, rest)) => {
+            let entry = table
+                .entry(head.to_string())
+                .or_insert_with(|| Value::Table(Map::new()));
+            match entry {
+                Value::Table(inner) => insert(inner, rest, value),
+                _ => Err(Error::ConfigError(format!("'{}' is not a table", head))),
+            }
+        }
+    }
+}
+
+/// Leaf values of a table as dotted keys; arrays are leaves
+fn flatten(prefix: &str, table: &Map<String, Value>, leaves: &mut Vec<(String, Value)>) {
+    for (name, value) in table {
+        let key = join(prefix, name);
+        match value {
+            Value::Table(inner) => flatten(&key, inner, leaves),
+            _ => leaves.push((key, value.clone())),
+        }
+    }
+}
+
+fn join(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", prefix, name)
+    }
+}
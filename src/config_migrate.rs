@@ -0,0 +1,307 @@
+//! Schema versions of config files, their upgrades and validation.
+//!
+//! Files without a `version` key predate versioning and are version 1. Each
+//! migration upgrades a raw TOML table by one version; files are upgraded in
+//! place when loaded, after a copy of the original is kept next to them. Only
+//! the keys a migration changed are rewritten, so comments and order survive.
+
+use crate::config::{Config, CONFIG_VERSION};
+use crate::config_layers::known_keys;
+use crate::error::{Error, Result};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use toml::{map::Map, Value};
+use toml_edit::{DocumentMut, Item, TableLike};
+
+/// Upgrade of a table from version `n` to `n + 1`, at index `n - 1`
+type Migration = fn(&mut Map<String, Value>);
+
+const MIGRATIONS: &[Migration] = &[v1_to_v2];
+
+/// Keys still understood through a migration, with what replaced them
+const DEPRECATED_KEYS: &[(&str, &str)] = &[
+    ("llm.output_path", "moved to the top-level `output_path`"),
+    ("llm.api_key", "keys do not belong in config files, use `api_key_command` or `raidme credentials set`"),
+];
+
+/// Tables whose keys are chosen by the user
+const FREE_FORM_TABLES: &[&str] = &["llm.headers.", "analysis.overrides."];
+
+/// Version 1 kept `output_path` under `[llm]` and always wrote an empty `api_key`
+fn v1_to_v2(table: &mut Map<String, Value>) {
+    let Some(Value::Table(llm)) = table.get_mut("llm") else {
+        return;
+    };
+
+    let output_path = llm.remove("output_path");
+    if llm.get("api_key").and_then(Value::as_str) == Some("") {
+        llm.remove("api_key");
+    }
+
+    if let Some(output_path) = output_path {
+        table.entry("output_path").or_insert(output_path);
+    }
+}
+
+/// Schema version of a raw config table
+pub fn file_version(table: &Map<String, Value>) -> Result<u32> {
+    match table.get("version") {
+        None => Ok(1),
+        Some(Value::Integer(version)) if *version >= 1 => u32::try_from(*version)
+            .map_err(|_| Error::ConfigError(format!("Invalid config version: {}", version))),
+        Some(version) => Err(Error::ConfigError(format!("Invalid config version: {}", version))),
+    }
+}
+
+/// Upgrade a table to the current version, returning the version it had
+pub fn migrate(table: &mut Map<String, Value>) -> Result<u32> {
+    let version = file_version(table)?;
+    if version > CONFIG_VERSION {
+        return Err(Error::ConfigError(format!(
+            "Config version {} is newer than the supported version {}, upgrade raidme",
+            version, CONFIG_VERSION
+        )));
+    }
+
+    for migration in &MIGRATIONS[version as usize - 1..] {
+        migration(table);
+    }
+    table.insert("version".to_string(), Value::Integer(CONFIG_VERSION as i64));
+
+    Ok(version)
+}
+
+/// Upgrade a loaded config file in place, keeping the original as `<file>.v<N>.bak`
+pub fn upgrade_file(path: &Path, table: &mut Map<String, Value>) -> Result<()> {
+    let original = table.clone();
+    let version = migrate(table)?;
+    if version == CONFIG_VERSION {
+        return Ok(());
+    }
+
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(".v{}.bak", version));
+    let backup = PathBuf::from(backup);
+
+    let mut document: DocumentMut = std::fs::read_to_string(path)?
+        .parse()
+        .map_err(|e| Error::ConfigError(format!("Invalid config file {}: {}", path.display(), e)))?;
+    apply_changes(document.as_table_mut(), &original, table)?;
+
+    std::fs::copy(path, &backup)?;
+    std::fs::write(path, document.to_string())?;
+    println!(
+        "⬆️ Upgraded {} from config version {} to {} (backup: {})",
+        path.display(),
+        version,
+        CONFIG_VERSION,
+        backup.display()
+    );
+
+    Ok(())
+}
+
+/// Rewrite in `document` the keys that differ between the `original` and `migrated` tables
+fn apply_changes(document: &mut dyn TableLike, original: &Map<String, Value>, migrated: &Map<String, Value>) -> Result<()> {
+    for key in original.keys().filter(|key| !migrated.contains_key(*key)) {
+        document.remove(key);
+    }
+    for (key, value) in migrated {
+        match (original.get(key), value) {
+            (Some(before), _) if before == value => {}
+            (Some(Value::Table(before)), Value::Table(after)) => {
+                if let Some(inner) = document.get_mut(key).and_then(Item::as_table_like_mut) {
+                    apply_changes(inner, before, after)?;
+                }
+            }
+            _ => {
+                let value: toml_edit::Value = value
+                    .to_string()
+                    .parse()
+                    .map_err(|e| Error::ConfigError(format!("Invalid value for '{}': {}", key, e)))?;
+                document.insert(key, Item::Value(value));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// Problem found in a config file
+#[derive(Debug, Clone)]
+pub struct Issue {
+    pub severity: Severity,
+    pub key: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let icon = match self.severity {
+            Severity::Warning => "⚠️",
+            Severity::Error => "❌",
+        };
+        match &self.key {
+            Some(key) => write!(f, "{} {}: {}", icon, key, self.message),
+            None => write!(f, "{} {}", icon, self.message),
+        }
+    }
+}
+
+impl Issue {
+    fn warning(key: &str, message: impl Into<String>) -> Self {
+        Self { severity: Severity::Warning, key: Some(key.to_string()), message: message.into() }
+    }
+
+    fn error(key: Option<&str>, message: impl Into<String>) -> Self {
+        Self { severity: Severity::Error, key: key.map(str::to_string), message: message.into() }
+    }
+}
+
+/// Check a config file without modifying it: version, deprecated and unknown keys, value types
+pub fn validate_file(path: &Path) -> Result<Vec<Issue>> {
+    let content = std::fs::read_to_string(path)?;
+    let mut table: Map<String, Value> = match toml::from_str(&content) {
+        Ok(table) => table,
+        Err(e) => return Ok(vec![Issue::error(None, e.to_string())]),
+    };
+
+    let mut issues = Vec::new();
+    let mut keys = Vec::new();
+    flatten("", &table, &mut keys);
+
+    // The empty key written by version 1 is simply dropped by the upgrade
+    let empty_api_key = table.get("llm").and_then(|llm| llm.get("api_key")).and_then(Value::as_str) == Some("");
+    for (key, replacement) in DEPRECATED_KEYS {
+        if *key == "llm.api_key" && empty_api_key {
+            continue;
+        }
+        if keys.iter().any(|k| k == key) {
+            issues.push(Issue::warning(key, format!("deprecated, {}", replacement)));
+        }
+    }
+
+    match migrate(&mut table) {
+        Ok(version) if version < CONFIG_VERSION => issues.push(Issue::warning(
+            "version",
+            format!("version {} will be upgraded to {} on the next run", version, CONFIG_VERSION),
+        )),
+        Ok(_) => {}
+        Err(Error::ConfigError(message)) => {
+            issues.push(Issue::error(Some("version"), message));
+            return Ok(issues);
+        }
+        Err(e) => return Err(e),
+    }
+
+    let known: Vec<String> = known_keys().into_iter().map(|(key, _)| key).collect();
+    let mut keys = Vec::new();
+    flatten("", &table, &mut keys);
+    for key in keys {
+        // Profiles hold the same keys as the file, under `profiles.<name>.`
+        let schema_key = match key.strip_prefix("profiles.") {
+            Some(rest) => match rest.split_once('.') {
+                Some((_, schema_key)) => schema_key,
+                None => {
+                    issues.push(Issue::error(Some(&key), "a profile must be a table"));
+                    continue;
+                }
+            },
+            None => key.as_str(),
+        };
+        let deprecated = DEPRECATED_KEYS.iter().any(|(k, _)| *k == schema_key);
+        let free_form = FREE_FORM_TABLES.iter().any(|table| schema_key.starts_with(table));
+        if !deprecated && !free_form && !known.iter().any(|k| k == schema_key) {
+            issues.push(Issue::warning(&key, "unknown key, ignored"));
+        }
+    }
+
+    if let Err(e) = Value::Table(table).try_into::<Config>() {
+        issues.push(Issue::error(None, e.to_string()));
+    }
+
+    Ok(issues)
+}
+
+/// Dotted keys of a table; arrays are leaves
+fn flatten(prefix: &str, table: &Map<String, Value>, keys: &mut Vec<String>) {
+    for (name, value) in table {
+        let key = if prefix.is_empty() { name.clone() } else { format!("{}.{}", prefix, name) };
+        match value {
+            Value::Table(inner) => flatten(&key, inner, keys),
+            _ => keys.push(key),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V1: &str = r#"# Team settings
+[llm]
+# Local gateway
+provider = "OpenAICompatible"
+api_key = ""
+output_path = "docs/README.md"
+temperature = 0.2
+
+[analysis]
+max_file_size = 4096 # bytes
+"#;
+
+    #[test]
+    fn migrates_v1_tables() {
+        let mut table: Map<String, Value> = toml::from_str(V1).unwrap();
+        assert_eq!(migrate(&mut table).unwrap(), 1);
+
+        let llm = table["llm"].as_table().unwrap();
+        assert!(!llm.contains_key("api_key"));
+        assert!(!llm.contains_key("output_path"));
+        assert_eq!(table["output_path"].as_str(), Some("docs/README.md"));
+        assert_eq!(table["version"].as_integer(), Some(CONFIG_VERSION as i64));
+
+        // A key a user actually set is kept, and reported by validation instead
+        let mut table: Map<String, Value> = toml::from_str("[llm]\napi_key = \"sk-123\"\n").unwrap();
+        migrate(&mut table).unwrap();
+        assert_eq!(table["llm"]["api_key"].as_str(), Some("sk-123"));
+    }
+
+    #[test]
+    fn upgrades_files_keeping_comments() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, V1).unwrap();
+
+        let mut table: Map<String, Value> = toml::from_str(V1).unwrap();
+        upgrade_file(&path, &mut table).unwrap();
+
+        assert_eq!(std::fs::read_to_string(dir.path().join("config.toml.v1.bak")).unwrap(), V1);
+        let content = std::fs::read_to_string(&path).unwrap();
+        for kept in ["# Team settings", "# Local gateway", "max_file_size = 4096 # bytes", "temperature = 0.2"] {
+            assert!(content.contains(kept), "{} missing from:\n{}", kept, content);
+        }
+        assert!(!content.contains("api_key"));
+        let upgraded: Map<String, Value> = toml::from_str(&content).unwrap();
+        assert_eq!(upgraded, table);
+
+        // Already current: left untouched
+        upgrade_file(&path, &mut table).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), content);
+    }
+
+    #[test]
+    fn rejects_invalid_versions() {
+        for version in ["0", "-1", "4294967296", "\"2\""] {
+            let table: Map<String, Value> = toml::from_str(&format!("version = {}", version)).unwrap();
+            assert!(file_version(&table).is_err(), "version {} accepted", version);
+        }
+        let mut table: Map<String, Value> = toml::from_str(&format!("version = {}", CONFIG_VERSION + 1)).unwrap();
+        assert!(migrate(&mut table).is_err());
+    }
+}
@@ -0,0 +1,235 @@
+//! API key resolution and the encrypted credential store.
+//!
+//! A key given on the command line, in `RAIDME_API_KEY` or in the provider's
+//! variable always wins. Otherwise `llm.api_key_command` is run (e.g.
+//! `pass show anthropic` or `secret-tool lookup service anthropic`), and
+//! finally the credential store is opened. Keys are only ever held in memory:
+//! they are never written to `.raidme.toml` nor to the knowledge database.
+//!
+//! The store is a single file in the raidme config directory, encrypted with
+//! ChaCha20-Poly1305 under a key derived with Argon2id from a passphrase read
+//! from `RAIDME_CREDENTIALS_PASSPHRASE` or prompted for.
+
+use crate::config::{LlmConfig, LlmProvider};
+use crate::error::{Error, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Variable holding the passphrase of the credential store
+pub const PASSPHRASE_ENV_VAR: &str = "RAIDME_CREDENTIALS_PASSPHRASE";
+
+/// File name of the store, in the raidme config directory
+const STORE_FILE: &str = "credentials.enc";
+
+const STORE_VERSION: u32 = 1;
+const SALT_LEN: usize = 16;
+
+/// Fill `llm.api_key` from the key command or the credential store when no key was given
+pub fn resolve_api_key(llm: &mut LlmConfig) -> Result<()> {
+    if !llm.api_key.is_empty() {
+        return Ok(());
+    }
+
+    if let Some(command) = llm.api_key_command.as_deref().filter(|c| !c.trim().is_empty()) {
+        llm.api_key = run_key_command(command)?;
+        return Ok(());
+    }
+
+    if llm.provider.requires_api_key() && CredentialStore::exists() {
+        let store = CredentialStore::open(&passphrase(false)?)?;
+        if let Some(key) = store.get(&llm.provider) {
+            llm.api_key = key.to_string();
+        }
+    }
+
+    if llm.api_key.is_empty() && llm.provider.requires_api_key() {
+        let env_hint = llm.provider.api_key_env_var()
+            .map(|var| format!("export {}, ", var))
+            .unwrap_or_default();
+        return Err(Error::ConfigError(format!(
+            "API key is required: pass --api-key, {}set llm.api_key_command or run `raidme credentials set {}`",
+            env_hint,
+            llm.provider.id()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Run a credential helper and return the first line of its output
+fn run_key_command(command: &str) -> Result<String> {
+    let output = if cfg!(windows) {
+        Command::new("cmd").args(["/C", command]).output()?
+    } else {
+        Command::new("sh").args(["-c", command]).output()?
+    };
+
+    if !output.status.success() {
+        return Err(Error::Auth(format!(
+            "api_key_command `{}` failed ({}): {}",
+            command,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let key = stdout.lines().next().unwrap_or_default().trim();
+    if key.is_empty() {
+        return Err(Error::Auth(format!("api_key_command `{}` printed no key", command)));
+    }
+    Ok(key.to_string())
+}
+
+/// Passphrase of the store, from the environment or the terminal; `confirm` asks twice
+pub fn passphrase(confirm: bool) -> Result<String> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV_VAR) {
+        if passphrase.is_empty() {
+            return Err(Error::Auth(format!("Empty passphrase in {}", PASSPHRASE_ENV_VAR)));
+        }
+        return Ok(passphrase);
+    }
+
+    let passphrase = rpassword::prompt_password("🔐 Credential store passphrase: ")?;
+    if confirm && rpassword::prompt_password("🔐 Confirm passphrase: ")? != passphrase {
+        return Err(Error::Auth("Passphrases do not match".to_string()));
+    }
+    if passphrase.is_empty() {
+        return Err(Error::Auth("Empty passphrase".to_string()));
+    }
+    Ok(passphrase)
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoreFile {
+    version: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// API keys by provider, decrypted in memory
+pub struct CredentialStore {
+    passphrase: String,
+    keys: BTreeMap<String, String>,
+}
+
+impl CredentialStore {
+    /// Location of the store file
+    pub fn path() -> Result<PathBuf> {
+        let config_dir = dirs::config_dir()
+            .ok_or_else(|| Error::ConfigError("Unable to determine config directory".to_string()))?;
+        Ok(config_dir.join("raidme").join(STORE_FILE))
+    }
+
+    pub fn exists() -> bool {
+        Self::path().is_ok_and(|path| path.is_file())
+    }
+
+    /// Empty store, encrypted with `passphrase` once saved
+    pub fn create(passphrase: &str) -> Self {
+        Self { passphrase: passphrase.to_string(), keys: BTreeMap::new() }
+    }
+
+    /// Decrypt the store file
+    pub fn open(passphrase: &str) -> Result<Self> {
+        let path = Self::path()?;
+        let file: StoreFile = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+        if file.version != STORE_VERSION {
+            return Err(Error::ConfigError(format!(
+                "Unsupported credential store version {} in {}",
+                file.version,
+                path.display()
+            )));
+        }
+
+        let decode = |field: &str| {
+            BASE64.decode(field).map_err(|e| Error::ConfigError(format!("Corrupted credential store: {}", e)))
+        };
+        let salt = decode(&file.salt)?;
+        let nonce = decode(&file.nonce)?;
+        let ciphertext = decode(&file.ciphertext)?;
+        if nonce.len() != 12 {
+            return Err(Error::ConfigError("Corrupted credential store: bad nonce".to_string()));
+        }
+
+        let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, &salt)?);
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+            .map_err(|_| Error::Auth(format!("Wrong passphrase for {}", path.display())))?;
+
+        Ok(Self {
+            passphrase: passphrase.to_string(),
+            keys: serde_json::from_slice(&plaintext)?,
+        })
+    }
+
+    /// Encrypt the store with a fresh salt and nonce, readable by the current user only
+    pub fn save(&self) -> Result<()> {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+        let cipher = ChaCha20Poly1305::new(&derive_key(&self.passphrase, &salt)?);
+        let ciphertext = cipher
+            .encrypt(&nonce, serde_json::to_vec(&self.keys)?.as_ref())
+            .map_err(|e| Error::Generic(format!("Encryption failed: {}", e)))?;
+
+        let file = StoreFile {
+            version: STORE_VERSION,
+            salt: BASE64.encode(salt),
+            nonce: BASE64.encode(nonce),
+            ciphertext: BASE64.encode(ciphertext),
+        };
+
+        let path = Self::path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        // Created private, so the ciphertext is never readable by others, even briefly
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        options.open(&path)?.write_all(serde_json::to_string_pretty(&file)?.as_bytes())?;
+
+        Ok(())
+    }
+
+    pub fn get(&self, provider: &LlmProvider) -> Option<&str> {
+        self.keys.get(provider.id()).map(String::as_str)
+    }
+
+    pub fn set(&mut self, provider: &LlmProvider, api_key: &str) {
+        self.keys.insert(provider.id().to_string(), api_key.to_string());
+    }
+
+    /// Remove a key, returning whether there was one
+    pub fn remove(&mut self, provider: &LlmProvider) -> bool {
+        self.keys.remove(provider.id()).is_some()
+    }
+
+    /// Providers with a stored key
+    pub fn providers(&self) -> impl Iterator<Item = &str> {
+        self.keys.keys().map(String::as_str)
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key> {
+    let mut key = Key::default();
+    argon2::Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| Error::Generic(format!("Key derivation failed: {}", e)))?;
+    Ok(key)
+}
@@ -0,0 +1,300 @@
+//! Mermaid diagrams generated from the extracted structure.
+//!
+//! The module graph, the crate graph and the directory layering are drawn by
+//! raidme from the resolved imports and the manifests, so every node is an
+//! existing path. The architecture agent only annotates them: it returns a
+//! one-line role per path, shown in the node labels, and the diagrams are
+//! inserted into the knowledge file at [`DIAGRAMS_MARKER`].
+
+use crate::cargo_workspace::CargoWorkspace;
+use crate::imports::{Import, ModuleGraph};
+use crate::manifests::DependencyKind;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Line of the final consolidation replaced by the diagrams
+pub const DIAGRAMS_MARKER: &str = "<!-- raidme:diagrams -->";
+
+/// Heading of the section holding the diagrams
+pub const DIAGRAMS_HEADING: &str = "## Diagrams";
+
+/// Knowledge category of the roles given to diagram nodes, keyed by path
+pub const ROLE_CATEGORY: &str = "role";
+
+/// Info string of the fenced block the architecture agent writes its annotations in
+const ANNOTATIONS_FENCE: &str = "```annotations";
+
+/// Longest role kept in a node label
+const MAX_ROLE_LEN: usize = 60;
+
+/// Directory depth of the layering diagram
+const LAYER_DEPTH: usize = 2;
+
+/// One-line roles of diagram nodes, by path
+pub type Annotations = BTreeMap<String, String>;
+
+#[derive(Debug, Clone)]
+struct DiagramNode {
+    /// Path relative to the repository root, `.` for the root
+    path: String,
+    /// Shown above the path, e.g. a crate name
+    name: Option<String>,
+    /// Top-level directory the node is drawn in
+    group: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct DiagramEdge {
+    from: String,
+    to: String,
+    dotted: bool,
+    /// Part of a cycle
+    highlighted: bool,
+}
+
+/// A diagram drawn from extracted facts
+#[derive(Debug, Clone)]
+pub struct Diagram {
+    pub title: String,
+    direction: &'static str,
+    nodes: Vec<DiagramNode>,
+    edges: Vec<DiagramEdge>,
+}
+
+impl Diagram {
+    /// Import dependencies between modules, the edges of cycles highlighted
+    pub fn modules(graph: &ModuleGraph) -> Option<Self> {
+        if graph.is_empty() {
+            return None;
+        }
+
+        let cycle_of: HashMap<String, usize> = graph
+            .cycles()
+            .into_iter()
+            .enumerate()
+            .flat_map(|(i, cycle)| cycle.into_iter().map(move |module| (module, i)))
+            .collect();
+        let modules: BTreeSet<&str> = graph.edges.keys().flat_map(|(from, to)| [from.as_str(), to.as_str()]).collect();
+
+        Some(Self {
+            title: "Module dependencies".to_string(),
+            direction: "LR",
+            nodes: modules.into_iter().map(|path| DiagramNode { path: path.to_string(), name: None, group: None }).collect(),
+            edges: graph
+                .edges
+                .keys()
+                .map(|(from, to)| DiagramEdge {
+                    from: from.clone(),
+                    to: to.clone(),
+                    dotted: false,
+                    highlighted: cycle_of.get(from).is_some_and(|cycle| cycle_of.get(to) == Some(cycle)),
+                })
+                .collect(),
+        })
+    }
+
+    /// Dependencies between the crates of a Cargo workspace; dev and build dependencies are dotted
+    pub fn crates(workspace: &CargoWorkspace) -> Self {
+        let dir_of: HashMap<&str, String> = workspace
+            .crates
+            .iter()
+            .map(|krate| (krate.name.as_str(), if krate.dir.is_empty() { ".".to_string() } else { krate.dir.clone() }))
+            .collect();
+
+        let nodes = workspace
+            .crates
+            .iter()
+            .map(|krate| {
+                let kinds: Vec<&str> = krate.targets.iter().map(|t| t.kind.as_str()).filter(|k| *k == "lib" || *k == "bin").collect();
+                let name = if kinds.is_empty() {
+                    krate.name.clone()
+                } else {
+                    format!("{} ({})", krate.name, kinds.join(", "))
+                };
+                DiagramNode { path: dir_of[krate.name.as_str()].clone(), name: Some(name), group: None }
+            })
+            .collect();
+
+        let edges = workspace
+            .edges
+            .iter()
+            .filter_map(|edge| {
+                Some(DiagramEdge {
+                    from: dir_of.get(edge.from.as_str())?.clone(),
+                    to: dir_of.get(edge.to.as_str())?.clone(),
+                    dotted: matches!(edge.kind, DependencyKind::Dev | DependencyKind::Build),
+                    highlighted: false,
+                })
+            })
+            .collect();
+
+        Self { title: format!("Crate dependencies ({})", workspace.root), direction: "TD", nodes, edges }
+    }
+
+    /// Dependencies between directories, grouped by top-level directory; `None`
+    /// unless some top-level directories depend on each other
+    pub fn layers(imports: &[Import]) -> Option<Self> {
+        let graph = ModuleGraph::with_depth(imports, Some(LAYER_DEPTH));
+        let top = |path: &str| path.split('/').next().unwrap_or(path).to_string();
+        if !graph.edges.keys().any(|(from, to)| top(from) != top(to)) {
+            return None;
+        }
+
+        let mut diagram = Self::modules(&graph)?;
+        diagram.title = "Directory layers".to_string();
+        diagram.direction = "TB";
+        for node in &mut diagram.nodes {
+            if node.path != "." {
+                node.group = Some(top(&node.path));
+            }
+        }
+        Some(diagram)
+    }
+
+    /// Paths of the nodes, the only ones that can be annotated
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.nodes.iter().map(|node| node.path.as_str())
+    }
+
+    /// Mermaid source, nodes labelled with their path and role
+    pub fn render(&self, annotations: &Annotations) -> String {
+        let ids = node_ids(self.nodes.iter().map(|node| node.path.as_str()));
+        let mut graph = format!("```mermaid\ngraph {}\n", self.direction);
+
+        let mut groups: BTreeMap<Option<&str>, Vec<&DiagramNode>> = BTreeMap::new();
+        for node in &self.nodes {
+            groups.entry(node.group.as_deref()).or_default().push(node);
+        }
+        for (group, nodes) in groups {
+            let indent = if group.is_some() { "        " } else { "    " };
+            if let Some(group) = group {
+                graph.push_str(&format!("    subgraph g_{}[\"{}\"]\n", sanitize(group), escape(group)));
+            }
+            for node in nodes {
+                let mut label = Vec::new();
+                label.extend(node.name.as_deref().map(escape));
+                label.push(escape(&node.path));
+                label.extend(annotations.get(&node.path).map(|role| format!("<i>{}</i>", escape(role))));
+                graph.push_str(&format!("{}{}[\"{}\"]\n", indent, ids[node.path.as_str()], label.join("<br/>")));
+            }
+            if group.is_some() {
+                graph.push_str("    end\n");
+            }
+        }
+
+        let mut highlighted = Vec::new();
+        for (i, edge) in self.edges.iter().enumerate() {
+            let arrow = if edge.dotted { "-.->" } else { "-->" };
+            graph.push_str(&format!("    {} {} {}\n", ids[edge.from.as_str()], arrow, ids[edge.to.as_str()]));
+            if edge.highlighted {
+                highlighted.push(i.to_string());
+            }
+        }
+        if !highlighted.is_empty() {
+            graph.push_str(&format!("    linkStyle {} stroke:#d62728,stroke-width:2px\n", highlighted.join(",")));
+        }
+
+        graph.push_str("```\n");
+        graph
+    }
+}
+
+/// Diagrams section of the knowledge file, empty without diagrams
+pub fn section(diagrams: &[Diagram], annotations: &Annotations) -> String {
+    if diagrams.is_empty() {
+        return String::new();
+    }
+    let mut section = format!("{}\n", DIAGRAMS_HEADING);
+    for diagram in diagrams {
+        section.push_str(&format!("\n### {}\n\n{}", diagram.title, diagram.render(annotations)));
+    }
+    section
+}
+
+/// Insert the diagrams section into a generated document: at the marker, else at the end
+pub fn embed(document: &str, section: &str) -> String {
+    embed_section(document, DIAGRAMS_MARKER, DIAGRAMS_HEADING, section)
+}
+
+/// Insert a section generated by raidme at its marker, else at the end of the document
+pub fn embed_section(document: &str, marker: &str, heading: &str, section: &str) -> String {
+    if let Some((before, after)) = document.split_once(marker) {
+        // The heading is kept once when the model wrote it above the marker
+        let section = match before.trim_end().ends_with(heading) {
+            true => format!("\n{}", section.strip_prefix(heading).unwrap_or(section).trim()),
+            false => section.trim_end().to_string(),
+        };
+        return format!("{}{}{}", before, section, after);
+    }
+    if section.is_empty() {
+        return document.to_string();
+    }
+    format!("{}\n\n{}", document.trim_end(), section)
+}
+
+/// Split an agent answer into its prose and the roles it gave to known paths;
+/// Mermaid blocks are dropped, diagrams being drawn by raidme
+pub fn parse_annotations<'a>(answer: &str, paths: impl IntoIterator<Item = &'a str>) -> (String, Annotations) {
+    let paths: BTreeSet<&str> = paths.into_iter().collect();
+    let mut prose = String::new();
+    let mut annotations = Annotations::new();
+    let mut fence: Option<&str> = None;
+
+    for line in answer.lines() {
+        let trimmed = line.trim();
+        match fence {
+            None if trimmed.starts_with(ANNOTATIONS_FENCE) || trimmed.starts_with("```mermaid") => {
+                fence = Some(if trimmed.starts_with(ANNOTATIONS_FENCE) { "annotations" } else { "mermaid" });
+            }
+            None => {
+                prose.push_str(line);
+                prose.push('\n');
+            }
+            Some(_) if trimmed.starts_with("```") => fence = None,
+            Some("annotations") => {
+                let Some((path, role)) = trimmed.trim_start_matches(['-', '*', ' ']).split_once(':') else { continue };
+                let path = path.trim().trim_matches('`').trim_end_matches('/');
+                let role = role.trim();
+                if paths.contains(path) && !role.is_empty() {
+                    annotations.insert(path.to_string(), truncate(role));
+                }
+            }
+            Some(_) => {}
+        }
+    }
+
+    (prose.trim().to_string(), annotations)
+}
+
+fn truncate(role: &str) -> String {
+    if role.chars().count() <= MAX_ROLE_LEN {
+        return role.to_string();
+    }
+    let truncated: String = role.chars().take(MAX_ROLE_LEN - 1).collect();
+    format!("{}…", truncated.trim_end())
+}
+
+/// Unique Mermaid identifiers of the node paths
+fn node_ids<'a>(paths: impl Iterator<Item = &'a str>) -> HashMap<&'a str, String> {
+    let mut ids = HashMap::new();
+    let mut used = BTreeSet::new();
+    for path in paths {
+        let base = format!("n_{}", sanitize(path));
+        let mut id = base.clone();
+        let mut suffix = 1;
+        while !used.insert(id.clone()) {
+            suffix += 1;
+            id = format!("{}_{}", base, suffix);
+        }
+        ids.insert(path, id);
+    }
+    ids
+}
+
+fn sanitize(name: &str) -> String {
+    name.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect()
+}
+
+/// Text safe inside a quoted Mermaid label
+fn escape(text: &str) -> String {
+    text.replace('"', "#quot;").replace('<', "#lt;").replace('>', "#gt;")
+}
@@ -0,0 +1,392 @@
+//! Repository file discovery shared by every analysis step.
+//!
+//! Walks the repository honouring `.gitignore`, `.ignore` and `.raidmeignore`,
+//! then applies the `analysis` filters: excluded directory and file names,
+//! `exclude` globs, and `include_extensions` / `include` globs for the files
+//! to analyze. `analysis.overrides` come last and win over every other rule,
+//! so they can bring back a gitignored path or drop a tracked one.
+//!
+//! Binary, generated and vendored files are still listed, tagged with their
+//! [`FileClass`], but only forced includes among them are analyzed.
+
+use crate::classify::{Classifier, FileClass};
+use crate::config::{AnalysisConfig, PathRule};
+use crate::error::{Error, Result};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use ignore::overrides::OverrideBuilder;
+use ignore::WalkBuilder;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Ignore file specific to raidme, using the gitignore syntax
+pub const IGNORE_FILE: &str = ".raidmeignore";
+
+/// Prefix of the files raidme keeps in the repository (config, database, prompts)
+const RAIDME_PREFIX: &str = ".raidme";
+
+/// A path found in the repository
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Entry {
+    /// Path relative to the repository root
+    pub relative: PathBuf,
+    /// Absolute (or repository-joined) path
+    pub path: PathBuf,
+    pub is_dir: bool,
+    /// Why the content is not analyzed, if it is not
+    pub class: Option<FileClass>,
+}
+
+impl Entry {
+    /// Number of components below the root, 1 for top-level entries
+    pub fn depth(&self) -> usize {
+        self.relative.components().count()
+    }
+
+    pub fn file_name(&self) -> String {
+        self.relative
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+}
+
+/// Matchers compiled from the analysis configuration
+struct Filters {
+    exclude_dirs: Vec<String>,
+    exclude_files: Vec<String>,
+    exclude: GlobSet,
+    forced_exclude: GlobSet,
+}
+
+impl Filters {
+    /// Whether a path is dropped regardless of the ignore files; `forced` only applies the overrides
+    fn excludes(&self, relative: &Path, is_dir: bool, forced: bool) -> bool {
+        let name = relative.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+        if name.starts_with(RAIDME_PREFIX) || self.forced_exclude.is_match(relative) {
+            return true;
+        }
+        if forced {
+            return false;
+        }
+        let listed = if is_dir {
+            self.exclude_dirs.iter().any(|dir| *dir == name)
+        } else {
+            self.exclude_files.iter().any(|file| *file == name)
+        };
+        listed || self.exclude.is_match(relative)
+    }
+}
+
+/// File discovery for one repository and analysis configuration
+pub struct Discovery {
+    root: PathBuf,
+    max_depth: Option<usize>,
+    max_file_size: usize,
+    respect_gitignore: bool,
+    classify: bool,
+    vendored_dirs: Vec<String>,
+    include_extensions: Vec<String>,
+    include: GlobSet,
+    forced_include: Vec<String>,
+    forced_include_set: GlobSet,
+    filters: Arc<Filters>,
+}
+
+impl Discovery {
+    pub fn new(repo_path: &Path, analysis: &AnalysisConfig) -> Result<Self> {
+        let rules = |rule: PathRule| -> Vec<String> {
+            analysis.overrides
+                .iter()
+                .filter(|(_, r)| **r == rule)
+                .map(|(pattern, _)| pattern.clone())
+                .collect()
+        };
+        let forced_include = rules(PathRule::Include);
+
+        Ok(Self {
+            root: repo_path.to_path_buf(),
+            max_depth: analysis.max_depth,
+            max_file_size: analysis.max_file_size,
+            respect_gitignore: analysis.respect_gitignore,
+            classify: analysis.classify,
+            vendored_dirs: analysis.vendored_dirs.clone(),
+            include_extensions: analysis.include_extensions.iter().map(|ext| ext.to_lowercase()).collect(),
+            include: glob_set(&analysis.include)?,
+            forced_include_set: glob_set(&forced_include)?,
+            forced_include,
+            filters: Arc::new(Filters {
+                exclude_dirs: analysis.exclude_dirs.clone(),
+                exclude_files: analysis.exclude_files.clone(),
+                exclude: glob_set(&analysis.exclude)?,
+                forced_exclude: glob_set(&rules(PathRule::Exclude))?,
+            }),
+        })
+    }
+
+    /// Every path that is not ignored nor excluded, directories included, sorted by path and classified
+    pub fn entries(&self) -> Result<Vec<Entry>> {
+        let mut entries = BTreeMap::new();
+
+        let mut walker = self.walker(false);
+        walker
+            .git_ignore(self.respect_gitignore)
+            .git_global(self.respect_gitignore)
+            .git_exclude(self.respect_gitignore)
+            .ignore(self.respect_gitignore)
+            .parents(self.respect_gitignore);
+        self.collect(walker, false, &mut entries)?;
+
+        // Forced includes bypass the ignore files, which would otherwise prune their directories
+        if !self.forced_include.is_empty() {
+            let mut overrides = OverrideBuilder::new(&self.root);
+            for pattern in &self.forced_include {
+                overrides.add(pattern)
+                    .map_err(|e| Error::ConfigError(format!("Invalid override '{}': {}", pattern, e)))?;
+            }
+            let overrides = overrides.build()
+                .map_err(|e| Error::ConfigError(format!("Invalid overrides: {}", e)))?;
+
+            let mut walker = self.walker(true);
+            walker.standard_filters(false).overrides(overrides);
+            self.collect(walker, true, &mut entries)?;
+        }
+
+        let mut entries: Vec<Entry> = entries.into_values().collect();
+        if self.classify {
+            self.classify(&mut entries)?;
+        }
+        Ok(entries)
+    }
+
+    /// Files of `entries` selected for analysis: unclassified, matching the includes and within the size limit
+    pub fn files<'a>(&self, entries: &'a [Entry]) -> Vec<&'a Entry> {
+        entries
+            .iter()
+            .filter(|entry| !entry.is_dir && self.is_analyzed(entry))
+            .filter(|entry| {
+                std::fs::metadata(&entry.path).is_ok_and(|m| m.len() as usize <= self.max_file_size)
+            })
+            .collect()
+    }
+
+    /// Whether a file is analyzed, given it was discovered
+    pub fn is_included(&self, relative: &Path) -> bool {
+        if self.forced_include_set.is_match(relative) {
+            return true;
+        }
+        if self.include_extensions.is_empty() && self.include.is_empty() {
+            return true;
+        }
+        let extension = relative
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        self.include_extensions.contains(&extension) || self.include.is_match(relative)
+    }
+
+    /// Whether the content of a discovered entry is analyzed: forced includes always are
+    pub fn is_analyzed(&self, entry: &Entry) -> bool {
+        if self.forced_include_set.is_match(&entry.relative) {
+            return true;
+        }
+        entry.class.is_none() && self.is_included(&entry.relative)
+    }
+
+    /// Tag binary, generated and vendored entries, honouring every `.gitattributes` found
+    fn classify(&self, entries: &mut [Entry]) -> Result<()> {
+        let mut classifier = Classifier::new(&self.vendored_dirs);
+        // Sorted by path, so outer attribute files are loaded before nested ones
+        for entry in entries.iter().filter(|e| !e.is_dir && e.file_name() == ".gitattributes") {
+            classifier.add_attributes(&entry.relative, &entry.path)?;
+        }
+        for entry in entries.iter_mut() {
+            entry.class = classifier.classify(&entry.relative, &entry.path, entry.is_dir);
+        }
+        Ok(())
+    }
+
+    fn walker(&self, forced: bool) -> WalkBuilder {
+        let mut walker = WalkBuilder::new(&self.root);
+        walker
+            .hidden(false)
+            .require_git(false)
+            .follow_links(false)
+            .max_depth(self.max_depth)
+            .add_custom_ignore_filename(IGNORE_FILE);
+
+        let root = self.root.clone();
+        let filters = Arc::clone(&self.filters);
+        walker.filter_entry(move |entry| {
+            let Ok(relative) = entry.path().strip_prefix(&root) else {
+                return true;
+            };
+            if relative.as_os_str().is_empty() {
+                return true;
+            }
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            !filters.excludes(relative, is_dir, forced)
+        });
+        walker
+    }
+
+    /// Add the walked entries; `forced` keeps only the files matching a forced include, with their parents
+    fn collect(&self, walker: WalkBuilder, forced: bool, entries: &mut BTreeMap<PathBuf, Entry>) -> Result<()> {
+        for entry in walker.build() {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    tracing::debug!("Skipping unreadable path: {}", e);
+                    continue;
+                }
+            };
+            let Ok(relative) = entry.path().strip_prefix(&self.root) else { continue };
+            if relative.as_os_str().is_empty() {
+                continue;
+            }
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+
+            if forced {
+                if is_dir || !self.forced_include_set.is_match(relative) {
+                    continue;
+                }
+                for parent in relative.ancestors().skip(1).filter(|p| !p.as_os_str().is_empty()) {
+                    entries.entry(parent.to_path_buf()).or_insert_with(|| Entry {
+                        relative: parent.to_path_buf(),
+                        path: self.root.join(parent),
+                        is_dir: true,
+                        class: None,
+                    });
+                }
+            }
+
+            entries.entry(relative.to_path_buf()).or_insert_with(|| Entry {
+                relative: relative.to_path_buf(),
+                path: entry.path().to_path_buf(),
+                is_dir,
+                class: None,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Compile glob patterns matched against relative paths: `*` stays within one
+/// directory, so `**/` is needed to match at any depth (`**/*.rs`, `docs/**`)
+pub(crate) fn glob_set(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| Error::ConfigError(format!("Invalid glob '{}': {}", pattern, e)))?;
+        builder.add(glob);
+    }
+    builder.build().map_err(|e| Error::ConfigError(format!("Invalid globs: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Write `files` below a fresh directory
+    fn repo(files: &[(&str, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for (path, content) in files {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    /// Relative paths of the files selected for analysis
+    fn analyzed(dir: &Path, analysis: &AnalysisConfig) -> Vec<String> {
+        let discovery = Discovery::new(dir, analysis).unwrap();
+        let entries = discovery.entries().unwrap();
+        discovery
+            .files(&entries)
+            .iter()
+            .map(|entry| entry.relative.to_string_lossy().replace('\\', "/"))
+            .collect()
+    }
+
+    fn patterns(patterns: &[&str]) -> Vec<String> {
+        patterns.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn glob_star_stays_within_a_directory() {
+        let set = glob_set(&patterns(&["src/*.rs"])).unwrap();
+        assert!(set.is_match("src/main.rs"));
+        assert!(!set.is_match("src/a/b/c.rs"));
+
+        let set = glob_set(&patterns(&["src/**/*.rs"])).unwrap();
+        assert!(set.is_match("src/main.rs"));
+        assert!(set.is_match("src/a/b/c.rs"));
+    }
+
+    #[test]
+    fn raidmeignore_drops_paths() {
+        let dir = repo(&[
+            (IGNORE_FILE, "fixtures/\n*.snap.md\n"),
+            ("src/main.rs", "fn main() {}"),
+            ("fixtures/data.rs", "// fixture"),
+            ("docs/api.snap.md", "# snapshot"),
+            ("docs/guide.md", "# guide"),
+        ]);
+        let analysis = AnalysisConfig { respect_gitignore: false, ..AnalysisConfig::default() };
+        assert_eq!(analyzed(dir.path(), &analysis), ["docs/guide.md", "src/main.rs"]);
+    }
+
+    #[test]
+    fn include_and_exclude_globs() {
+        let dir = repo(&[
+            ("Dockerfile", "FROM scratch"),
+            ("deploy/Dockerfile", "FROM scratch"),
+            ("src/main.rs", "fn main() {}"),
+            ("src/a/b/c.rs", "fn c() {}"),
+            ("docs/generated/api.md", "# api"),
+            ("docs/guide.md", "# guide"),
+        ]);
+
+        let analysis = AnalysisConfig {
+            include_extensions: Vec::new(),
+            include: patterns(&["src/*.rs", "Dockerfile"]),
+            ..AnalysisConfig::default()
+        };
+        assert_eq!(analyzed(dir.path(), &analysis), ["Dockerfile", "src/main.rs"]);
+
+        let analysis = AnalysisConfig {
+            include: patterns(&["**/Dockerfile"]),
+            exclude: patterns(&["docs/generated/**", "src/*/**"]),
+            ..AnalysisConfig::default()
+        };
+        assert_eq!(
+            analyzed(dir.path(), &analysis),
+            ["Dockerfile", "deploy/Dockerfile", "docs/guide.md", "src/main.rs"]
+        );
+    }
+
+    #[test]
+    fn forced_include_brings_back_a_gitignored_file() {
+        let dir = repo(&[
+            (".gitignore", "build/\n*.log\n"),
+            ("src/main.rs", "fn main() {}"),
+            ("build/schema.sql", "CREATE TABLE users (id INT);"),
+            ("build/cache.bin", "cache"),
+            ("debug.log", "log"),
+        ]);
+        let mut analysis = AnalysisConfig {
+            include_extensions: patterns(&["rs"]),
+            ..AnalysisConfig::default()
+        };
+        assert_eq!(analyzed(dir.path(), &analysis), ["src/main.rs"]);
+
+        analysis.overrides.insert("build/*.sql".to_string(), PathRule::Include);
+        assert_eq!(analyzed(dir.path(), &analysis), ["build/schema.sql", "src/main.rs"]);
+
+        analysis.overrides.insert("src/**".to_string(), PathRule::Exclude);
+        assert_eq!(analyzed(dir.path(), &analysis), ["build/schema.sql"]);
+    }
+}
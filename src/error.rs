@@ -16,7 +16,7 @@ pub enum Error {
     #[error("📝 TOML config error: {0}")]
     Toml(#[from] toml::de::Error),
 
-    #[error("🤖 Invalid LLM provider: {0}\nValid providers: Anthropic, OpenAI, OpenRouter, Ollama")]
+    #[error("🤖 Invalid LLM provider: {0}\nValid providers: Anthropic, OpenAI, OpenRouter, Ollama, Gemini, Mistral, Groq, DeepSeek, xAI, Azure, OpenAI-compatible")]
     InvalidProvider(String),
 
     #[error("TOML serialization error: {0}")]
@@ -52,6 +52,9 @@ pub enum Error {
     #[error("Network error: {0}")]
     Network(String),
 
+    #[error("🦙 Ollama is not reachable at {0}\nStart it with `ollama serve` or point OLLAMA_HOST / --base-url to a running daemon")]
+    OllamaUnavailable(String),
+
     #[error("Rate limit exceeded: {0}")]
     RateLimit(String),
 
@@ -77,10 +80,7 @@ impl From<rig::completion::CompletionError> for Error {
 
 impl From<walkdir::Error> for Error {
     fn from(err: walkdir::Error) -> Self {
-        Error::Io(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            err.to_string(),
-        ))
+        Error::Io(std::io::Error::other(err.to_string()))
     }
 }
 
@@ -0,0 +1,897 @@
+//! Import graph between the files and modules of the repository.
+//!
+//! Rust `use`/`mod` declarations, Go imports, Python imports and
+//! JavaScript/TypeScript imports are read with tree-sitter and resolved to
+//! the files of the repository; the others are kept as external packages.
+//! The imports are stored in the `imports` table, aggregated into a module
+//! graph whose cycles are detected, and drawn by [`crate::diagrams`].
+
+use crate::error::{Error, Result};
+use crate::manifests::{Ecosystem, Manifest};
+use crate::symbols::{self, Language};
+use sqlx::{Row, SqlitePool};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
+use tree_sitter::Node;
+
+/// Most modules drawn in a graph; files are grouped by directory above that
+const MAX_GRAPH_NODES: usize = 40;
+
+/// Rust crates that are never dependencies
+const RUST_BUILTIN_CRATES: &[&str] = &["std", "core", "alloc"];
+
+/// Extensions tried when resolving a JavaScript/TypeScript specifier
+const JS_EXTENSIONS: &[&str] = &["ts", "tsx", "d.ts", "js", "jsx", "mjs", "cjs"];
+
+/// An import of a source file
+#[derive(Debug, Clone)]
+pub struct Import {
+    /// Importing file, relative to the repository root
+    pub file: String,
+    /// Import as written: module path, package or relative specifier
+    pub specifier: String,
+    /// Imported file, or package directory for Go, when it is part of the repository
+    pub target: Option<String>,
+    /// Imported external package, when it is not
+    pub package: Option<String>,
+    /// 1-based line of the import
+    pub line: usize,
+}
+
+/// Import as written in the source, before resolution
+struct RawImport {
+    specifier: String,
+    /// Names of a Python `from … import`, which may be submodules
+    names: Vec<String>,
+    /// Rust `mod name;` rather than `use`
+    is_mod: bool,
+    line: usize,
+}
+
+/// Resolves import specifiers to the files of the repository
+pub struct Resolver {
+    files: BTreeSet<String>,
+    /// Source directory of each Cargo crate, by its name in Rust paths
+    crates: BTreeMap<String, String>,
+    /// Directory of each Go module, by module path
+    go_modules: BTreeMap<String, String>,
+}
+
+impl Resolver {
+    /// `files` are the paths of the repository, relative to its root
+    pub fn new(files: impl IntoIterator<Item = String>, manifests: &[Manifest]) -> Self {
+        let mut crates = BTreeMap::new();
+        let mut go_modules = BTreeMap::new();
+        for manifest in manifests {
+            let (Some(ecosystem), Some(name)) = (manifest.ecosystem, &manifest.name) else { continue };
+            let dir = parent(&manifest.path).to_string();
+            match ecosystem {
+                Ecosystem::Cargo => {
+                    crates.insert(name.replace('-', "_"), join(&dir, "src").unwrap_or_default());
+                }
+                Ecosystem::Go => {
+                    go_modules.insert(name.clone(), dir);
+                }
+                _ => {}
+            }
+        }
+        Self { files: files.into_iter().collect(), crates, go_modules }
+    }
+
+    fn resolve(&self, language: Language, file: &str, raw: RawImport) -> Vec<Import> {
+        let import = |specifier: &str, (target, package): (Option<String>, Option<String>)| Import {
+            file: file.to_string(),
+            specifier: specifier.to_string(),
+            target: target.filter(|target| target != file),
+            package,
+            line: raw.line,
+        };
+
+        match language {
+            Language::Rust if raw.is_mod => {
+                let target = self.rust_submodule(&rust_module_dir(file), &[raw.specifier.as_str()]);
+                vec![import(&raw.specifier, (target, None))]
+            }
+            Language::Rust => expand_use(&raw.specifier)
+                .iter()
+                .filter_map(|path| self.resolve_rust(file, path).map(|resolved| import(path, resolved)))
+                .collect(),
+            Language::Go => vec![import(&raw.specifier, self.resolve_go(&raw.specifier))],
+            Language::Python => vec![import(&raw.specifier, self.resolve_python(file, &raw.specifier, &raw.names))],
+            Language::TypeScript | Language::Tsx => vec![import(&raw.specifier, self.resolve_js(file, &raw.specifier))],
+            Language::Java | Language::C | Language::Cpp => Vec::new(),
+        }
+    }
+
+    /// Module file of a Rust path, `None` for the standard library
+    fn resolve_rust(&self, file: &str, path: &str) -> Option<(Option<String>, Option<String>)> {
+        let segments: Vec<&str> = path.split("::").filter(|segment| !segment.is_empty()).collect();
+        let first = *segments.first()?;
+        if RUST_BUILTIN_CRATES.contains(&first) {
+            return None;
+        }
+
+        let (base, rest) = match first {
+            "crate" => (self.crate_src(file), &segments[1..]),
+            "self" => (rust_module_dir(file), &segments[1..]),
+            "super" => {
+                let supers = segments.iter().take_while(|segment| **segment == "super").count();
+                let mut dir = rust_module_dir(file);
+                for _ in 0..supers {
+                    dir = parent(&dir).to_string();
+                }
+                (dir, &segments[supers..])
+            }
+            name => match self.crates.get(name) {
+                Some(src) => (src.clone(), &segments[1..]),
+                // A module declared in the current one, else an external crate
+                None => {
+                    return Some(match self.rust_submodule(&rust_module_dir(file), &segments) {
+                        Some(target) => (Some(target), None),
+                        None => (None, Some(name.to_string())),
+                    })
+                }
+            },
+        };
+
+        let target = self.rust_submodule(&base, rest).or_else(|| {
+            [format!("{}/lib.rs", base), format!("{}/main.rs", base), format!("{}.rs", base), format!("{}/mod.rs", base)]
+                .into_iter()
+                .find(|candidate| self.files.contains(candidate.trim_start_matches('/')))
+                .map(|candidate| candidate.trim_start_matches('/').to_string())
+        });
+        Some((target, None))
+    }
+
+    /// File of the deepest module of `segments` below a module directory
+    fn rust_submodule(&self, base: &str, segments: &[&str]) -> Option<String> {
+        (1..=segments.len()).rev().find_map(|len| {
+            let module = join(base, &segments[..len].join("/"))?;
+            [format!("{}.rs", module), format!("{}/mod.rs", module)]
+                .into_iter()
+                .find(|candidate| self.files.contains(candidate))
+        })
+    }
+
+    /// Source directory of the crate a Rust file belongs to
+    fn crate_src(&self, file: &str) -> String {
+        let owner = self
+            .crates
+            .values()
+            .filter(|src| src.is_empty() || file.starts_with(&format!("{}/", src)))
+            .max_by_key(|src| src.len());
+        if let Some(src) = owner {
+            return src.clone();
+        }
+
+        let mut dir = parent(file);
+        while !dir.is_empty() {
+            if dir == "src" || dir.ends_with("/src") {
+                return dir.to_string();
+            }
+            dir = parent(dir);
+        }
+        parent(file).to_string()
+    }
+
+    /// Package directory of a Go import path
+    fn resolve_go(&self, specifier: &str) -> (Option<String>, Option<String>) {
+        for (module, dir) in &self.go_modules {
+            let rest = if specifier == module {
+                Some("")
+            } else {
+                specifier.strip_prefix(module.as_str()).and_then(|rest| rest.strip_prefix('/'))
+            };
+            let Some(target) = rest.and_then(|rest| join(dir, rest)) else { continue };
+            let prefix = if target.is_empty() { String::new() } else { format!("{}/", target) };
+            let is_package = self.files.iter().any(|file| {
+                file.strip_prefix(&prefix).is_some_and(|name| name.ends_with(".go") && !name.contains('/'))
+            });
+            if is_package {
+                return (Some(target), None);
+            }
+        }
+        (None, Some(specifier.to_string()))
+    }
+
+    /// Module file of a Python import, trying `from` names as submodules first
+    fn resolve_python(&self, file: &str, specifier: &str, names: &[String]) -> (Option<String>, Option<String>) {
+        let dots = specifier.chars().take_while(|c| *c == '.').count();
+        let module = specifier[dots..].replace('.', "/");
+
+        // Relative imports start from the package of the file, absolute ones from any enclosing directory
+        let bases: Vec<String> = if dots > 0 {
+            let mut dir = parent(file).to_string();
+            for _ in 1..dots {
+                dir = parent(&dir).to_string();
+            }
+            vec![dir]
+        } else {
+            let mut bases = Vec::new();
+            let mut dir = parent(file);
+            loop {
+                bases.push(dir.to_string());
+                if dir.is_empty() {
+                    break;
+                }
+                dir = parent(dir);
+            }
+            bases
+        };
+
+        for base in &bases {
+            let Some(module) = join(base, &module) else { continue };
+            let submodules = names.iter().filter_map(|name| join(&module, name));
+            for candidate in submodules.chain(std::iter::once(module.clone())) {
+                let found = [format!("{}.py", candidate), format!("{}/__init__.py", candidate)]
+                    .into_iter()
+                    .find(|path| self.files.contains(path.trim_start_matches('/')));
+                if let Some(path) = found {
+                    return (Some(path.trim_start_matches('/').to_string()), None);
+                }
+            }
+        }
+
+        match dots {
+            0 => (None, specifier.split('.').next().map(str::to_string)),
+            _ => (None, None),
+        }
+    }
+
+    /// File of a relative JavaScript/TypeScript specifier, package name of a bare one
+    fn resolve_js(&self, file: &str, specifier: &str) -> (Option<String>, Option<String>) {
+        if !specifier.starts_with('.') {
+            let mut parts = specifier.splitn(3, '/');
+            let package = match (parts.next(), parts.next()) {
+                (Some(scope), Some(name)) if scope.starts_with('@') => format!("{}/{}", scope, name),
+                (Some(name), _) => name.to_string(),
+                _ => specifier.to_string(),
+            };
+            return (None, Some(package));
+        }
+
+        let Some(path) = join(parent(file), specifier) else { return (None, None) };
+        let mut candidates = vec![path.clone()];
+        // Compiled `.js` specifiers of TypeScript sources
+        if let Some(stem) = path.strip_suffix(".js") {
+            candidates.extend(["ts", "tsx"].iter().map(|extension| format!("{}.{}", stem, extension)));
+        }
+        candidates.extend(JS_EXTENSIONS.iter().map(|extension| format!("{}.{}", path, extension)));
+        candidates.extend(JS_EXTENSIONS.iter().map(|extension| format!("{}/index.{}", path, extension)));
+        (candidates.into_iter().find(|candidate| self.files.contains(candidate)), None)
+    }
+}
+
+/// Resolved imports of a source file, `None` when its language is not supported
+pub fn extract_file(path: &Path, relative: &str, resolver: &Resolver) -> Result<Option<Vec<Import>>> {
+    let Some(language) = Language::for_path(path) else {
+        return Ok(None);
+    };
+    let source = std::fs::read_to_string(path)?;
+    extract(language, &source, relative, resolver).map(Some)
+}
+
+/// Resolved imports of source code
+pub fn extract(language: Language, source: &str, file: &str, resolver: &Resolver) -> Result<Vec<Import>> {
+    let tree = symbols::parse(language, source, file)?;
+    let source = source.as_bytes();
+
+    let mut raw = Vec::new();
+    let mut stack = vec![tree.root_node()];
+    while let Some(node) = stack.pop() {
+        if let Some(import) = raw_import(language, node, source) {
+            raw.extend(import);
+            continue;
+        }
+        let mut cursor = node.walk();
+        stack.extend(node.named_children(&mut cursor));
+    }
+
+    raw.sort_by_key(|import| import.line);
+    Ok(raw.into_iter().flat_map(|import| resolver.resolve(language, file, import)).collect())
+}
+
+/// Imports declared by a node, `None` when it is not an import
+fn raw_import(language: Language, node: Node, source: &[u8]) -> Option<Vec<RawImport>> {
+    let text = |node: Node| node.utf8_text(source).unwrap_or_default().to_string();
+    let raw = |specifier: String, names: Vec<String>, is_mod: bool| RawImport {
+        specifier,
+        names,
+        is_mod,
+        line: node.start_position().row + 1,
+    };
+    let mut cursor = node.walk();
+
+    match (language, node.kind()) {
+        (Language::Rust, "use_declaration") => {
+            let argument = node.child_by_field_name("argument")?;
+            Some(vec![raw(text(argument), Vec::new(), false)])
+        }
+        (Language::Rust, "mod_item") if node.child_by_field_name("body").is_none() => {
+            let name = node.child_by_field_name("name")?;
+            Some(vec![raw(text(name), Vec::new(), true)])
+        }
+        (Language::Go, "import_spec") => {
+            let path = node.child_by_field_name("path")?;
+            Some(vec![raw(unquote(&text(path)), Vec::new(), false)])
+        }
+        (Language::Python, "import_statement") => Some(
+            node.children_by_field_name("name", &mut cursor)
+                .map(|name| raw(text(python_name(name)), Vec::new(), false))
+                .collect(),
+        ),
+        (Language::Python, "import_from_statement") => {
+            let module = node.child_by_field_name("module_name")?;
+            let names = node
+                .children_by_field_name("name", &mut cursor)
+                .map(|name| text(python_name(name)).replace('.', "/"))
+                .collect();
+            Some(vec![raw(text(module), names, false)])
+        }
+        (Language::TypeScript | Language::Tsx, "import_statement" | "export_statement") => {
+            let source = node.child_by_field_name("source")?;
+            Some(vec![raw(unquote(&text(source)), Vec::new(), false)])
+        }
+        (Language::TypeScript | Language::Tsx, "call_expression") => {
+            let function = node.child_by_field_name("function")?;
+            if function.kind() != "import" && text(function) != "require" {
+                return None;
+            }
+            let argument = node.child_by_field_name("arguments")?.named_child(0)?;
+            if argument.kind() != "string" {
+                return None;
+            }
+            Some(vec![raw(unquote(&text(argument)), Vec::new(), false)])
+        }
+        _ => None,
+    }
+}
+
+/// Imported name of a Python `aliased_import`
+fn python_name(node: Node) -> Node {
+    match node.kind() {
+        "aliased_import" => node.child_by_field_name("name").unwrap_or(node),
+        _ => node,
+    }
+}
+
+fn unquote(literal: &str) -> String {
+    literal.trim_matches(['"', '\'', '`']).to_string()
+}
+
+/// Paths of a Rust use tree, `a::{b, c::d as e}` giving `a::b` and `a::c::d`
+fn expand_use(tree: &str) -> Vec<String> {
+    let tree: String = tree.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut paths = Vec::new();
+    expand_use_into("", &tree, &mut paths);
+    paths
+}
+
+fn expand_use_into(prefix: &str, tree: &str, paths: &mut Vec<String>) {
+    let tree = tree.trim();
+    let join_path = |path: &str| match (prefix.is_empty(), path.is_empty()) {
+        (true, _) => path.to_string(),
+        (false, true) => prefix.to_string(),
+        (false, false) => format!("{}::{}", prefix, path),
+    };
+
+    if let Some(open) = tree.find('{') {
+        let prefix = join_path(tree[..open].trim().trim_end_matches("::"));
+        let close = tree.rfind('}').unwrap_or(tree.len());
+        let mut depth = 0;
+        let mut start = open + 1;
+        for (i, c) in tree[..close].char_indices().skip_while(|(i, _)| *i <= open) {
+            match c {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                ',' if depth == 0 => {
+                    expand_use_into(&prefix, &tree[start..i], paths);
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        expand_use_into(&prefix, &tree[start..close], paths);
+        return;
+    }
+
+    let path = tree.split(" as ").next().unwrap_or(tree).trim();
+    let path = path.trim_end_matches('*').trim_end_matches("::");
+    match path {
+        "" if tree.is_empty() => {}
+        "" | "self" => paths.push(prefix.to_string()),
+        _ => paths.push(join_path(path)),
+    }
+}
+
+/// Directory holding the submodules of a Rust file
+fn rust_module_dir(file: &str) -> String {
+    let path = Path::new(file);
+    let name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    match name.as_ref() {
+        "lib.rs" | "main.rs" | "mod.rs" => parent(file).to_string(),
+        _ => file.trim_end_matches(".rs").to_string(),
+    }
+}
+
+/// Parent directory of a relative path, empty at the root
+fn parent(path: &str) -> &str {
+    path.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("")
+}
+
+/// `relative` resolved against `dir`, `None` when it leaves the repository
+fn join(dir: &str, relative: &str) -> Option<String> {
+    let mut components: Vec<&str> = dir.split('/').filter(|c| !c.is_empty()).collect();
+    for component in relative.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                components.pop()?;
+            }
+            _ => components.push(component),
+        }
+    }
+    Some(components.join("/"))
+}
+
+/// Dependencies between modules: files, or directories on large repositories
+#[derive(Debug, Clone, Default)]
+pub struct ModuleGraph {
+    /// Directory depth modules are grouped at, `None` when modules are files
+    pub depth: Option<usize>,
+    /// Imports from a module to another one, with their count
+    pub edges: BTreeMap<(String, String), usize>,
+    /// External packages imported by each module
+    pub packages: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl ModuleGraph {
+    /// Graph of files, or of directories when there are too many files to draw
+    pub fn new(imports: &[Import]) -> Self {
+        let internal: Vec<(&str, &str)> = imports
+            .iter()
+            .filter_map(|import| import.target.as_deref().map(|target| (import.file.as_str(), target)))
+            .collect();
+
+        let count = |depth: Option<usize>| {
+            internal
+                .iter()
+                .flat_map(|(from, to)| [module_of(from, depth), module_of(to, depth)])
+                .collect::<BTreeSet<_>>()
+                .len()
+        };
+        let max_depth = internal.iter().flat_map(|(from, to)| [from, to]).map(|path| path.split('/').count()).max();
+        let depth = match max_depth {
+            Some(max_depth) if count(None) > MAX_GRAPH_NODES => {
+                (1..max_depth).rev().find(|depth| count(Some(*depth)) <= MAX_GRAPH_NODES).or(Some(1))
+            }
+            _ => None,
+        };
+
+        Self::with_depth(imports, depth)
+    }
+
+    /// Graph with modules grouped at a given directory depth, `None` for files
+    pub fn with_depth(imports: &[Import], depth: Option<usize>) -> Self {
+        let mut graph = Self { depth, ..Default::default() };
+        for import in imports {
+            let from = module_of(&import.file, depth);
+            if let Some(to) = import.target.as_deref().map(|target| module_of(target, depth)) {
+                if from != to {
+                    *graph.edges.entry((from, to)).or_default() += 1;
+                }
+            } else if let Some(package) = &import.package {
+                graph.packages.entry(from).or_default().insert(package.clone());
+            }
+        }
+        graph
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty()
+    }
+
+    /// Groups of modules importing each other, found with Tarjan's algorithm
+    pub fn cycles(&self) -> Vec<Vec<String>> {
+        let mut successors: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for (from, to) in self.edges.keys() {
+            successors.entry(from).or_default().push(to);
+            successors.entry(to).or_default();
+        }
+
+        let mut tarjan = Tarjan::new(&successors);
+        for node in successors.keys() {
+            if !tarjan.index.contains_key(node) {
+                tarjan.visit(node);
+            }
+        }
+
+        let mut cycles: Vec<Vec<String>> = tarjan
+            .components
+            .into_iter()
+            .filter(|component| component.len() > 1)
+            .map(|mut component| {
+                component.sort();
+                component.into_iter().map(str::to_string).collect()
+            })
+            .collect();
+        cycles.sort();
+        cycles
+    }
+
+    /// Text summary of the dependencies sent to the LLM
+    pub fn summary(&self) -> String {
+        let mut summary = String::from("Module dependencies (module: modules it imports)\n");
+        if let Some(depth) = self.depth {
+            summary = format!("Module dependencies (module: modules it imports), modules being directories at depth {}\n", depth);
+        }
+        let mut dependencies: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for (from, to) in self.edges.keys() {
+            dependencies.entry(from).or_default().push(to);
+        }
+        for (module, imported) in dependencies {
+            summary.push_str(&format!("{}: {}\n", module, imported.join(", ")));
+        }
+
+        if !self.packages.is_empty() {
+            summary.push_str("\nExternal packages (module: packages it imports)\n");
+            for (module, packages) in &self.packages {
+                summary.push_str(&format!("{}: {}\n", module, packages.iter().cloned().collect::<Vec<_>>().join(", ")));
+            }
+        }
+
+        let cycles = self.cycles();
+        if !cycles.is_empty() {
+            summary.push_str("\nImport cycles\n");
+            for cycle in cycles {
+                summary.push_str(&format!("- {}\n", cycle.join(", ")));
+            }
+        }
+        summary
+    }
+}
+
+/// State of Tarjan's strongly connected components algorithm
+struct Tarjan<'a> {
+    successors: &'a BTreeMap<&'a str, Vec<&'a str>>,
+    index: HashMap<&'a str, usize>,
+    low_link: HashMap<&'a str, usize>,
+    stack: Vec<&'a str>,
+    on_stack: BTreeSet<&'a str>,
+    components: Vec<Vec<&'a str>>,
+}
+
+impl<'a> Tarjan<'a> {
+    fn new(successors: &'a BTreeMap<&'a str, Vec<&'a str>>) -> Self {
+        Self {
+            successors,
+            index: HashMap::new(),
+            low_link: HashMap::new(),
+            stack: Vec::new(),
+            on_stack: BTreeSet::new(),
+            components: Vec::new(),
+        }
+    }
+
+    /// Visit the nodes reachable from `root` with an explicit stack, long import chains
+    /// being deeper than the thread stack allows
+    fn visit(&mut self, root: &'a str) {
+        // Nodes being visited, with the position of the next successor to follow
+        let mut calls: Vec<(&'a str, usize)> = Vec::new();
+        self.open(root);
+        calls.push((root, 0));
+
+        while let Some((node, next)) = calls.last_mut() {
+            let node = *node;
+            let successor = self.successors.get(node).and_then(|successors| successors.get(*next)).copied();
+            *next += 1;
+            match successor {
+                Some(successor) if !self.index.contains_key(successor) => {
+                    self.open(successor);
+                    calls.push((successor, 0));
+                }
+                Some(successor) => {
+                    if self.on_stack.contains(successor) {
+                        let low_link = self.low_link[node].min(self.index[successor]);
+                        self.low_link.insert(node, low_link);
+                    }
+                }
+                None => {
+                    calls.pop();
+                    self.close(node);
+                    if let Some(&(caller, _)) = calls.last() {
+                        let low_link = self.low_link[caller].min(self.low_link[node]);
+                        self.low_link.insert(caller, low_link);
+                    }
+                }
+            }
+        }
+    }
+
+    fn open(&mut self, node: &'a str) {
+        let index = self.index.len();
+        self.index.insert(node, index);
+        self.low_link.insert(node, index);
+        self.stack.push(node);
+        self.on_stack.insert(node);
+    }
+
+    /// Pop the component of a node once all its successors are visited, if it is the root of one
+    fn close(&mut self, node: &'a str) {
+        if self.low_link[node] == self.index[node] {
+            let mut component = Vec::new();
+            while let Some(member) = self.stack.pop() {
+                self.on_stack.remove(member);
+                component.push(member);
+                if member == node {
+                    break;
+                }
+            }
+            self.components.push(component);
+        }
+    }
+}
+
+/// Module of a file or Go package directory, its directory truncated to `depth` when grouping
+fn module_of(path: &str, depth: Option<usize>) -> String {
+    // Go packages are directories, the unit of import
+    let path = if path.ends_with(".go") { parent(path) } else { path };
+    let Some(depth) = depth else {
+        return if path.is_empty() { ".".to_string() } else { path.to_string() };
+    };
+
+    let is_package_dir = Path::new(path).extension().is_none();
+    let dir = if is_package_dir { path } else { parent(path) };
+    let module: Vec<&str> = dir.split('/').filter(|c| !c.is_empty()).take(depth).collect();
+    if module.is_empty() {
+        ".".to_string()
+    } else {
+        module.join("/")
+    }
+}
+
+/// Replace the stored imports with the given ones
+pub async fn store(db: &SqlitePool, imports: &[Import]) -> Result<()> {
+    let mut tx = db.begin().await.map_err(Error::Sqlx)?;
+    sqlx::query("DELETE FROM imports").execute(&mut *tx).await.map_err(Error::Sqlx)?;
+    for import in imports {
+        sqlx::query("INSERT INTO imports (file, specifier, target, package, line) VALUES ($1, $2, $3, $4, $5)")
+            .bind(&import.file)
+            .bind(&import.specifier)
+            .bind(&import.target)
+            .bind(&import.package)
+            .bind(import.line as i64)
+            .execute(&mut *tx)
+            .await
+            .map_err(Error::Sqlx)?;
+    }
+    tx.commit().await.map_err(Error::Sqlx)?;
+    Ok(())
+}
+
+/// Stored imports
+pub async fn load(db: &SqlitePool) -> Result<Vec<Import>> {
+    let rows = sqlx::query("SELECT * FROM imports ORDER BY file, line")
+        .fetch_all(db)
+        .await
+        .map_err(Error::Sqlx)?;
+
+    rows.into_iter()
+        .map(|row| {
+            let line: i64 = row.try_get("line").map_err(Error::Sqlx)?;
+            Ok(Import {
+                file: row.try_get("file").map_err(Error::Sqlx)?,
+                specifier: row.try_get("specifier").map_err(Error::Sqlx)?,
+                target: row.try_get("target").map_err(Error::Sqlx)?,
+                package: row.try_get("package").map_err(Error::Sqlx)?,
+                line: line as usize,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn import(file: &str, target: &str) -> Import {
+        Import { file: file.to_string(), specifier: target.to_string(), target: Some(target.to_string()), package: None, line: 1 }
+    }
+
+    const FILES: &[&str] = &[
+        "src/main.rs",
+        "src/config.rs",
+        "src/net/mod.rs",
+        "src/net/http.rs",
+        "src/net/tls.rs",
+        "crates/core/src/lib.rs",
+        "crates/core/src/model.rs",
+        "go.mod",
+        "cmd/server/main.go",
+        "internal/store/store.go",
+        "app/__init__.py",
+        "app/models.py",
+        "app/api/__init__.py",
+        "app/api/views.py",
+        "app/api/serializers.py",
+        "web/src/index.ts",
+        "web/src/util.ts",
+        "web/src/components/index.tsx",
+        "web/src/lib/client.ts",
+    ];
+
+    fn resolver() -> Resolver {
+        let manifest = |path: &str, ecosystem, name: &str| Manifest {
+            path: path.to_string(),
+            ecosystem: Some(ecosystem),
+            name: Some(name.to_string()),
+            ..Default::default()
+        };
+        Resolver::new(
+            FILES.iter().map(|file| file.to_string()),
+            &[
+                manifest("Cargo.toml", Ecosystem::Cargo, "app"),
+                manifest("crates/core/Cargo.toml", Ecosystem::Cargo, "my-core"),
+                manifest("go.mod", Ecosystem::Go, "example.com/shop"),
+            ],
+        )
+    }
+
+    /// `(specifier, target, package)` of the imports of a source
+    fn resolved(language: Language, file: &str, source: &str) -> Vec<(String, Option<String>, Option<String>)> {
+        extract(language, source, file, &resolver())
+            .unwrap()
+            .into_iter()
+            .map(|import| (import.specifier, import.target, import.package))
+            .collect()
+    }
+
+    fn internal(specifier: &str, target: &str) -> (String, Option<String>, Option<String>) {
+        (specifier.to_string(), Some(target.to_string()), None)
+    }
+
+    fn external(specifier: &str, package: &str) -> (String, Option<String>, Option<String>) {
+        (specifier.to_string(), None, Some(package.to_string()))
+    }
+
+    #[test]
+    fn expands_use_trees() {
+        assert_eq!(expand_use("crate::config::Config"), ["crate::config::Config"]);
+        assert_eq!(
+            expand_use("crate::{config::{self, Config}, net::http as h, *}"),
+            ["crate::config", "crate::config::Config", "crate::net::http", "crate"]
+        );
+        assert_eq!(expand_use("std::io::{\n    Read,\n    Write,\n}"), ["std::io::Read", "std::io::Write"]);
+    }
+
+    #[test]
+    fn resolves_rust_paths() {
+        let imports = resolved(Language::Rust, "src/net/http.rs", "
+            mod inner;
+            use crate::config::Config;
+            use super::tls::{connect, Tls};
+            use my_core::model::Order;
+            use std::io::Read;
+            use serde::Deserialize;
+        ");
+        assert_eq!(
+            imports,
+            [
+                ("inner".to_string(), None, None),
+                internal("crate::config::Config", "src/config.rs"),
+                internal("super::tls::connect", "src/net/tls.rs"),
+                internal("super::tls::Tls", "src/net/tls.rs"),
+                internal("my_core::model::Order", "crates/core/src/model.rs"),
+                external("serde::Deserialize", "serde"),
+            ]
+        );
+
+        let imports = resolved(Language::Rust, "src/main.rs", "mod config;\nmod net;\nuse net::http;\nuse self::config::Config;\nuse my_core::Engine;");
+        assert_eq!(
+            imports,
+            [
+                internal("config", "src/config.rs"),
+                internal("net", "src/net/mod.rs"),
+                internal("net::http", "src/net/http.rs"),
+                internal("self::config::Config", "src/config.rs"),
+                internal("my_core::Engine", "crates/core/src/lib.rs"),
+            ]
+        );
+    }
+
+    #[test]
+    fn resolves_go_module_paths() {
+        let imports = resolved(Language::Go, "cmd/server/main.go", r#"package main
+
+import (
+	"fmt"
+	"example.com/shop/internal/store"
+	"example.com/shop/internal/missing"
+	"github.com/gin-gonic/gin"
+)
+"#);
+        assert_eq!(
+            imports,
+            [
+                external("fmt", "fmt"),
+                internal("example.com/shop/internal/store", "internal/store"),
+                external("example.com/shop/internal/missing", "example.com/shop/internal/missing"),
+                external("github.com/gin-gonic/gin", "github.com/gin-gonic/gin"),
+            ]
+        );
+    }
+
+    #[test]
+    fn resolves_python_imports() {
+        let imports = resolved(Language::Python, "app/api/views.py", "
+import os.path
+from . import serializers
+from .. import models as m
+from ..models import User
+from app.api import serializers as s
+import requests
+from .missing import thing
+");
+        assert_eq!(
+            imports,
+            [
+                external("os.path", "os"),
+                internal(".", "app/api/serializers.py"),
+                internal("..", "app/models.py"),
+                internal("..models", "app/models.py"),
+                internal("app.api", "app/api/serializers.py"),
+                external("requests", "requests"),
+                (".missing".to_string(), None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn resolves_javascript_specifiers() {
+        let imports = resolved(Language::TypeScript, "web/src/index.ts", "
+import { a } from './util';
+import App from './components';
+export * from './lib/client.js';
+const lazy = import('./missing');
+const react = require('react');
+import { x } from '@scope/pkg/sub';
+");
+        assert_eq!(
+            imports,
+            [
+                internal("./util", "web/src/util.ts"),
+                internal("./components", "web/src/components/index.tsx"),
+                internal("./lib/client.js", "web/src/lib/client.ts"),
+                ("./missing".to_string(), None, None),
+                external("react", "react"),
+                external("@scope/pkg/sub", "@scope/pkg"),
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_import_cycles() {
+        let imports = [
+            import("a.rs", "b.rs"),
+            import("b.rs", "c.rs"),
+            import("c.rs", "a.rs"),
+            import("c.rs", "d.rs"),
+            import("d.rs", "e.rs"),
+            import("e.rs", "d.rs"),
+            import("f.rs", "a.rs"),
+        ];
+        let cycles = ModuleGraph::with_depth(&imports, None).cycles();
+        assert_eq!(cycles, [vec!["a.rs", "b.rs", "c.rs"], vec!["d.rs", "e.rs"]]);
+    }
+
+    #[test]
+    fn finds_cycles_of_long_import_chains() {
+        let files: Vec<String> = (0..200_000).map(|i| format!("m{}.rs", i)).collect();
+        let mut imports: Vec<Import> = files.windows(2).map(|pair| import(&pair[0], &pair[1])).collect();
+        imports.push(import(&files[files.len() - 1], &files[0]));
+
+        let cycles = ModuleGraph::with_depth(&imports, None).cycles();
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(cycles[0].len(), files.len());
+    }
+}
@@ -0,0 +1,338 @@
+//! `raidme init`: survey a repository and write its `.raidme.toml`.
+//!
+//! The wizard suggests the analysis filters from the files actually present,
+//! lets the user pick a provider among the ones usable on this machine, and
+//! checks the provider answers before the configuration is written.
+
+use crate::config::{Config, LlmProvider};
+use crate::config_layers::REPO_CONFIG_FILE;
+use crate::error::{Error, Result};
+use crate::prompts::language_for_extension;
+use crate::providers::{self, ollama};
+use std::collections::{BTreeSet, HashMap};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+/// Build outputs, dependencies and caches worth excluding when present
+const GENERATED_DIRS: &[&str] = &[
+    "target", "node_modules", ".git", "build", "dist", ".next", "__pycache__",
+    "vendor", ".venv", "venv", "env", ".tox", ".gradle", "out", "bin", "obj",
+    "coverage", ".pytest_cache", ".mypy_cache", ".terraform", ".idea", ".vscode",
+];
+
+/// Documentation and configuration extensions analyzed alongside the sources
+const SUPPORT_EXTENSIONS: &[&str] = &["md", "txt", "toml", "yaml", "yml", "json"];
+
+/// Root files revealing the build system of the repository
+const MANIFESTS: &[(&str, &str)] = &[
+    ("Cargo.toml", "Cargo"),
+    ("package.json", "npm"),
+    ("pyproject.toml", "Python (pyproject)"),
+    ("setup.py", "Python (setuptools)"),
+    ("go.mod", "Go modules"),
+    ("pom.xml", "Maven"),
+    ("build.gradle", "Gradle"),
+    ("build.gradle.kts", "Gradle"),
+    ("CMakeLists.txt", "CMake"),
+    ("Makefile", "Make"),
+    ("Gemfile", "Bundler"),
+    ("composer.json", "Composer"),
+];
+
+/// What was found in the repository
+#[derive(Debug, Default)]
+pub struct RepoSurvey {
+    /// Source languages with their file count, most used first
+    pub languages: Vec<(String, usize)>,
+    /// Build systems detected from root manifests
+    pub build_systems: Vec<String>,
+    /// Suggested `analysis.include_extensions`
+    pub include_extensions: Vec<String>,
+    /// Suggested `analysis.exclude_dirs`
+    pub exclude_dirs: Vec<String>,
+}
+
+impl RepoSurvey {
+    /// Walk the repository and derive the analysis filters from its content
+    pub fn scan(repo_path: &Path, defaults: &Config) -> Self {
+        let mut languages: HashMap<&'static str, usize> = HashMap::new();
+        let mut extensions = BTreeSet::new();
+        let mut exclude_dirs: BTreeSet<String> = defaults.analysis.exclude_dirs.iter().cloned().collect();
+
+        let walker = walkdir::WalkDir::new(repo_path)
+            .max_depth(defaults.analysis.max_depth.unwrap_or(10))
+            .into_iter()
+            .filter_entry(|entry| {
+                if entry.depth() == 0 || !entry.file_type().is_dir() {
+                    return true;
+                }
+                let name = entry.file_name().to_string_lossy();
+                if GENERATED_DIRS.contains(&name.as_ref()) {
+                    exclude_dirs.insert(name.to_string());
+                    return false;
+                }
+                true
+            });
+
+        for entry in walker.filter_map(|entry| entry.ok()) {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let Some(extension) = entry.path().extension().and_then(|ext| ext.to_str()) else {
+                continue;
+            };
+            let extension = extension.to_lowercase();
+
+            if let Some(language) = language_for_extension(&extension) {
+                *languages.entry(language).or_default() += 1;
+                extensions.insert(extension);
+            } else if SUPPORT_EXTENSIONS.contains(&extension.as_str()) {
+                extensions.insert(extension);
+            }
+        }
+
+        let mut languages: Vec<_> = languages
+            .into_iter()
+            .map(|(language, count)| (language.to_string(), count))
+            .collect();
+        languages.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        let mut build_systems = Vec::new();
+        for (file, build_system) in MANIFESTS {
+            if repo_path.join(file).is_file() && !build_systems.iter().any(|b| b == build_system) {
+                build_systems.push(build_system.to_string());
+            }
+        }
+
+        let include_extensions = if extensions.is_empty() {
+            defaults.analysis.include_extensions.clone()
+        } else {
+            extensions.into_iter().collect()
+        };
+
+        Self {
+            languages,
+            build_systems,
+            include_extensions,
+            exclude_dirs: exclude_dirs.into_iter().collect(),
+        }
+    }
+}
+
+/// How a provider can be used on this machine
+#[derive(Debug, Clone)]
+pub struct ProviderProbe {
+    pub provider: LlmProvider,
+    /// Whether credentials or a local server were found
+    pub available: bool,
+    /// Human readable reason
+    pub detail: String,
+}
+
+/// Check which providers have an API key in the environment, and whether Ollama is running
+pub async fn probe_providers() -> Vec<ProviderProbe> {
+    let mut probes = Vec::new();
+
+    for provider in LlmProvider::ALL {
+        let (available, detail) = match provider {
+            LlmProvider::Ollama => {
+                let base_url = std::env::var("OLLAMA_HOST")
+                    .unwrap_or_else(|_| ollama::DEFAULT_BASE_URL.to_string());
+                let mut llm = Config::default().llm;
+                llm.base_url = Some(base_url);
+                let base_url = ollama::base_url(&llm);
+                match ollama::list_models(&base_url).await {
+                    Ok(models) => (!models.is_empty(), format!("{} model(s) at {}", models.len(), base_url)),
+                    Err(_) => (false, format!("not running at {}", base_url)),
+                }
+            }
+            LlmProvider::OpenAICompatible => match std::env::var("OPENAI_COMPATIBLE_BASE_URL") {
+                Ok(url) => (true, url),
+                Err(_) => (false, "OPENAI_COMPATIBLE_BASE_URL not set".to_string()),
+            },
+            _ => {
+                let var = provider.api_key_env_var().unwrap_or_default();
+                if std::env::var(var).is_ok_and(|key| !key.is_empty()) {
+                    (true, format!("{} set", var))
+                } else {
+                    (false, format!("{} not set", var))
+                }
+            }
+        };
+        probes.push(ProviderProbe { provider, available, detail });
+    }
+
+    probes
+}
+
+/// Options of the `init` wizard
+#[derive(Debug, Clone, Default)]
+pub struct InitOptions {
+    /// Accept every suggestion without prompting
+    pub yes: bool,
+    /// Overwrite an existing configuration
+    pub force: bool,
+    /// Skip the connectivity check
+    pub skip_check: bool,
+}
+
+/// Run the wizard and return the path of the written configuration
+pub async fn run(repo_path: &Path, options: &InitOptions) -> Result<PathBuf> {
+    let mut prompt = Prompter::new(options.yes);
+    let config_path = repo_path.join(REPO_CONFIG_FILE);
+
+    if config_path.exists()
+        && !options.force
+        && !prompt.confirm(&format!("{} already exists, overwrite it?", config_path.display()), false)?
+    {
+        return Err(Error::ConfigError(format!(
+            "{} already exists (use --force to overwrite)",
+            config_path.display()
+        )));
+    }
+
+    let mut config = Config::default();
+
+    println!("🔍 Surveying {}...", repo_path.display());
+    let survey = RepoSurvey::scan(repo_path, &config);
+    if survey.languages.is_empty() {
+        println!("   No known source language found");
+    }
+    for (language, count) in &survey.languages {
+        println!("   {}: {} file(s)", language, count);
+    }
+    if !survey.build_systems.is_empty() {
+        println!("   Build: {}", survey.build_systems.join(", "));
+    }
+
+    config.analysis.include_extensions = prompt.list("Extensions to analyze", &survey.include_extensions)?;
+    config.analysis.exclude_dirs = prompt.list("Directories to exclude", &survey.exclude_dirs)?;
+
+    println!("🤖 LLM providers:");
+    let probes = probe_providers().await;
+    for (index, probe) in probes.iter().enumerate() {
+        let mark = if probe.available { "✓" } else { " " };
+        println!("  {} {:>2}. {:<18} {}", mark, index + 1, probe.provider.id(), probe.detail);
+    }
+    let suggested = probes
+        .iter()
+        .position(|probe| probe.available)
+        .unwrap_or(0);
+    let choice = prompt.choose("Provider", probes.len(), suggested)?;
+    config.llm.provider = probes[choice].provider.clone();
+    let provider = config.llm.provider.clone();
+
+    if let Some(var) = provider.base_url_env_var() {
+        let default = std::env::var(var).ok().or_else(|| match provider {
+            LlmProvider::Ollama => Some(ollama::DEFAULT_BASE_URL.to_string()),
+            LlmProvider::OpenAICompatible => Some(providers::openai_compatible::DEFAULT_BASE_URL.to_string()),
+            _ => None,
+        });
+        let base_url = prompt.ask("Base URL", default.as_deref().unwrap_or_default())?;
+        config.llm.base_url = Some(base_url).filter(|url| !url.is_empty());
+    }
+
+    let default_model = match provider {
+        LlmProvider::Ollama => {
+            let base_url = ollama::base_url(&config.llm);
+            let models = ollama::list_models(&base_url).await.unwrap_or_default();
+            if !models.is_empty() {
+                let names: Vec<_> = models.iter().map(|m| m.name.as_str()).collect();
+                println!("   Installed: {}", names.join(", "));
+            }
+            ollama::default_model(&models)
+                .map(|m| m.name.clone())
+                .unwrap_or_else(|| provider.default_model().to_string())
+        }
+        _ => provider.default_model().to_string(),
+    };
+    config.llm.model = prompt.ask("Model", &default_model)?;
+
+    // The key is only used for the check; it is never written to the config
+    config.llm.api_key = std::env::var("RAIDME_API_KEY").ok()
+        .or_else(|| provider.api_key_env_var().and_then(|var| std::env::var(var).ok()))
+        .unwrap_or_default();
+
+    if !options.skip_check && prompt.confirm("Test the connection now?", true)? {
+        if let Err(e) = check_connectivity(&mut config).await {
+            println!("❌ {}", e);
+            if !prompt.confirm("Write the configuration anyway?", true)? {
+                return Err(e);
+            }
+        }
+    }
+
+    config.store(repo_path)?;
+
+    if config.llm.api_key.is_empty() && provider.requires_api_key() {
+        if let Some(var) = provider.api_key_env_var() {
+            println!("🔑 Export {} (or pass --api-key) before running an analysis", var);
+        }
+    }
+
+    Ok(config_path)
+}
+
+/// Send a one-line prompt to the configured provider
+pub async fn check_connectivity(config: &mut Config) -> Result<()> {
+    println!("📡 Contacting {} ({})...", config.llm.provider.id(), config.llm.model);
+    providers::resolve(&mut config.llm).await?;
+    config.validate()?;
+
+    let agent = providers::agent_factory(config)?("You are a connectivity check. Answer briefly.");
+    let answer = agent.prompt("Reply with OK.").await?;
+    println!("✅ {} answered: {}", config.llm.provider.id(), answer.trim());
+    Ok(())
+}
+
+/// Line-based questions on stdin, answered with the default when `yes` is set or stdin is closed
+struct Prompter {
+    yes: bool,
+    stdin: std::io::StdinLock<'static>,
+}
+
+impl Prompter {
+    fn new(yes: bool) -> Self {
+        Self { yes, stdin: std::io::stdin().lock() }
+    }
+
+    fn ask(&mut self, question: &str, default: &str) -> Result<String> {
+        if self.yes {
+            println!("{}: {}", question, default);
+            return Ok(default.to_string());
+        }
+
+        print!("{} [{}]: ", question, default);
+        std::io::stdout().flush()?;
+
+        let mut answer = String::new();
+        self.stdin.read_line(&mut answer)?;
+        let answer = answer.trim();
+        Ok(if answer.is_empty() { default } else { answer }.to_string())
+    }
+
+    fn confirm(&mut self, question: &str, default: bool) -> Result<bool> {
+        let answer = self.ask(&format!("{} (y/n)", question), if default { "y" } else { "n" })?;
+        Ok(matches!(answer.to_lowercase().as_str(), "y" | "yes"))
+    }
+
+    fn list(&mut self, question: &str, default: &[String]) -> Result<Vec<String>> {
+        let answer = self.ask(question, &default.join(","))?;
+        Ok(answer
+            .split(',')
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect())
+    }
+
+    /// Pick a 1-based entry among `count`, returned 0-based
+    fn choose(&mut self, question: &str, count: usize, default: usize) -> Result<usize> {
+        loop {
+            let answer = self.ask(question, &(default + 1).to_string())?;
+            match answer.parse::<usize>() {
+                Ok(index) if (1..=count).contains(&index) => return Ok(index - 1),
+                _ => println!("Enter a number between 1 and {}", count),
+            }
+        }
+    }
+}
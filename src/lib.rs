@@ -1,26 +1,53 @@
+// handlebars and sqlx errors make `Error` large; boxing them buys nothing for a CLI
+#![allow(clippy::result_large_err)]
+
 mod analyzer;
+pub mod apis;
+pub mod cargo_workspace;
+pub mod classify;
 pub mod config;
+pub mod config_layers;
+pub mod config_migrate;
+pub mod credentials;
+pub mod diagrams;
+pub mod discovery;
 pub mod error;
 // pub mod generator;
 // pub mod git;
+pub mod imports;
+pub mod init;
 pub mod llm;
+pub mod manifests;
+pub mod mermaid;
+pub mod projects;
+pub mod prompts;
+pub mod providers;
+pub mod redact;
+pub mod runs;
+pub mod schema;
+pub mod symbols;
 // pub mod template;
+pub mod tree;
+pub mod user_context;
 
-pub use analyzer::RepositoryAnalyzer;
+pub use analyzer::{RepositoryAnalyzer, StepStatus};
 pub use config::{Config, LlmProvider};
 pub use error::{Error, Result};
 // pub use generator::KnowledgeGenerator;
 // pub use git::GitRepository;
-pub use llm::LlmClient;
+pub use llm::{AgentRole, LlmClient};
+pub use prompts::{PromptLibrary, PromptVars};
 
-use std::path::{PathBuf};
-use sqlx::{sqlite::SqlitePool, migrate::Migrator};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use sqlx::{sqlite::{SqliteConnectOptions, SqlitePool}, migrate::Migrator};
 //
 /// Main API for the raidme library
 pub struct Raidme {
     config: Config,
     llm_client: LlmClient,
     db: SqlitePool,
+    repo_path: PathBuf,
 }
 
 static MIGRATOR: Migrator = sqlx::migrate!(); // <- macro looks for ./migrations/
@@ -29,43 +56,55 @@ async fn run_migrations(pool: &SqlitePool) -> Result<()> {
     MIGRATOR.run(pool).await.map_err(Error::Migrate)
 }
 
-impl Raidme {
-    /// Create a new Raidme instance with the given configuration
-    pub async fn new(repo_path: PathBuf, config: Config) -> Result<Self> {
-        // // Initialize database connection
-            // Set up database connection
-            let database_path = format!("{}/.raidme.db", repo_path.display());
-            let database_url = format!("sqlite:{}", database_path);
-            let db = SqlitePool::connect(&database_url)
-                .await
-                .map_err(Error::Sqlx)?;
+/// Open (creating and migrating if needed) the knowledge database of a repository
+pub async fn open_database(repo_path: &Path) -> Result<SqlitePool> {
+    let database_path = format!("{}/.raidme.db", repo_path.display());
+    let database_url = format!("sqlite:{}", database_path);
+    let db = SqlitePool::connect_with(
+            SqliteConnectOptions::from_str(&database_url)
+                .map_err(Error::Sqlx)?
+                .create_if_missing(true))
+        .await
+        .map_err(Error::Sqlx)?;
 
+    // Verify or create tables using migration
+    run_migrations(&db).await?;
+    println!("Database: {}", database_path);
 
-            // Verify or create tables using migration
-           run_migrations(&db).await?;
+    Ok(db)
+}
+
+impl Raidme {
+    /// Create a new Raidme instance with the given configuration
+    pub async fn new(repo_path: PathBuf, mut config: Config) -> Result<Self> {
+            let db = open_database(&repo_path).await?;
             println!("Created config: {:?}", config);
-            println!("Database: {}", database_path);
 
-            // Validate config before saving
-            config.validate()?;
+            // Resolve the API key, and pick a model from the server when the provider supports discovery
+            providers::resolve(&mut config.llm).await?;
 
-            // Store the config (excluding API key)
-            config.store(&repo_path)?;
+            config.validate()?;
 
-            let llm_client = LlmClient::new(&config)?;
+            let prompts = PromptLibrary::load(&repo_path)?;
+            let mut vars = PromptVars::detect(&repo_path, &config);
+            vars.user_context = user_context::load(&db).await?;
+            let llm_client = LlmClient::with_prompts(&config, &prompts, &vars)?;
             Ok(Self {
                     config,
                     llm_client,
-                    db
+                    db,
+                    repo_path,
             })
     }
 
-    // /// Analyze a repository and generate knowledge file incrementally
-    // pub async fn analyze_repository<P: AsRef<Path>>(
-    //     &self,
-    //     repo_path: P,
-    //     output_file: Option<&str>,
-    // ) -> Result<()> {
-    //     Ok(())
-    // }
+    /// Analyze the repository and generate the knowledge file incrementally
+    pub async fn analyze(self) -> Result<()> {
+        let analyzer = RepositoryAnalyzer::new(self.config, self.db, self.llm_client, self.repo_path)
+            .await
+            .map_err(|e| Error::Analysis(format!("{:#}", e)))?;
+
+        analyzer.analyze()
+            .await
+            .map_err(|e| Error::Analysis(format!("{:#}", e)))
+    }
 }
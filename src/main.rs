@@ -1,14 +1,22 @@
+#![allow(clippy::result_large_err)]
+
 use clap::{Args, Parser, Subcommand};
 use raidme::{
-    analyzer::RepositoryAnalyzer,
     config::{Config,LlmProvider},
-    Raidme,
+    config_layers::{self, CliOverrides},
+    config_migrate::{self, Severity},
+    credentials::{self, CredentialStore},
+    init::{self, InitOptions},
+    user_context,
     Error,
-    LlmClient,
-    Result
+    PromptLibrary,
+    runs,
+    Raidme,
+    Result,
+    StepStatus
 };
+use std::io::Read;
 use std::path::PathBuf;
-use tokio;
 
 #[derive(Parser)]
 #[command(name = "raidme")]
@@ -21,11 +29,152 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Commands {
+    /// Survey a repository and write its .raidme.toml interactively
+    Init(InitArgs),
+
     /// Analyze a repository and generate knowledge documentation
     Analyze(AnalyzeArgs),
 
     /// Show analysis status
     Status(StatusArgs),
+
+    /// Manage the prompt templates used by the analysis agents
+    #[command(subcommand)]
+    Prompts(PromptsCommand),
+
+    /// Inspect the merged configuration
+    #[command(subcommand)]
+    Config(ConfigCommand),
+
+    /// Manage API keys in the encrypted credential store
+    #[command(subcommand)]
+    Credentials(CredentialsCommand),
+
+    /// Manage the user context pinned into every analysis step
+    #[command(subcommand)]
+    Context(ContextCommand),
+}
+
+#[derive(Args)]
+struct InitArgs {
+    /// Path to the repository
+    #[arg(short, long)]
+    repo_path: PathBuf,
+
+    /// Accept every suggestion without prompting
+    #[arg(short, long)]
+    yes: bool,
+
+    /// Overwrite an existing .raidme.toml
+    #[arg(long)]
+    force: bool,
+
+    /// Do not test the connection to the provider
+    #[arg(long)]
+    skip_check: bool,
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Print the configuration merged from defaults, config files, RAIDME_* variables
+    Show(ConfigShowArgs),
+
+    /// Check the config files for errors, unknown and deprecated keys
+    Validate(RepoArgs),
+}
+
+#[derive(Args)]
+struct ConfigShowArgs {
+    /// Path to the repository
+    #[arg(short, long)]
+    repo_path: PathBuf,
+
+    /// Print where each value comes from
+    #[arg(long)]
+    origin: bool,
+
+    /// Configuration profile to apply
+    #[arg(long)]
+    profile: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum CredentialsCommand {
+    /// Store the API key of a provider (prompted for when --key is not given)
+    Set(CredentialsSetArgs),
+
+    /// Remove the API key of a provider
+    Remove(CredentialsProviderArgs),
+
+    /// List the providers with a stored key
+    List,
+}
+
+#[derive(Args)]
+struct CredentialsSetArgs {
+    /// Provider the key belongs to
+    provider: String,
+
+    /// API key (avoid it, it ends up in the shell history)
+    #[arg(long)]
+    key: Option<String>,
+}
+
+#[derive(Args)]
+struct CredentialsProviderArgs {
+    /// Provider the key belongs to
+    provider: String,
+}
+
+#[derive(Subcommand)]
+enum ContextCommand {
+    /// Set the user context (interactively when neither --text nor --file is given)
+    Set(ContextSetArgs),
+
+    /// Show the current user context
+    Show(RepoArgs),
+
+    /// Remove the user context
+    Clear(RepoArgs),
+}
+
+#[derive(Args)]
+struct ContextSetArgs {
+    /// Path to the repository
+    #[arg(short, long)]
+    repo_path: PathBuf,
+
+    /// Context text
+    #[arg(long, conflicts_with = "file")]
+    text: Option<String>,
+
+    /// Read the context from a file
+    #[arg(long)]
+    file: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct RepoArgs {
+    /// Path to the repository
+    #[arg(short, long)]
+    repo_path: PathBuf,
+}
+
+#[derive(Subcommand)]
+enum PromptsCommand {
+    /// Write the default prompts to .raidme/prompts for customization
+    Dump(PromptsDumpArgs),
+}
+
+#[derive(Args)]
+struct PromptsDumpArgs {
+    /// Path to the repository
+    #[arg(short, long)]
+    repo_path: PathBuf,
+
+    /// Overwrite prompts that were already dumped
+    #[arg(long)]
+    force: bool,
 }
 
 #[derive(Args)]
@@ -34,7 +183,7 @@ struct AnalyzeArgs {
     #[arg(short, long)]
     repo_path: PathBuf,
 
-    /// LLM provider to use (anthropic, openai, openrouter)
+    /// LLM provider to use (anthropic, openai, openrouter, ollama, gemini, mistral, groq, deepseek, xai, azure, openai-compatible)
     #[arg(short, long)]
     provider: Option<String>,
 
@@ -50,14 +199,26 @@ struct AnalyzeArgs {
     #[arg(long)]
     base_url: Option<String>,
 
-    /// Output path for the knowledge file
-    #[arg(short, long, default_value = "README.ai.md")]
-    output: PathBuf,
+    /// Output path for the knowledge file (default: README.ai.md)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
 
-    /// Additional context or instructions for the AI
+    /// Configuration profile to apply ([profiles.<name>] section)
     #[arg(long)]
+    profile: Option<String>,
+
+    /// Additional context or instructions for the AI
+    #[arg(long, conflicts_with_all = ["context_file", "interactive_context"])]
     context: Option<String>,
 
+    /// Read the additional context from a file
+    #[arg(long, conflicts_with = "interactive_context")]
+    context_file: Option<PathBuf>,
+
+    /// Type the additional context interactively before the analysis starts
+    #[arg(long)]
+    interactive_context: bool,
+
     /// Skip git commits for each step (useful for testing)
     #[arg(long)]
     no_commit: bool,
@@ -84,95 +245,238 @@ async fn run() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
+        Commands::Init(args) => {
+            let options = InitOptions {
+                yes: args.yes,
+                force: args.force,
+                skip_check: args.skip_check,
+            };
+            let path = init::run(&args.repo_path, &options).await?;
+            println!("✅ Configuration written to {}", path.display());
+        }
+
         Commands::Analyze(args) => {
             let config = create_config(&args)?;
+            let output_path = config.output_path.clone();
+
+            if let Some(context) = read_context(args.context.clone(), args.context_file.clone(), args.interactive_context)? {
+                let db = raidme::open_database(&args.repo_path).await?;
+                user_context::store(&db, &context).await?;
+                println!("📌 User context updated");
+            }
 
-            let raidme = Raidme::new(repo_path, config).await?;
-            // Set up database connection
+            let raidme = Raidme::new(args.repo_path.clone(), config).await?;
 
             println!("🔍 Starting repository analysis...");
             println!("📁 Repo: {}", args.repo_path.display());
             println!("🤖 Provider: {}", args.provider.as_deref().unwrap_or("default"));
-            println!("📄 Output: {}", args.output.display());
-            let llm_client = LlmClient::new(&config)?;
-            let analyzer = RepositoryAnalyzer::new(config, db, llm_client, &args.repo_path)?;
+            println!("📄 Output: {}", output_path);
 
-            analyzer.analyze().await?;
+            raidme.analyze().await?;
 
             println!("✅ Analysis completed successfully!");
-            println!("📄 Knowledge file generated: {}", args.output.display());
+            println!("📄 Knowledge file generated: {}", output_path);
         }
 
         Commands::Status(args) => {
-            let config = Config::load(&args.repo_path)?;
-            println!("Using config: {:?}", config);
-            // let status = RepoAnalyzer::get_status(&args.repo_path)?;
+            let db = raidme::open_database(&args.repo_path).await?;
+            let recent = runs::recent(&db, 5).await?;
+
             println!("📊 Analysis Status:");
-            // println!("{:#?}", status);
-            println!("dummy");
+            match recent.iter().find(|run| matches!(run.status, StepStatus::Completed)) {
+                Some(run) => println!(
+                    "📄 Knowledge produced on {} by profile {} ({} / {})",
+                    run.completed_at.unwrap_or(run.started_at).format("%Y-%m-%d %H:%M"),
+                    run.profile.as_deref().unwrap_or("<none>"),
+                    run.provider,
+                    run.model
+                ),
+                None => println!("📄 No completed analysis yet"),
+            }
+
+            for run in &recent {
+                println!(
+                    "   {} {:?} profile={} {} / {}",
+                    run.started_at.format("%Y-%m-%d %H:%M"),
+                    run.status,
+                    run.profile.as_deref().unwrap_or("-"),
+                    run.provider,
+                    run.model
+                );
+            }
+        }
+
+        Commands::Config(ConfigCommand::Show(args)) => {
+            let mut cli = CliOverrides::default();
+            if let Some(profile) = &args.profile {
+                cli.profile(profile);
+            }
+            let layered = config_layers::load_with(&args.repo_path, cli)?;
+            for (key, value, origin) in layered.entries() {
+                let value = if key == "llm.api_key" { "\"********\"".to_string() } else { value.to_string() };
+                if args.origin {
+                    println!("{} = {}  # {}", key, value, origin);
+                } else {
+                    println!("{} = {}", key, value);
+                }
+            }
+        }
+
+        Commands::Credentials(CredentialsCommand::Set(args)) => {
+            let provider: LlmProvider = args.provider.parse()?;
+            let mut store = if CredentialStore::exists() {
+                CredentialStore::open(&credentials::passphrase(false)?)?
+            } else {
+                println!("Creating {}", CredentialStore::path()?.display());
+                CredentialStore::create(&credentials::passphrase(true)?)
+            };
+
+            let key = match args.key {
+                Some(key) => key,
+                None => rpassword::prompt_password(format!("🔑 {} API key: ", provider.id()))?,
+            };
+            if key.trim().is_empty() {
+                return Err(Error::Auth("Empty API key".to_string()));
+            }
+
+            store.set(&provider, key.trim());
+            store.save()?;
+            println!("🔑 Stored the {} API key", provider.id());
+        }
+
+        Commands::Credentials(CredentialsCommand::Remove(args)) => {
+            let provider: LlmProvider = args.provider.parse()?;
+            if !CredentialStore::exists() {
+                println!("No credential store");
+                return Ok(());
+            }
+            let mut store = CredentialStore::open(&credentials::passphrase(false)?)?;
+            if store.remove(&provider) {
+                store.save()?;
+                println!("🗑️ Removed the {} API key", provider.id());
+            } else {
+                println!("No {} API key stored", provider.id());
+            }
+        }
+
+        Commands::Credentials(CredentialsCommand::List) => {
+            if !CredentialStore::exists() {
+                println!("No credential store");
+                return Ok(());
+            }
+            let store = CredentialStore::open(&credentials::passphrase(false)?)?;
+            for provider in store.providers() {
+                println!("🔑 {}", provider);
+            }
+        }
+
+        Commands::Config(ConfigCommand::Validate(args)) => {
+            let mut files = vec![args.repo_path.join(config_layers::REPO_CONFIG_FILE)];
+            files.extend(Config::default_config_path().ok());
+
+            let mut errors = 0;
+            for path in files.iter().filter(|path| path.exists()) {
+                let issues = config_migrate::validate_file(path)?;
+                println!("📝 {}", path.display());
+                if issues.is_empty() {
+                    println!("✅ no issue");
+                }
+                for issue in &issues {
+                    println!("   {}", issue);
+                }
+                errors += issues.iter().filter(|i| i.severity == Severity::Error).count();
+            }
+
+            if errors == 0 {
+                if let Err(e) = config_layers::load_read_only(&args.repo_path).and_then(|layered| layered.config.validate()) {
+                    println!("❌ merged configuration: {}", e);
+                    errors += 1;
+                }
+            }
+
+            if errors > 0 {
+                return Err(Error::ConfigError(format!("{} error(s) found", errors)));
+            }
+        }
+
+        Commands::Context(ContextCommand::Set(args)) => {
+            let interactive = args.text.is_none() && args.file.is_none();
+            let context = read_context(args.text, args.file, interactive)?
+                .ok_or_else(|| Error::Generic("User context is empty".to_string()))?;
+            let db = raidme::open_database(&args.repo_path).await?;
+            user_context::store(&db, &context).await?;
+            println!("📌 User context updated, it will be used by the next analysis");
+        }
+
+        Commands::Context(ContextCommand::Show(args)) => {
+            let db = raidme::open_database(&args.repo_path).await?;
+            match user_context::load(&db).await? {
+                Some(context) => println!("{}", context),
+                None => println!("No user context set"),
+            }
+        }
+
+        Commands::Context(ContextCommand::Clear(args)) => {
+            let db = raidme::open_database(&args.repo_path).await?;
+            user_context::clear(&db).await?;
+            println!("🗑️ User context removed");
+        }
+
+        Commands::Prompts(PromptsCommand::Dump(args)) => {
+            let written = PromptLibrary::dump(&args.repo_path, args.force)?;
+            for path in &written {
+                println!("📝 {}", path.display());
+            }
+            println!("✅ {} prompt(s) written, edit them to customize the analysis", written.len());
         }
     }
 
     Ok(())
 }
 
-fn create_config(args: &AnalyzeArgs) -> Result<Config> {
-    // Load the base config from repo or global file (or default)
-    let mut config = Config::load_or_default(&args.repo_path)?;
+/// Read user context from inline text, a file, or stdin; `None` when nothing (or only blanks) was given
+fn read_context(text: Option<String>, file: Option<PathBuf>, interactive: bool) -> Result<Option<String>> {
+    let context = if let Some(text) = text {
+        text
+    } else if let Some(file) = file {
+        std::fs::read_to_string(file)?
+    } else if interactive {
+        println!("✍️ Enter the context for the analysis, then press Ctrl-D:");
+        let mut input = String::new();
+        std::io::stdin().read_to_string(&mut input)?;
+        input
+    } else {
+        return Ok(None);
+    };
 
-    // Override LLM provider if passed in CLI args
+    let context = context.trim();
+    Ok((!context.is_empty()).then(|| context.to_string()))
+}
+
+fn create_config(args: &AnalyzeArgs) -> Result<Config> {
+    // Flags are the last layer, above the config files and RAIDME_* variables
+    let mut cli = CliOverrides::default();
     if let Some(provider) = &args.provider {
-        config.llm.provider = match provider.as_str() {
-            "anthropic" => LlmProvider::Anthropic,
-            "openai" => LlmProvider::OpenAI,
-            "openrouter" => LlmProvider::OpenRouter,
-            _ => return Err(Error::InvalidProvider(provider.clone())),
-        };
+        cli.set_provider(&provider.parse::<LlmProvider>()?)?;
+    }
+    if let Some(api_key) = &args.api_key {
+        cli.set("llm.api_key", api_key.as_str());
+    }
+    if let Some(model) = args.model.as_deref().filter(|m| !m.is_empty()) {
+        cli.set("llm.model", model);
     }
-
-    // Override api_key with CLI or env vars or keep existing
-    config.llm.api_key = args.api_key.clone()
-        .or_else(|| std::env::var("RAIDME_API_KEY").ok())
-        .or_else(|| match config.llm.provider {
-            LlmProvider::Anthropic => std::env::var("ANTHROPIC_API_KEY").ok(),
-            LlmProvider::OpenAI => std::env::var("OPENAI_API_KEY").ok(),
-            LlmProvider::OpenRouter => std::env::var("OPENROUTER_API_KEY").ok(),
-            LlmProvider::Ollama => None,
-        })
-        .unwrap_or_else(|| config.llm.api_key.clone());
-
-    // Override base URL if specified
     if let Some(base_url) = &args.base_url {
-        config.llm.base_url = Some(base_url.clone());
+        cli.set("llm.base_url", base_url.as_str());
+    }
+    if let Some(output) = &args.output {
+        cli.set("output_path", output.to_string_lossy().as_ref());
+    }
+    if let Some(profile) = &args.profile {
+        cli.profile(profile);
     }
 
-    // Determine the model with the following precedence:
-    // 1. CLI argument
-    // 2. Config value (non-empty)
-    // 3. Provider default
-
-    config.llm.model = args.model.clone()
-        .filter(|m| !m.is_empty())
-        .or_else(|| {
-            if !config.llm.model.is_empty() {
-                Some(config.llm.model.clone())
-            } else {
-                None
-            }
-        })
-        .unwrap_or_else(|| match config.llm.provider {
-            LlmProvider::Anthropic => "claude-3-sonnet-20240229".to_string(),
-            LlmProvider::OpenAI => "gpt-4-turbo-preview".to_string(),
-            LlmProvider::OpenRouter => "anthropic/claude-3-sonnet".to_string(),
-            LlmProvider::Ollama => "ollama-default".to_string(),
-        });
-    
-    // You can override other parts similarly, e.g. context, commit_each_step, etc.
-
+    let config = config_layers::load_with(&args.repo_path, cli)?.config;
     config.validate()?;
 
-    // Store the config (excluding API key)
-    config.store(&args.repo_path)?;
-
     Ok(config)
 }
@@ -0,0 +1,870 @@
+//! Build manifests parsed into structured facts.
+//!
+//! Cargo.toml, package.json, go.mod, pyproject.toml, setup.cfg, pom.xml and
+//! build.gradle(.kts) files found by discovery are reduced to their name,
+//! targets, dependencies and scripts; go.work, pnpm-workspace.yaml and
+//! settings.gradle(.kts) to the members they declare. The facts are stored in the `manifests`
+//! table and sent to the LLM as compact summaries instead of raw files.
+
+use crate::discovery::Entry;
+use crate::error::{Error, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as Json;
+use sqlx::{Row, SqlitePool};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::LazyLock;
+use toml::Value;
+
+/// Dependencies listed per kind in a summary before eliding the rest
+const SUMMARY_DEPENDENCIES: usize = 40;
+
+static GRADLE_ROOT_NAME: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?m)^\s*rootProject\.name\s*=\s*["']([^"']+)["']"#).expect("valid gradle settings name pattern")
+});
+
+static GRADLE_INCLUDE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?m)^\s*include\b(.*)$"#).expect("valid gradle include pattern"));
+
+static GRADLE_PROJECT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"["']([^"']+)["']"#).expect("valid gradle project pattern"));
+
+static GRADLE_DEPENDENCY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?m)^\s*(implementation|api|compileOnly|runtimeOnly|annotationProcessor|kapt|testImplementation|testRuntimeOnly|androidTestImplementation)\s*\(?\s*["']([^"':]+):([^"':]+)(?::([^"']+))?["']"#,
+    )
+    .expect("valid gradle dependency pattern")
+});
+
+static GRADLE_PLUGIN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?m)^\s*(?:id\s*\(?\s*["']([\w.-]+)["']|apply\s+plugin:\s*["']([\w.-]+)["'])"#)
+        .expect("valid gradle plugin pattern")
+});
+
+static GRADLE_VERSION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?m)^\s*version\s*=\s*["']([^"']+)["']"#).expect("valid gradle version pattern")
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Ecosystem {
+    Cargo,
+    Npm,
+    Go,
+    Python,
+    Maven,
+    Gradle,
+}
+
+impl Ecosystem {
+    /// Ecosystem of a manifest file name
+    pub fn for_file(name: &str) -> Option<Self> {
+        match name {
+            "Cargo.toml" => Some(Ecosystem::Cargo),
+            "package.json" | "pnpm-workspace.yaml" => Some(Ecosystem::Npm),
+            "go.mod" | "go.work" => Some(Ecosystem::Go),
+            "pyproject.toml" | "setup.cfg" => Some(Ecosystem::Python),
+            "pom.xml" => Some(Ecosystem::Maven),
+            "build.gradle" | "build.gradle.kts" | "settings.gradle" | "settings.gradle.kts" => Some(Ecosystem::Gradle),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Ecosystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Ecosystem::Cargo => "Cargo",
+            Ecosystem::Npm => "npm",
+            Ecosystem::Go => "Go",
+            Ecosystem::Python => "Python",
+            Ecosystem::Maven => "Maven",
+            Ecosystem::Gradle => "Gradle",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Something the project builds: a library, a binary, a console script…
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Target {
+    pub kind: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyKind {
+    Normal,
+    Dev,
+    Build,
+    Peer,
+    Optional,
+    Indirect,
+}
+
+impl DependencyKind {
+    fn label(&self) -> &'static str {
+        match self {
+            DependencyKind::Normal => "Dependencies",
+            DependencyKind::Dev => "Dev dependencies",
+            DependencyKind::Build => "Build dependencies",
+            DependencyKind::Peer => "Peer dependencies",
+            DependencyKind::Optional => "Optional dependencies",
+            DependencyKind::Indirect => "Indirect dependencies",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dependency {
+    pub name: String,
+    /// Version requirement, path or other source as written
+    pub version: Option<String>,
+    pub kind: DependencyKind,
+}
+
+/// Facts extracted from one manifest
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Path relative to the repository root
+    pub path: String,
+    pub ecosystem: Option<Ecosystem>,
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub targets: Vec<Target>,
+    pub dependencies: Vec<Dependency>,
+    /// Dependencies shared with the members (`[workspace.dependencies]`), not used by the manifest itself
+    #[serde(default)]
+    pub workspace_dependencies: Vec<Dependency>,
+    pub scripts: BTreeMap<String, String>,
+    pub features: Vec<String>,
+    /// Workspace members or modules, as globs or paths
+    pub members: Vec<String>,
+}
+
+impl Manifest {
+    fn new(path: &str, ecosystem: Ecosystem) -> Self {
+        Self { path: path.to_string(), ecosystem: Some(ecosystem), ..Default::default() }
+    }
+
+    fn target(&mut self, kind: &str, name: impl Into<String>) {
+        self.targets.push(Target { kind: kind.to_string(), name: name.into() });
+    }
+
+    fn dependency(&mut self, name: impl Into<String>, version: Option<String>, kind: DependencyKind) {
+        self.dependencies.push(Dependency { name: name.into(), version, kind });
+    }
+
+    /// Compact text summary sent to the LLM
+    pub fn summary(&self) -> String {
+        let ecosystem = self.ecosystem.map(|e| e.to_string()).unwrap_or_default();
+        let mut summary = match (&self.name, &self.version) {
+            (Some(name), Some(version)) => format!("{} project {} {} ({})\n", ecosystem, name, version, self.path),
+            (Some(name), None) => format!("{} project {} ({})\n", ecosystem, name, self.path),
+            _ => format!("{} project ({})\n", ecosystem, self.path),
+        };
+
+        if !self.members.is_empty() {
+            summary.push_str(&format!("Members: {}\n", self.members.join(", ")));
+        }
+        if !self.targets.is_empty() {
+            let targets: Vec<String> = self.targets.iter().map(|t| format!("{} {}", t.kind, t.name)).collect();
+            summary.push_str(&format!("Targets: {}\n", targets.join(", ")));
+        }
+        if !self.features.is_empty() {
+            summary.push_str(&format!("Features: {}\n", self.features.join(", ")));
+        }
+
+        let mut by_kind: BTreeMap<DependencyKind, Vec<&Dependency>> = BTreeMap::new();
+        for dependency in &self.dependencies {
+            by_kind.entry(dependency.kind).or_default().push(dependency);
+        }
+        for (kind, dependencies) in by_kind {
+            let mut listed: Vec<String> = dependencies
+                .iter()
+                .take(SUMMARY_DEPENDENCIES)
+                .map(|d| match &d.version {
+                    Some(version) => format!("{} {}", d.name, version),
+                    None => d.name.clone(),
+                })
+                .collect();
+            if dependencies.len() > SUMMARY_DEPENDENCIES {
+                listed.push(format!("… {} more", dependencies.len() - SUMMARY_DEPENDENCIES));
+            }
+            summary.push_str(&format!("{} ({}): {}\n", kind.label(), dependencies.len(), listed.join(", ")));
+        }
+
+        if !self.workspace_dependencies.is_empty() {
+            let mut listed: Vec<&str> = self.workspace_dependencies
+                .iter()
+                .take(SUMMARY_DEPENDENCIES)
+                .map(|d| d.name.as_str())
+                .collect();
+            let more = format!("… {} more", self.workspace_dependencies.len().saturating_sub(SUMMARY_DEPENDENCIES));
+            if self.workspace_dependencies.len() > SUMMARY_DEPENDENCIES {
+                listed.push(&more);
+            }
+            summary.push_str(&format!("Workspace dependencies ({}): {}\n", self.workspace_dependencies.len(), listed.join(", ")));
+        }
+
+        if !self.scripts.is_empty() {
+            summary.push_str("Scripts:\n");
+            for (name, command) in &self.scripts {
+                summary.push_str(&format!("  {}: {}\n", name, command));
+            }
+        }
+        summary
+    }
+}
+
+/// Parse every manifest among the discovered entries, skipping binary, generated and vendored ones
+pub fn discover(entries: &[Entry]) -> Vec<Manifest> {
+    let mut manifests = Vec::new();
+    for entry in entries.iter().filter(|e| !e.is_dir && e.class.is_none()) {
+        let relative = entry.relative.to_string_lossy();
+        match parse(&entry.path, &relative) {
+            Ok(Some(manifest)) => manifests.push(manifest),
+            Ok(None) => {}
+            Err(e) => println!("⚠️ Skipping manifest {}: {}", relative, e),
+        }
+    }
+    manifests
+}
+
+/// Parse a manifest, `None` when the file name is not a known manifest
+pub fn parse(path: &Path, relative: &str) -> Result<Option<Manifest>> {
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let Some(ecosystem) = Ecosystem::for_file(&name) else {
+        return Ok(None);
+    };
+    let content = std::fs::read_to_string(path)?;
+    let mut manifest = Manifest::new(relative, ecosystem);
+    let dir = path.parent().unwrap_or(Path::new(""));
+
+    match name.as_str() {
+        "Cargo.toml" => parse_cargo(&content, dir, &mut manifest)?,
+        "package.json" => parse_package_json(&content, &mut manifest)?,
+        "go.mod" => parse_go_mod(&content, &mut manifest),
+        "pyproject.toml" => parse_pyproject(&content, &mut manifest)?,
+        "setup.cfg" => parse_setup_cfg(&content, &mut manifest),
+        "pom.xml" => parse_pom(&content, &mut manifest)?,
+        "go.work" => parse_go_work(&content, &mut manifest),
+        "pnpm-workspace.yaml" => parse_pnpm_workspace(&content, &mut manifest)?,
+        "settings.gradle" | "settings.gradle.kts" => parse_gradle_settings(&content, &mut manifest),
+        _ => parse_gradle(&content, dir, &mut manifest),
+    }
+    Ok(Some(manifest))
+}
+
+fn invalid(e: impl fmt::Display) -> Error {
+    Error::Analysis(e.to_string())
+}
+
+fn toml_str(table: &Value, key: &str) -> Option<String> {
+    table.get(key).and_then(Value::as_str).map(str::to_string)
+}
+
+fn toml_strings(value: Option<&Value>) -> Vec<String> {
+    value
+        .and_then(Value::as_array)
+        .map(|items| items.iter().filter_map(Value::as_str).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+fn parse_cargo(content: &str, dir: &Path, manifest: &mut Manifest) -> Result<()> {
+    let cargo: Value = toml::from_str(content)?;
+
+    if let Some(package) = cargo.get("package") {
+        manifest.name = toml_str(package, "name");
+        // `version.workspace = true` is inherited and left out
+        manifest.version = toml_str(package, "version");
+    }
+    if let Some(workspace) = cargo.get("workspace") {
+        manifest.members = toml_strings(workspace.get("members"));
+        cargo_dependencies(workspace.get("dependencies"), DependencyKind::Normal, &mut manifest.workspace_dependencies);
+    }
+
+    let package_name = manifest.name.clone().unwrap_or_default();
+    match cargo.get("lib") {
+        Some(lib) => manifest.target("lib", toml_str(lib, "name").unwrap_or_else(|| package_name.replace('-', "_"))),
+        None if dir.join("src/lib.rs").is_file() => manifest.target("lib", package_name.replace('-', "_")),
+        None => {}
+    }
+    let bins = cargo.get("bin").and_then(Value::as_array);
+    if bins.is_none() && dir.join("src/main.rs").is_file() {
+        manifest.target("bin", package_name.clone());
+    }
+    for (kind, key) in [("bin", "bin"), ("example", "example"), ("test", "test"), ("bench", "bench")] {
+        for target in cargo.get(key).and_then(Value::as_array).into_iter().flatten() {
+            if let Some(name) = toml_str(target, "name") {
+                manifest.target(kind, name);
+            }
+        }
+    }
+
+    if let Some(features) = cargo.get("features").and_then(Value::as_table) {
+        manifest.features = features.keys().cloned().collect();
+    }
+
+    cargo_dependencies(cargo.get("dependencies"), DependencyKind::Normal, &mut manifest.dependencies);
+    cargo_dependencies(cargo.get("dev-dependencies"), DependencyKind::Dev, &mut manifest.dependencies);
+    cargo_dependencies(cargo.get("build-dependencies"), DependencyKind::Build, &mut manifest.dependencies);
+    Ok(())
+}
+
+fn cargo_dependencies(table: Option<&Value>, kind: DependencyKind, dependencies: &mut Vec<Dependency>) {
+    let Some(table) = table.and_then(Value::as_table) else { return };
+    for (name, spec) in table {
+        let version = match spec {
+            Value::String(version) => Some(version.clone()),
+            Value::Table(spec) => spec.get("version").and_then(Value::as_str).map(str::to_string)
+                .or_else(|| spec.get("path").and_then(Value::as_str).map(|path| format!("path:{}", path)))
+                .or_else(|| spec.get("git").and_then(Value::as_str).map(|git| format!("git:{}", git)))
+                .or_else(|| spec.get("workspace").and_then(Value::as_bool).filter(|w| *w).map(|_| "workspace".to_string())),
+            _ => None,
+        };
+        let optional = spec.get("optional").and_then(Value::as_bool).unwrap_or(false);
+        let name = spec.get("package").and_then(Value::as_str).unwrap_or(name).to_string();
+        dependencies.push(Dependency { name, version, kind: if optional { DependencyKind::Optional } else { kind } });
+    }
+}
+
+fn parse_package_json(content: &str, manifest: &mut Manifest) -> Result<()> {
+    let package: Json = serde_json::from_str(content)?;
+    let text = |key: &str| package.get(key).and_then(Json::as_str).map(str::to_string);
+
+    manifest.name = text("name");
+    manifest.version = text("version");
+
+    match package.get("bin") {
+        Some(Json::String(_)) => manifest.target("bin", manifest.name.clone().unwrap_or_default()),
+        Some(Json::Object(bins)) => {
+            for name in bins.keys() {
+                manifest.target("bin", name.clone());
+            }
+        }
+        _ => {}
+    }
+    if let Some(main) = text("main") {
+        manifest.target("main", main);
+    }
+
+    if let Some(scripts) = package.get("scripts").and_then(Json::as_object) {
+        for (name, command) in scripts {
+            if let Some(command) = command.as_str() {
+                manifest.scripts.insert(name.clone(), command.to_string());
+            }
+        }
+    }
+
+    // Either a list of globs or `{ "packages": [...] }` (yarn)
+    let workspaces = package.get("workspaces");
+    let workspaces = workspaces.and_then(|w| w.get("packages")).or(workspaces);
+    if let Some(members) = workspaces.and_then(Json::as_array) {
+        manifest.members = members.iter().filter_map(Json::as_str).map(str::to_string).collect();
+    }
+
+    for (key, kind) in [
+        ("dependencies", DependencyKind::Normal),
+        ("devDependencies", DependencyKind::Dev),
+        ("peerDependencies", DependencyKind::Peer),
+        ("optionalDependencies", DependencyKind::Optional),
+    ] {
+        for (name, version) in package.get(key).and_then(Json::as_object).into_iter().flatten() {
+            manifest.dependency(name, version.as_str().map(str::to_string), kind);
+        }
+    }
+    Ok(())
+}
+
+/// `use` directives of a Go workspace
+fn parse_go_work(content: &str, manifest: &mut Manifest) {
+    let mut in_use = false;
+    for line in content.lines() {
+        let line = line.split_once("//").map_or(line, |(line, _)| line).trim();
+        let dir = if in_use {
+            if line == ")" {
+                in_use = false;
+                continue;
+            }
+            Some(line)
+        } else if line == "use (" {
+            in_use = true;
+            None
+        } else if let Some(go) = line.strip_prefix("go ") {
+            manifest.version = Some(format!("go {}", go.trim()));
+            None
+        } else {
+            line.strip_prefix("use ")
+        };
+        if let Some(dir) = dir.map(|dir| dir.trim().trim_matches('"')).filter(|dir| !dir.is_empty()) {
+            manifest.members.push(dir.trim_start_matches("./").to_string());
+        }
+    }
+}
+
+/// `packages` globs of a pnpm workspace
+fn parse_pnpm_workspace(content: &str, manifest: &mut Manifest) -> Result<()> {
+    let workspace: serde_yaml::Value = serde_yaml::from_str(content).map_err(invalid)?;
+    if let Some(packages) = workspace.get("packages").and_then(serde_yaml::Value::as_sequence) {
+        manifest.members = packages.iter().filter_map(serde_yaml::Value::as_str).map(str::to_string).collect();
+    }
+    Ok(())
+}
+
+/// Root project name and `include`d projects of a Gradle build, `:a:b` being `a/b`
+fn parse_gradle_settings(content: &str, manifest: &mut Manifest) {
+    manifest.name = GRADLE_ROOT_NAME.captures(content).map(|caps| caps[1].to_string());
+
+    for caps in GRADLE_INCLUDE.captures_iter(content) {
+        for project in GRADLE_PROJECT.captures_iter(&caps[1]) {
+            manifest.members.push(project[1].trim_start_matches(':').replace(':', "/"));
+        }
+    }
+}
+
+fn parse_go_mod(content: &str, manifest: &mut Manifest) {
+    let mut in_require = false;
+    for line in content.lines() {
+        let (line, comment) = match line.split_once("//") {
+            Some((line, comment)) => (line.trim(), comment.trim()),
+            None => (line.trim(), ""),
+        };
+
+        let require = if in_require {
+            if line == ")" {
+                in_require = false;
+                continue;
+            }
+            Some(line)
+        } else if let Some(module) = line.strip_prefix("module ") {
+            manifest.name = Some(module.trim().trim_matches('"').to_string());
+            None
+        } else if let Some(go) = line.strip_prefix("go ") {
+            manifest.version = Some(format!("go {}", go.trim()));
+            None
+        } else if line == "require (" {
+            in_require = true;
+            None
+        } else {
+            line.strip_prefix("require ")
+        };
+
+        let mut fields = require.unwrap_or_default().split_whitespace();
+        if let (Some(module), Some(version)) = (fields.next(), fields.next()) {
+            let kind = if comment == "indirect" { DependencyKind::Indirect } else { DependencyKind::Normal };
+            manifest.dependency(module, Some(version.to_string()), kind);
+        }
+    }
+}
+
+/// Name of a PEP 508 requirement and the rest of it
+fn split_requirement(requirement: &str) -> (String, Option<String>) {
+    let requirement = requirement.trim();
+    let end = requirement.find(|c: char| !(c.is_alphanumeric() || "-_.".contains(c))).unwrap_or(requirement.len());
+    let rest = requirement[end..].trim();
+    (requirement[..end].to_string(), (!rest.is_empty()).then(|| rest.to_string()))
+}
+
+fn parse_pyproject(content: &str, manifest: &mut Manifest) -> Result<()> {
+    let pyproject: Value = toml::from_str(content)?;
+
+    if let Some(project) = pyproject.get("project") {
+        manifest.name = toml_str(project, "name");
+        manifest.version = toml_str(project, "version");
+        for requirement in toml_strings(project.get("dependencies")) {
+            let (name, version) = split_requirement(&requirement);
+            manifest.dependency(name, version, DependencyKind::Normal);
+        }
+        for (extra, requirements) in project.get("optional-dependencies").and_then(Value::as_table).into_iter().flatten() {
+            manifest.features.push(extra.clone());
+            for requirement in toml_strings(Some(requirements)) {
+                let (name, version) = split_requirement(&requirement);
+                manifest.dependency(name, version, DependencyKind::Optional);
+            }
+        }
+        for (name, entry_point) in project.get("scripts").and_then(Value::as_table).into_iter().flatten() {
+            manifest.target("script", name.clone());
+            if let Some(entry_point) = entry_point.as_str() {
+                manifest.scripts.insert(name.clone(), entry_point.to_string());
+            }
+        }
+    }
+
+    if let Some(poetry) = pyproject.get("tool").and_then(|tool| tool.get("poetry")) {
+        manifest.name = manifest.name.take().or_else(|| toml_str(poetry, "name"));
+        manifest.version = manifest.version.take().or_else(|| toml_str(poetry, "version"));
+        poetry_dependencies(poetry.get("dependencies"), DependencyKind::Normal, manifest);
+        poetry_dependencies(poetry.get("dev-dependencies"), DependencyKind::Dev, manifest);
+        for group in poetry.get("group").and_then(Value::as_table).into_iter().flatten().map(|(_, g)| g) {
+            poetry_dependencies(group.get("dependencies"), DependencyKind::Dev, manifest);
+        }
+        for (name, entry_point) in poetry.get("scripts").and_then(Value::as_table).into_iter().flatten() {
+            manifest.target("script", name.clone());
+            if let Some(entry_point) = entry_point.as_str() {
+                manifest.scripts.insert(name.clone(), entry_point.to_string());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn poetry_dependencies(table: Option<&Value>, kind: DependencyKind, manifest: &mut Manifest) {
+    for (name, spec) in table.and_then(Value::as_table).into_iter().flatten() {
+        if name == "python" {
+            continue;
+        }
+        let version = match spec {
+            Value::String(version) => Some(version.clone()),
+            spec => toml_str(spec, "version").or_else(|| toml_str(spec, "path").map(|p| format!("path:{}", p))),
+        };
+        manifest.dependency(name, version, kind);
+    }
+}
+
+fn parse_setup_cfg(content: &str, manifest: &mut Manifest) {
+    // INI sections with indented continuation lines for lists
+    let mut section = String::new();
+    let mut key = String::new();
+    let mut values: BTreeMap<(String, String), Vec<String>> = BTreeMap::new();
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with(';') {
+            continue;
+        }
+        if let Some(name) = trimmed.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+            section = name.trim().to_string();
+            continue;
+        }
+        if line.starts_with(char::is_whitespace) {
+            values.entry((section.clone(), key.clone())).or_default().push(trimmed.to_string());
+        } else if let Some((name, value)) = trimmed.split_once(['=', ':']) {
+            key = name.trim().to_string();
+            let value = value.trim();
+            let entry = values.entry((section.clone(), key.clone())).or_default();
+            if !value.is_empty() {
+                entry.push(value.to_string());
+            }
+        }
+    }
+
+    let get = |section: &str, key: &str| values.get(&(section.to_string(), key.to_string()));
+    manifest.name = get("metadata", "name").and_then(|v| v.first().cloned());
+    manifest.version = get("metadata", "version").and_then(|v| v.first().cloned());
+    for (key, kind) in [("install_requires", DependencyKind::Normal), ("setup_requires", DependencyKind::Build), ("tests_require", DependencyKind::Dev)] {
+        for requirement in get("options", key).into_iter().flatten() {
+            let (name, version) = split_requirement(requirement);
+            manifest.dependency(name, version, kind);
+        }
+    }
+    for entry_point in get("options.entry_points", "console_scripts").into_iter().flatten() {
+        if let Some((name, target)) = entry_point.split_once('=') {
+            manifest.target("script", name.trim());
+            manifest.scripts.insert(name.trim().to_string(), target.trim().to_string());
+        }
+    }
+}
+
+fn parse_pom(content: &str, manifest: &mut Manifest) -> Result<()> {
+    let document = roxmltree::Document::parse(content).map_err(invalid)?;
+    let project = document.root_element();
+
+    let group = text(project, "groupId").or_else(|| child(project, "parent").and_then(|p| text(p, "groupId")));
+    let artifact = text(project, "artifactId");
+    manifest.name = match (group, artifact) {
+        (Some(group), Some(artifact)) => Some(format!("{}:{}", group, artifact)),
+        (None, artifact) => artifact,
+        (_, None) => None,
+    };
+    manifest.version = text(project, "version");
+
+    let packaging = text(project, "packaging").unwrap_or_else(|| "jar".to_string());
+    if packaging != "pom" {
+        manifest.target(&packaging, text(project, "artifactId").unwrap_or_default());
+    }
+    if let Some(modules) = child(project, "modules") {
+        manifest.members = modules.children().filter(|c| c.has_tag_name("module")).filter_map(|c| c.text()).map(str::to_string).collect();
+    }
+
+    if let Some(dependencies) = child(project, "dependencies") {
+        for dependency in dependencies.children().filter(|c| c.has_tag_name("dependency")) {
+            let name = format!(
+                "{}:{}",
+                text(dependency, "groupId").unwrap_or_default(),
+                text(dependency, "artifactId").unwrap_or_default()
+            );
+            let kind = match text(dependency, "scope").as_deref() {
+                Some("test") => DependencyKind::Dev,
+                Some("provided") => DependencyKind::Build,
+                _ if text(dependency, "optional").as_deref() == Some("true") => DependencyKind::Optional,
+                _ => DependencyKind::Normal,
+            };
+            manifest.dependency(name, text(dependency, "version"), kind);
+        }
+    }
+    Ok(())
+}
+
+fn child<'a, 'input>(node: roxmltree::Node<'a, 'input>, name: &str) -> Option<roxmltree::Node<'a, 'input>> {
+    node.children().find(|c| c.has_tag_name(name))
+}
+
+fn text(node: roxmltree::Node, name: &str) -> Option<String> {
+    child(node, name).and_then(|c| c.text()).map(|t| t.trim().to_string())
+}
+
+fn parse_gradle(content: &str, dir: &Path, manifest: &mut Manifest) {
+    manifest.name = dir.file_name().map(|n| n.to_string_lossy().to_string());
+
+    for caps in GRADLE_DEPENDENCY.captures_iter(content) {
+        let kind = match &caps[1] {
+            configuration if configuration.starts_with("test") || configuration.starts_with("androidTest") => DependencyKind::Dev,
+            "compileOnly" | "annotationProcessor" | "kapt" => DependencyKind::Build,
+            _ => DependencyKind::Normal,
+        };
+        manifest.dependency(format!("{}:{}", &caps[2], &caps[3]), caps.get(4).map(|v| v.as_str().to_string()), kind);
+    }
+
+    for caps in GRADLE_PLUGIN.captures_iter(content) {
+        let id = caps.get(1).or(caps.get(2)).map(|m| m.as_str()).unwrap_or_default();
+        match id {
+            "application" => manifest.target("application", manifest.name.clone().unwrap_or_default()),
+            "java-library" | "org.jetbrains.kotlin.jvm" => manifest.target("lib", manifest.name.clone().unwrap_or_default()),
+            "com.android.application" => manifest.target("android-app", manifest.name.clone().unwrap_or_default()),
+            "com.android.library" => manifest.target("android-lib", manifest.name.clone().unwrap_or_default()),
+            _ => {}
+        }
+    }
+
+    manifest.version = GRADLE_VERSION.captures(content).map(|caps| caps[1].to_string());
+}
+
+/// Replace the stored manifests with the given ones
+pub async fn store(db: &SqlitePool, manifests: &[Manifest]) -> Result<()> {
+    sqlx::query("DELETE FROM manifests").execute(db).await.map_err(Error::Sqlx)?;
+    for manifest in manifests {
+        sqlx::query(
+            "INSERT INTO manifests (path, ecosystem, name, facts, updated_at) VALUES ($1, $2, $3, $4, $5)"
+        )
+        .bind(&manifest.path)
+        .bind(manifest.ecosystem.map(|e| e.to_string()))
+        .bind(&manifest.name)
+        .bind(serde_json::to_string(manifest)?)
+        .bind(chrono::Utc::now())
+        .execute(db)
+        .await
+        .map_err(Error::Sqlx)?;
+    }
+    Ok(())
+}
+
+/// Stored manifests, by path
+pub async fn load(db: &SqlitePool) -> Result<Vec<Manifest>> {
+    let rows = sqlx::query("SELECT facts FROM manifests ORDER BY path")
+        .fetch_all(db)
+        .await
+        .map_err(Error::Sqlx)?;
+
+    rows.into_iter()
+        .map(|row| {
+            let facts: String = row.try_get("facts").map_err(Error::Sqlx)?;
+            Ok(serde_json::from_str(&facts)?)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parse `content` written as `relative` below a fresh directory
+    fn manifest(relative: &str, content: &str) -> Manifest {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(relative);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, content).unwrap();
+        parse(&path, relative).unwrap().expect("known manifest")
+    }
+
+    fn dependencies(dependencies: &[Dependency]) -> Vec<(&str, Option<&str>, DependencyKind)> {
+        dependencies.iter().map(|d| (d.name.as_str(), d.version.as_deref(), d.kind)).collect()
+    }
+
+    #[test]
+    fn parses_cargo_workspace_dependencies() {
+        let root = manifest("Cargo.toml", r#"
+[workspace]
+members = ["crates/*"]
+
+[workspace.dependencies]
+serde = "1"
+tokio = { version = "1.40", features = ["full"] }
+my-core = { path = "crates/core" }
+"#);
+        assert_eq!(root.ecosystem, Some(Ecosystem::Cargo));
+        assert_eq!(root.name, None);
+        assert_eq!(root.members, ["crates/*"]);
+        assert!(root.dependencies.is_empty());
+        assert_eq!(
+            dependencies(&root.workspace_dependencies),
+            [
+                ("my-core", Some("path:crates/core"), DependencyKind::Normal),
+                ("serde", Some("1"), DependencyKind::Normal),
+                ("tokio", Some("1.40"), DependencyKind::Normal),
+            ]
+        );
+
+        let member = manifest("crates/app/Cargo.toml", r#"
+[package]
+name = "my-app"
+version.workspace = true
+
+[[bin]]
+name = "app"
+
+[dependencies]
+serde.workspace = true
+my-core = { workspace = true }
+
+[dev-dependencies]
+tempfile = "3"
+"#);
+        assert_eq!(member.name.as_deref(), Some("my-app"));
+        assert_eq!(member.version, None);
+        assert_eq!(member.targets, [Target { kind: "bin".to_string(), name: "app".to_string() }]);
+        assert_eq!(
+            dependencies(&member.dependencies),
+            [
+                ("my-core", Some("workspace"), DependencyKind::Normal),
+                ("serde", Some("workspace"), DependencyKind::Normal),
+                ("tempfile", Some("3"), DependencyKind::Dev),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_pep621_and_poetry_projects() {
+        let pep621 = manifest("pyproject.toml", r#"
+[project]
+name = "shop"
+version = "0.3.0"
+dependencies = ["requests>=2.31", "click"]
+
+[project.optional-dependencies]
+postgres = ["psycopg[binary]~=3.1"]
+
+[project.scripts]
+shop = "shop.cli:main"
+"#);
+        assert_eq!(pep621.name.as_deref(), Some("shop"));
+        assert_eq!(pep621.version.as_deref(), Some("0.3.0"));
+        assert_eq!(pep621.features, ["postgres"]);
+        assert_eq!(pep621.scripts.get("shop").map(String::as_str), Some("shop.cli:main"));
+        assert_eq!(
+            dependencies(&pep621.dependencies),
+            [
+                ("requests", Some(">=2.31"), DependencyKind::Normal),
+                ("click", None, DependencyKind::Normal),
+                ("psycopg", Some("[binary]~=3.1"), DependencyKind::Optional),
+            ]
+        );
+
+        let poetry = manifest("pyproject.toml", r#"
+[tool.poetry]
+name = "shop"
+version = "0.4.0"
+
+[tool.poetry.dependencies]
+python = "^3.11"
+django = "^5.0"
+shared = { path = "../shared" }
+
+[tool.poetry.group.test.dependencies]
+pytest = "^8.0"
+"#);
+        assert_eq!(poetry.name.as_deref(), Some("shop"));
+        assert_eq!(poetry.version.as_deref(), Some("0.4.0"));
+        assert_eq!(
+            dependencies(&poetry.dependencies),
+            [
+                ("django", Some("^5.0"), DependencyKind::Normal),
+                ("shared", Some("path:../shared"), DependencyKind::Normal),
+                ("pytest", Some("^8.0"), DependencyKind::Dev),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_pom_modules() {
+        let pom = manifest("pom.xml", r#"<?xml version="1.0"?>
+<project xmlns="http://maven.apache.org/POM/4.0.0">
+  <groupId>com.example</groupId>
+  <artifactId>shop-parent</artifactId>
+  <version>1.2.0</version>
+  <packaging>pom</packaging>
+  <modules>
+    <module>shop-api</module>
+    <module>shop-web</module>
+  </modules>
+  <dependencies>
+    <dependency>
+      <groupId>org.junit.jupiter</groupId>
+      <artifactId>junit-jupiter</artifactId>
+      <version>5.10.0</version>
+      <scope>test</scope>
+    </dependency>
+  </dependencies>
+</project>
+"#);
+        assert_eq!(pom.ecosystem, Some(Ecosystem::Maven));
+        assert_eq!(pom.name.as_deref(), Some("com.example:shop-parent"));
+        assert_eq!(pom.version.as_deref(), Some("1.2.0"));
+        assert!(pom.targets.is_empty());
+        assert_eq!(pom.members, ["shop-api", "shop-web"]);
+        assert_eq!(
+            dependencies(&pom.dependencies),
+            [("org.junit.jupiter:junit-jupiter", Some("5.10.0"), DependencyKind::Dev)]
+        );
+    }
+
+    #[test]
+    fn parses_gradle_settings_and_builds() {
+        let settings = manifest("settings.gradle.kts", r#"
+rootProject.name = "shop"
+include(":app", ":libs:core")
+include 'legacy'
+"#);
+        assert_eq!(settings.ecosystem, Some(Ecosystem::Gradle));
+        assert_eq!(settings.name.as_deref(), Some("shop"));
+        assert_eq!(settings.members, ["app", "libs/core", "legacy"]);
+
+        let build = manifest("app/build.gradle.kts", r#"
+plugins {
+    id("application")
+}
+
+version = "2.0.1"
+
+dependencies {
+    implementation("com.squareup.okhttp3:okhttp:4.12.0")
+    compileOnly("org.projectlombok:lombok:1.18.30")
+    testImplementation("junit:junit:4.13.2")
+}
+"#);
+        assert_eq!(build.name.as_deref(), Some("app"));
+        assert_eq!(build.version.as_deref(), Some("2.0.1"));
+        assert_eq!(build.targets, [Target { kind: "application".to_string(), name: "app".to_string() }]);
+        assert_eq!(
+            dependencies(&build.dependencies),
+            [
+                ("com.squareup.okhttp3:okhttp", Some("4.12.0"), DependencyKind::Normal),
+                ("org.projectlombok:lombok", Some("1.18.30"), DependencyKind::Build),
+                ("junit:junit", Some("4.13.2"), DependencyKind::Dev),
+            ]
+        );
+    }
+}
@@ -0,0 +1,569 @@
+//! Validation of the Mermaid diagrams written by the agents.
+//!
+//! A line-oriented parser for the subsets of flowcharts (`graph`/`flowchart`),
+//! sequence, class and ER diagrams the agents produce. It catches the
+//! mistakes that break rendering — unquoted labels with brackets, unknown
+//! arrows, unbalanced blocks — and reports the first one with its line, so
+//! the diagram can be sent back to the agent for repair. Other diagram types
+//! are accepted without validation.
+
+use regex::Regex;
+use std::fmt;
+use std::ops::Range;
+use std::sync::LazyLock;
+
+/// Flowchart node shapes, `(open, closes)`, longest openers first
+const SHAPES: &[(&str, &[&str])] = &[
+    ("(((", &[")))"]),
+    ("([", &["])"]),
+    ("[[", &["]]"]),
+    ("[(", &[")]"]),
+    ("[/", &["/]", "\\]"]),
+    ("[\\", &["\\]", "/]"]),
+    ("((", &["))"]),
+    ("{{", &["}}"]),
+    ("[", &["]"]),
+    ("(", &[")"]),
+    ("{", &["}"]),
+    (">", &["]"]),
+];
+
+/// Flowchart statements starting with a keyword, checked no further
+const FLOWCHART_KEYWORDS: &[&str] = &["classDef", "class", "style", "linkStyle", "click", "direction"];
+
+static FLOWCHART_LINK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^\s*(?:[<xo]?(?:--\s[^-|]+?\s--+|==\s[^=|]+?\s==+|-\.\s[^.|]+?\s\.-+)[>xo]?|[<xo]?(?:-{2,}|={2,}|-\.+-|~{3,})[>xo]?)\s*(?:\|[^|]*\|)?\s*",
+    )
+    .expect("valid flowchart link pattern")
+});
+
+static SEQUENCE_MESSAGE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^([^:\s][^:]*?)\s*(<<-->>|<<->>|-->>|->>|-->|->|--x|-x|--\)|-\))[+-]?\s*([^:\s][^:]*?)\s*:.*$")
+        .expect("valid sequence message pattern")
+});
+
+static SEQUENCE_NOTE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^note\s+(left of|right of|over)\s+[^:]+:.*$").expect("valid sequence note pattern")
+});
+
+static CLASS_NAME: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"^class\s+[\w.-]+(~[^~]+~)?(\["[^"]*"\])?(:::\w+)?\s*(\{.*)?$"#).expect("valid class pattern")
+});
+
+static CLASS_RELATION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"^[\w.-]+(~[^~]+~)?\s*("[^"]*"\s*)?(<\||\*|o|<)?(--|\.\.)(\|>|\*|o|>)?\s*("[^"]*"\s*)?[\w.-]+(~[^~]+~)?\s*(:.*)?$"#,
+    )
+    .expect("valid class relation pattern")
+});
+
+static CLASS_MEMBER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[\w.-]+\s*:\s*\S.*$").expect("valid class member pattern"));
+
+static ER_ENTITY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"^("[^"]+"|[\w-]+)(\["[^"]*"\])?\s*(\{)?$"#).expect("valid entity pattern")
+});
+
+static ER_ATTRIBUTE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"^[\w\-\[\]()]+(,\s*\d+\))?\s+\*?[\w-]+(\s+(PK|FK|UK)(\s*,\s*(PK|FK|UK))*)?(\s+"[^"]*")?$"#)
+        .expect("valid attribute pattern")
+});
+
+static ER_RELATION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"^("[^"]+"|[\w-]+)\s*(\|o|\|\||\}o|\}\|)(--|\.\.)(o\||\|\||o\{|\|\{)\s*("[^"]+"|[\w-]+)\s*:\s*\S.*$"#)
+        .expect("valid relation pattern")
+});
+
+/// Why a diagram does not parse
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MermaidError {
+    /// 1-based line within the diagram, the type declaration being line 1
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for MermaidError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// A fenced `mermaid` block of a Markdown document
+#[derive(Debug, Clone)]
+pub struct Block {
+    /// Byte range of the whole block, fences included
+    pub range: Range<usize>,
+    /// Diagram source between the fences
+    pub source: String,
+}
+
+/// Fenced `mermaid` blocks of a Markdown document; an unclosed block runs to the end
+pub fn blocks(markdown: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut open: Option<(usize, usize)> = None;
+    let mut offset = 0;
+
+    for line in markdown.split_inclusive('\n') {
+        let trimmed = line.trim();
+        match open {
+            None if trimmed.starts_with("```mermaid") => open = Some((offset, offset + line.len())),
+            Some((start, body)) if trimmed == "```" => {
+                let end = offset + line.trim_end_matches(['\n', '\r']).len();
+                blocks.push(Block { range: start..end, source: markdown[body..offset].to_string() });
+                open = None;
+            }
+            _ => {}
+        }
+        offset += line.len();
+    }
+
+    if let Some((start, body)) = open {
+        blocks.push(Block { range: start..markdown.len(), source: markdown[body..].to_string() });
+    }
+    blocks
+}
+
+/// Check a diagram, returning the first syntax error
+pub fn validate(source: &str) -> Result<(), MermaidError> {
+    let lines: Vec<(usize, &str)> = significant_lines(source);
+    let Some(&(header_line, header)) = lines.first() else {
+        return Err(MermaidError { line: 1, message: "empty diagram".to_string() });
+    };
+
+    let kind = header.split_whitespace().next().unwrap_or_default();
+    let body = &lines[1..];
+    match kind {
+        "graph" | "flowchart" => {
+            let direction = header.split_whitespace().nth(1).map(|d| d.trim_end_matches(';'));
+            if let Some(direction) = direction.filter(|d| !["TB", "TD", "BT", "RL", "LR"].contains(d)) {
+                return Err(error(header_line, format!("unknown direction '{}', use TB, TD, BT, RL or LR", direction)));
+            }
+            validate_flowchart(body)
+        }
+        "sequenceDiagram" => validate_sequence(body),
+        "classDiagram" | "classDiagram-v2" => validate_class(body),
+        "erDiagram" => validate_er(body),
+        // Mermaid keeps adding diagram types and renderers, the others are left to it
+        _ => Ok(()),
+    }
+}
+
+/// Request sent back to the agent to repair a diagram
+pub fn repair_request(source: &str, error: &MermaidError) -> String {
+    format!(
+        "=== Invalid Mermaid Diagram ===\n```mermaid\n{}\n```\n\n=== Parse Error ===\n{}\n\n=== Instructions ===\n\
+         This Mermaid diagram from your previous answer does not render. Fix the syntax error without changing \
+         what the diagram shows: quote labels containing brackets or punctuation, e.g. A[\"Cache (Redis)\"]. \
+         Answer with the corrected diagram only, in a single ```mermaid block.\n",
+        source.trim_end(),
+        error
+    )
+}
+
+/// Non-blank lines with their 1-based number, without comments, directives and front matter
+fn significant_lines(source: &str) -> Vec<(usize, &str)> {
+    let mut lines = Vec::new();
+    let mut front_matter = false;
+    for (i, line) in source.lines().enumerate() {
+        let trimmed = line.trim();
+        if lines.is_empty() && trimmed == "---" {
+            front_matter = !front_matter;
+            continue;
+        }
+        if front_matter || trimmed.is_empty() || trimmed.starts_with("%%") {
+            continue;
+        }
+        lines.push((i + 1, trimmed));
+    }
+    lines
+}
+
+fn error(line: usize, message: impl Into<String>) -> MermaidError {
+    MermaidError { line, message: message.into() }
+}
+
+fn validate_flowchart(lines: &[(usize, &str)]) -> Result<(), MermaidError> {
+    let mut subgraphs = Vec::new();
+    for &(number, line) in lines {
+        for statement in split_statements(line) {
+            let keyword = statement.split_whitespace().next().unwrap_or_default();
+            match keyword {
+                "subgraph" => {
+                    if statement.split_whitespace().nth(1).is_none() {
+                        return Err(error(number, "subgraph without an identifier"));
+                    }
+                    subgraphs.push(number);
+                }
+                "end" if statement == "end" => {
+                    if subgraphs.pop().is_none() {
+                        return Err(error(number, "'end' without an open subgraph"));
+                    }
+                }
+                _ if FLOWCHART_KEYWORDS.contains(&keyword) => {
+                    if statement.split_whitespace().nth(1).is_none() {
+                        return Err(error(number, format!("'{}' without arguments", keyword)));
+                    }
+                }
+                _ => parse_chain(statement).map_err(|message| error(number, message))?,
+            }
+        }
+    }
+
+    match subgraphs.last() {
+        Some(opened) => Err(error(*opened, "subgraph is never closed with 'end'")),
+        None => Ok(()),
+    }
+}
+
+/// Statements of a flowchart line, split on `;` outside quotes
+fn split_statements(line: &str) -> Vec<&str> {
+    let mut statements = Vec::new();
+    let mut quoted = false;
+    let mut start = 0;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ';' if !quoted => {
+                statements.push(&line[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    statements.push(&line[start..]);
+    statements.into_iter().map(str::trim).filter(|s| !s.is_empty()).collect()
+}
+
+/// `nodes (link nodes)*`, where `nodes` is `node (& node)*`
+fn parse_chain(statement: &str) -> Result<(), String> {
+    let mut rest = statement;
+    loop {
+        loop {
+            rest = parse_node(rest)?.trim_start();
+            match rest.strip_prefix('&') {
+                Some(after) => rest = after.trim_start(),
+                None => break,
+            }
+        }
+        if rest.is_empty() {
+            return Ok(());
+        }
+        let link = FLOWCHART_LINK
+            .find(rest)
+            .ok_or_else(|| format!("expected an arrow such as '-->' before '{}'", excerpt(rest)))?;
+        rest = &rest[link.end()..];
+        if rest.is_empty() {
+            return Err("arrow without a target node".to_string());
+        }
+    }
+}
+
+/// Parse one node, returning what follows it
+fn parse_node(input: &str) -> Result<&str, String> {
+    let id_len = node_id_len(input);
+    if id_len == 0 {
+        return Err(format!("expected a node identifier at '{}'", excerpt(input)));
+    }
+    let id = &input[..id_len];
+    if id == "end" {
+        return Err("'end' cannot be a node identifier, rename the node (e.g. 'End')".to_string());
+    }
+
+    let mut rest = &input[id_len..];
+    if let Some(after) = rest.strip_prefix("@{") {
+        let close = after.find('}').ok_or("unclosed '@{' shape")?;
+        rest = &after[close + 1..];
+    } else if let Some((open, closes)) = SHAPES.iter().find(|(open, _)| rest.starts_with(open)) {
+        rest = parse_label(&rest[open.len()..], open, closes)?;
+    }
+
+    if let Some(after) = rest.strip_prefix(":::") {
+        let len = node_id_len(after);
+        if len == 0 {
+            return Err("':::' without a class name".to_string());
+        }
+        rest = &after[len..];
+    }
+    Ok(rest)
+}
+
+/// Parse a node label up to its closing delimiter, returning what follows it
+fn parse_label<'a>(input: &'a str, open: &str, closes: &[&str]) -> Result<&'a str, String> {
+    let trimmed = input.trim_start();
+    if let Some(quoted) = trimmed.strip_prefix('"') {
+        let end = quoted.find('"').ok_or("unclosed '\"' in a node label")?;
+        let after = quoted[end + 1..].trim_start();
+        return closes
+            .iter()
+            .find_map(|close| after.strip_prefix(close))
+            .ok_or_else(|| format!("expected '{}' after the quoted label of '{}…'", closes[0], open));
+    }
+
+    let (end, close) = closes
+        .iter()
+        .filter_map(|close| input.find(close).map(|end| (end, *close)))
+        .min()
+        .ok_or_else(|| format!("node label opened with '{}' is never closed with '{}'", open, closes[0]))?;
+    let label = &input[..end];
+    if let Some(c) = label.chars().find(|c| matches!(c, '[' | ']' | '(' | ')' | '{' | '}' | '"')) {
+        return Err(format!("unquoted node label '{}' contains '{}', wrap the label in double quotes", label.trim(), c));
+    }
+    Ok(&input[end + close.len()..])
+}
+
+/// Length of the node identifier at the start of the input
+fn node_id_len(input: &str) -> usize {
+    let chars: Vec<(usize, char)> = input.char_indices().collect();
+    for (i, &(offset, c)) in chars.iter().enumerate() {
+        let next = chars.get(i + 1).map(|(_, c)| *c);
+        let is_id = c.is_alphanumeric() || c == '_' || c == '.' && next.is_some_and(char::is_alphanumeric)
+            || c == '-' && next.is_some_and(|n| n.is_alphanumeric() || n == '_');
+        if !is_id {
+            return offset;
+        }
+    }
+    input.len()
+}
+
+fn excerpt(text: &str) -> String {
+    let excerpt: String = text.chars().take(30).collect();
+    if excerpt.len() < text.len() {
+        format!("{}…", excerpt)
+    } else {
+        excerpt
+    }
+}
+
+fn validate_sequence(lines: &[(usize, &str)]) -> Result<(), MermaidError> {
+    let mut blocks: Vec<(usize, &str)> = Vec::new();
+    for &(number, line) in lines {
+        let keyword = line.split_whitespace().next().unwrap_or_default();
+        match keyword {
+            "participant" | "actor" | "destroy" | "activate" | "deactivate" => {
+                if line.split_whitespace().nth(1).is_none() {
+                    return Err(error(number, format!("'{}' without a participant", keyword)));
+                }
+            }
+            "create" => {
+                if !matches!(line.split_whitespace().nth(1), Some("participant" | "actor")) {
+                    return Err(error(number, "'create' must be followed by 'participant' or 'actor'"));
+                }
+            }
+            "autonumber" | "title" | "link" | "links" | "properties" | "details" => {}
+            "loop" | "alt" | "opt" | "par" | "critical" | "break" | "rect" | "box" => blocks.push((number, keyword)),
+            "else" | "and" | "option" => {
+                let expected = match keyword {
+                    "else" => "alt",
+                    "and" => "par",
+                    _ => "critical",
+                };
+                if blocks.last().map(|(_, block)| *block) != Some(expected) {
+                    return Err(error(number, format!("'{}' outside of an '{}' block", keyword, expected)));
+                }
+            }
+            "end" => {
+                if blocks.pop().is_none() {
+                    return Err(error(number, "'end' without an open block"));
+                }
+            }
+            _ if keyword.eq_ignore_ascii_case("note") => {
+                if !SEQUENCE_NOTE.is_match(line) {
+                    return Err(error(number, "notes are written 'Note right of A: text', 'left of' or 'over A,B'"));
+                }
+            }
+            _ => {
+                if !SEQUENCE_MESSAGE.is_match(line) {
+                    return Err(error(
+                        number,
+                        format!("expected a message such as 'A->>B: text', got '{}'", excerpt(line)),
+                    ));
+                }
+            }
+        }
+    }
+
+    match blocks.last() {
+        Some((opened, block)) => Err(error(*opened, format!("'{}' block is never closed with 'end'", block))),
+        None => Ok(()),
+    }
+}
+
+fn validate_class(lines: &[(usize, &str)]) -> Result<(), MermaidError> {
+    // Class bodies and namespaces, by the line opening them
+    let mut open: Vec<(usize, bool)> = Vec::new();
+    for &(number, line) in lines {
+        let in_body = open.last().is_some_and(|(_, is_class)| *is_class);
+        if line == "}" {
+            if open.pop().is_none() {
+                return Err(error(number, "'}' without an open class body"));
+            }
+            continue;
+        }
+        if in_body {
+            if line.contains('{') {
+                return Err(error(number, "class body is never closed with '}' before this line"));
+            }
+            continue;
+        }
+
+        let keyword = line.split_whitespace().next().unwrap_or_default();
+        match keyword {
+            "class" => {
+                if !CLASS_NAME.is_match(line) {
+                    return Err(error(number, format!("invalid class declaration '{}'", excerpt(line))));
+                }
+                if line.ends_with('{') {
+                    open.push((number, true));
+                }
+            }
+            "namespace" => {
+                if !line.ends_with('{') {
+                    return Err(error(number, "namespace must open a block with '{'"));
+                }
+                open.push((number, false));
+            }
+            "direction" | "note" | "classDef" | "style" | "cssClass" | "callback" | "click" | "link" | "title" => {}
+            _ if line.starts_with("<<") => {
+                if !line.contains(">>") {
+                    return Err(error(number, "annotation is written '<<interface>> Name'"));
+                }
+            }
+            _ => {
+                if !CLASS_RELATION.is_match(line) && !CLASS_MEMBER.is_match(line) {
+                    return Err(error(
+                        number,
+                        format!("expected a relation such as 'A <|-- B' or a member 'A : +field', got '{}'", excerpt(line)),
+                    ));
+                }
+            }
+        }
+    }
+
+    match open.last() {
+        Some((opened, _)) => Err(error(*opened, "block is never closed with '}'")),
+        None => Ok(()),
+    }
+}
+
+fn validate_er(lines: &[(usize, &str)]) -> Result<(), MermaidError> {
+    let mut entity: Option<usize> = None;
+    for &(number, line) in lines {
+        if entity.is_some() {
+            if line == "}" {
+                entity = None;
+            } else if !ER_ATTRIBUTE.is_match(line) {
+                return Err(error(
+                    number,
+                    format!("expected an attribute such as 'string name PK \"comment\"', got '{}'", excerpt(line)),
+                ));
+            }
+            continue;
+        }
+
+        let keyword = line.split_whitespace().next().unwrap_or_default();
+        if matches!(keyword, "title" | "direction" | "classDef" | "style" | "class") {
+            continue;
+        }
+        if let Some(captures) = ER_ENTITY.captures(line) {
+            if captures.get(3).is_some() {
+                entity = Some(number);
+            }
+            continue;
+        }
+        if !ER_RELATION.is_match(line) {
+            return Err(error(
+                number,
+                format!("expected a relation such as 'A ||--o{{ B : has', got '{}'", excerpt(line)),
+            ));
+        }
+    }
+
+    match entity {
+        Some(opened) => Err(error(opened, "entity is never closed with '}'")),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_line(source: &str) -> usize {
+        validate(source).expect_err("diagram should not validate").line
+    }
+
+    #[test]
+    fn accepts_flowcharts() {
+        let source = "flowchart LR\n    %% comment\n    A[\"Cache (Redis)\"] -->|reads| B(Service) & C{Decide?}\n    \
+                      subgraph core [Core]\n        D[[Queue]] -. async .-> E((Worker)):::hot\n    end\n    \
+                      classDef hot fill:#f96\n    B --> D; C --- E";
+        assert_eq!(validate(source), Ok(()));
+        assert_eq!(validate("graph TD\n    a.b --> c-d"), Ok(()));
+    }
+
+    #[test]
+    fn rejects_broken_flowcharts() {
+        assert_eq!(error_line("graph XY\n    A --> B"), 1);
+        assert!(validate("flowchart TD\n    A[Cache (Redis)] --> B").unwrap_err().message.contains("double quotes"));
+        assert_eq!(error_line("flowchart TD\n    A --> B\n    B -->"), 3);
+        assert_eq!(error_line("flowchart TD\n    A --> end"), 2);
+        assert_eq!(error_line("flowchart TD\n    subgraph one\n    A --> B"), 2);
+        assert_eq!(error_line("flowchart TD\n    A --> B\n    end"), 3);
+        assert_eq!(error_line("flowchart TD\n    A[label --> B"), 2);
+    }
+
+    #[test]
+    fn validates_sequence_diagrams() {
+        let source = "sequenceDiagram\n    participant C as Client\n    autonumber\n    C->>+S: request\n    \
+                      alt cached\n        S-->>C: hit\n    else\n        S--)C: miss\n    end\n    Note over C,S: done";
+        assert_eq!(validate(source), Ok(()));
+        assert_eq!(error_line("sequenceDiagram\n    C->>S request"), 2);
+        assert_eq!(error_line("sequenceDiagram\n    else\n    end"), 2);
+        assert_eq!(error_line("sequenceDiagram\n    loop every minute\n    A->>B: ping"), 2);
+    }
+
+    #[test]
+    fn validates_class_diagrams() {
+        let source = "classDiagram\n    class Animal {\n        +String name\n        +speak() void\n    }\n    \
+                      <<interface>> Animal\n    Animal <|-- Dog\n    Dog \"1\" *-- \"many\" Leg : has\n    Dog : +bark()";
+        assert_eq!(validate(source), Ok(()));
+        assert_eq!(error_line("classDiagram\n    class Animal {\n    +name\n"), 2);
+        assert_eq!(error_line("classDiagram\n    Animal <|== Dog"), 2);
+    }
+
+    #[test]
+    fn validates_er_diagrams() {
+        let source = "erDiagram\n    USER {\n        int id PK\n        string email UK \"login\"\n        \
+                      varchar(255) name\n    }\n    USER ||--o{ ORDER : places\n    \"line item\" }|..|| ORDER : \"belongs to\"";
+        assert_eq!(validate(source), Ok(()));
+        assert_eq!(error_line("erDiagram\n    USER {\n        id\n    }"), 3);
+        assert_eq!(error_line("erDiagram\n    USER ||--o{ ORDER"), 2);
+        assert_eq!(error_line("erDiagram\n    USER {\n        int id"), 2);
+    }
+
+    #[test]
+    fn accepts_other_diagram_types_unchecked() {
+        for header in ["flowchart-elk TD", "zenuml", "treemap-beta", "architecture-beta", "stateDiagram-v2", "radar-beta"] {
+            assert_eq!(validate(&format!("{}\n    anything [goes ((here", header)), Ok(()), "{}", header);
+        }
+    }
+
+    #[test]
+    fn skips_front_matter_and_rejects_empty_diagrams() {
+        assert_eq!(validate("---\ntitle: Flow\n---\nflowchart TD\n    A --> B"), Ok(()));
+        assert_eq!(error_line("---\ntitle: Flow\n---\nflowchart TD\n    A --> "), 5);
+        assert_eq!(validate("  \n%% nothing").unwrap_err().message, "empty diagram");
+    }
+
+    #[test]
+    fn finds_fenced_blocks() {
+        let markdown = "# Doc\n\n```mermaid\ngraph TD\n    A --> B\n```\n\ntext\n\n```mermaid\nsequenceDiagram\n";
+        let blocks = blocks(markdown);
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].source, "graph TD\n    A --> B\n");
+        assert_eq!(&markdown[blocks[0].range.clone()], "```mermaid\ngraph TD\n    A --> B\n```");
+        assert_eq!(blocks[1].source, "sequenceDiagram\n");
+        assert_eq!(blocks[1].range.end, markdown.len());
+    }
+}
@@ -0,0 +1,105 @@
+//! Sub-projects of a monorepo: Cargo members, npm and pnpm workspaces, Go
+//! workspace modules, Gradle and Maven modules… Each one is analyzed on its
+//! own and gets its own knowledge file, linked from the top-level one.
+
+use crate::manifests::{Ecosystem, Manifest};
+use globset::{GlobBuilder, GlobMatcher};
+use std::collections::BTreeMap;
+use std::path::{Component, Path};
+
+/// A package root below the repository root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Project {
+    pub name: String,
+    /// Directory relative to the repository root
+    pub dir: String,
+    pub ecosystem: Option<Ecosystem>,
+}
+
+/// Directory names whose packages are samples or test data rather than sub-projects
+const NON_PROJECT_DIRS: &[&str] = &["examples", "example", "fixtures", "testdata", "tests", "test", "benches", "samples"];
+
+/// Package roots of a monorepo, by directory; the repository root itself is not one.
+///
+/// Members declared by workspace manifests (Cargo `workspace.members`, npm and
+/// pnpm `workspaces`, `go.work`, Gradle `include`, Maven `<modules>`) are the
+/// sub-projects. Only when nothing is declared does every named nested
+/// manifest outside example and test directories count as one.
+pub fn detect(manifests: &[Manifest]) -> Vec<Project> {
+    let mut members = Vec::new();
+    let mut excluded = Vec::new();
+    for manifest in manifests {
+        let root = manifest_dir(&manifest.path);
+        for member in &manifest.members {
+            match member.strip_prefix('!') {
+                Some(pattern) => excluded.extend(member_matcher(&root, pattern)),
+                None => members.extend(member_matcher(&root, member)),
+            }
+        }
+    }
+    let declared = !members.is_empty();
+
+    let mut projects: BTreeMap<String, Project> = BTreeMap::new();
+    for manifest in manifests {
+        let Some(name) = &manifest.name else { continue };
+        let dir = manifest_dir(&manifest.path);
+        if dir.is_empty() {
+            continue;
+        }
+        let is_project = match declared {
+            true => members.iter().any(|m| m.is_match(&dir)) && !excluded.iter().any(|m| m.is_match(&dir)),
+            false => !dir.split('/').any(|component| NON_PROJECT_DIRS.contains(&component)),
+        };
+        if !is_project {
+            continue;
+        }
+        // Manifests are sorted by path, so the first one of a directory wins (Cargo.toml before package.json)
+        projects.entry(dir.clone()).or_insert_with(|| Project {
+            name: name.clone(),
+            dir,
+            ecosystem: manifest.ecosystem,
+        });
+    }
+    projects.into_values().collect()
+}
+
+/// Matcher of the directories of a workspace member pattern relative to `root`;
+/// `*` does not cross `/`, as in Cargo and npm
+pub(crate) fn member_matcher(root: &str, pattern: &str) -> Option<GlobMatcher> {
+    let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+    let pattern = if root.is_empty() { pattern.to_string() } else { format!("{}/{}", root, pattern) };
+    GlobBuilder::new(&pattern).literal_separator(true).build().ok().map(|glob| glob.compile_matcher())
+}
+
+/// Directory of a manifest, empty for the repository root
+fn manifest_dir(path: &str) -> String {
+    Path::new(path).parent().map(|dir| dir.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+/// Section of the top-level knowledge file linking to the sub-projects
+pub fn index_section(projects: &[Project], output_path: &str) -> String {
+    let mut section = String::from("## Sub-projects\n\n");
+    for project in projects {
+        let link = relative_link(output_path, &format!("{}/{}", project.dir, output_path));
+        let ecosystem = project.ecosystem.map(|e| format!(" ({})", e)).unwrap_or_default();
+        section.push_str(&format!("- [{}]({}){} — `{}`\n", project.name, link, ecosystem, project.dir));
+    }
+    section
+}
+
+/// Line of a sub-project knowledge file linking back to the top-level one
+pub fn parent_link(project: &Project, output_path: &str) -> String {
+    let link = relative_link(&format!("{}/{}", project.dir, output_path), output_path);
+    format!("_Part of a monorepo, see the [repository overview]({})._\n", link)
+}
+
+/// Link from the document at `from` to the one at `to`, both relative to the repository root
+pub(crate) fn relative_link(from: &str, to: &str) -> String {
+    let from_dir: Vec<Component> = Path::new(from).parent().map(|p| p.components().collect()).unwrap_or_default();
+    let to: Vec<Component> = Path::new(to).components().collect();
+
+    let common = from_dir.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut link: Vec<String> = vec!["..".to_string(); from_dir.len() - common];
+    link.extend(to[common..].iter().map(|c| c.as_os_str().to_string_lossy().to_string()));
+    link.join("/")
+}
@@ -0,0 +1,209 @@
+//! Prompt library for the analysis agents.
+//!
+//! Default prompts are embedded from `prompts/*.md`. A repository can override
+//! any of them, or add prompts for custom roles, with `.raidme/prompts/<role>.md`.
+//! Prompts are handlebars templates rendered with `PromptVars`.
+
+use crate::config::Config;
+use crate::discovery::Discovery;
+use crate::error::{Error, Result};
+use crate::llm::AgentRole;
+use handlebars::Handlebars;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory, relative to the repository, holding prompt overrides
+pub const PROMPTS_DIR: &str = ".raidme/prompts";
+
+/// Partial included at the top of every default prompt
+const PROJECT_CONTEXT_PARTIAL: &str = "project_context";
+
+/// Embedded default templates, by name
+const DEFAULT_PROMPTS: &[(&str, &str)] = &[
+    ("basic_analysis", include_str!("../prompts/basic_analysis.md")),
+    ("readme_analysis", include_str!("../prompts/readme_analysis.md")),
+    ("documentation_analysis", include_str!("../prompts/documentation_analysis.md")),
+    ("package_analysis", include_str!("../prompts/package_analysis.md")),
+    ("coding_analysis", include_str!("../prompts/coding_analysis.md")),
+    ("architecture_analysis", include_str!("../prompts/architecture_analysis.md")),
+    ("file_analysis", include_str!("../prompts/file_analysis.md")),
+    ("final_consolidation", include_str!("../prompts/final_consolidation.md")),
+    ("summarization", include_str!("../prompts/summarization.md")),
+    (PROJECT_CONTEXT_PARTIAL, include_str!("../prompts/project_context.md")),
+];
+
+/// Embedded default template for a role, `None` for custom roles
+pub fn default_template(role: &AgentRole) -> Option<&'static str> {
+    let name = role.to_string();
+    DEFAULT_PROMPTS
+        .iter()
+        .find(|(prompt, _)| *prompt == name)
+        .map(|(_, template)| *template)
+}
+
+/// Variables available to prompt templates
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PromptVars {
+    /// Repository directory name
+    pub project_name: Option<String>,
+    /// Dominant programming language
+    pub language: Option<String>,
+    /// Maximum directory depth analyzed
+    pub depth: Option<usize>,
+    /// Free-form context given by the user
+    pub user_context: Option<String>,
+}
+
+impl PromptVars {
+    /// Derive the variables from the repository and configuration
+    pub fn detect(repo_path: &Path, config: &Config) -> Self {
+        let project_name = repo_path
+            .canonicalize()
+            .unwrap_or_else(|_| repo_path.to_path_buf())
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string());
+
+        Self {
+            project_name,
+            language: detect_language(repo_path, config),
+            depth: config.analysis.max_depth,
+            user_context: None,
+        }
+    }
+}
+
+/// Map a file extension to the language it denotes
+pub(crate) fn language_for_extension(extension: &str) -> Option<&'static str> {
+    match extension {
+        "rs" => Some("Rust"),
+        "py" => Some("Python"),
+        "js" | "jsx" | "mjs" => Some("JavaScript"),
+        "ts" | "tsx" => Some("TypeScript"),
+        "go" => Some("Go"),
+        "java" => Some("Java"),
+        "kt" | "kts" => Some("Kotlin"),
+        "c" | "h" => Some("C"),
+        "cpp" | "cc" | "hpp" => Some("C++"),
+        "cs" => Some("C#"),
+        "rb" => Some("Ruby"),
+        "php" => Some("PHP"),
+        "swift" => Some("Swift"),
+        "scala" => Some("Scala"),
+        _ => None,
+    }
+}
+
+/// Most common source language among the files selected for analysis
+fn detect_language(repo_path: &Path, config: &Config) -> Option<String> {
+    let mut counts: HashMap<&'static str, usize> = HashMap::new();
+
+    let files = Discovery::new(repo_path, &config.analysis)
+        .and_then(|discovery| discovery.entries())
+        .unwrap_or_default();
+
+    for entry in files.iter().filter(|entry| !entry.is_dir && entry.class.is_none()) {
+        let language = entry.relative
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(language_for_extension);
+        if let Some(language) = language {
+            *counts.entry(language).or_default() += 1;
+        }
+    }
+
+    counts
+        .into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(a.0)))
+        .map(|(language, _)| language.to_string())
+}
+
+/// Prompt templates, defaults overlaid with the repository overrides
+pub struct PromptLibrary {
+    handlebars: Handlebars<'static>,
+}
+
+impl PromptLibrary {
+    /// Library with the embedded default prompts only
+    pub fn embedded() -> Result<Self> {
+        let mut handlebars = Handlebars::new();
+        // Prompts are Markdown, not HTML
+        handlebars.register_escape_fn(handlebars::no_escape);
+
+        for (name, template) in DEFAULT_PROMPTS {
+            Self::register(&mut handlebars, name, template)?;
+        }
+
+        Ok(Self { handlebars })
+    }
+
+    /// Library with the defaults overridden by `.raidme/prompts/*.md` in the repository
+    pub fn load(repo_path: &Path) -> Result<Self> {
+        let mut library = Self::embedded()?;
+
+        let dir = repo_path.join(PROMPTS_DIR);
+        if !dir.is_dir() {
+            return Ok(library);
+        }
+
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("md") {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+
+            let template = fs::read_to_string(&path)?;
+            Self::register(&mut library.handlebars, name, &template)?;
+            println!("Using prompt override: {}", path.display());
+        }
+
+        Ok(library)
+    }
+
+    fn register(handlebars: &mut Handlebars<'static>, name: &str, template: &str) -> Result<()> {
+        if name == PROJECT_CONTEXT_PARTIAL {
+            handlebars.register_partial(name, template)?;
+        }
+        handlebars.register_template_string(name, template)?;
+        Ok(())
+    }
+
+    /// Whether a template exists for the role
+    pub fn has(&self, role: &AgentRole) -> bool {
+        self.handlebars.has_template(&role.to_string())
+    }
+
+    /// Render the preamble of a role
+    pub fn render(&self, role: &AgentRole, vars: &PromptVars) -> Result<String> {
+        let name = role.to_string();
+        if !self.handlebars.has_template(&name) {
+            return Err(Error::Generic(format!("No prompt defined for role '{}'", name)));
+        }
+
+        let rendered = self.handlebars.render(&name, vars)?;
+        Ok(rendered.trim().to_string())
+    }
+
+    /// Write the default prompts to `.raidme/prompts`, keeping existing files unless `force`
+    pub fn dump(repo_path: &Path, force: bool) -> Result<Vec<PathBuf>> {
+        let dir = repo_path.join(PROMPTS_DIR);
+        fs::create_dir_all(&dir)?;
+
+        let mut written = Vec::new();
+        for (name, template) in DEFAULT_PROMPTS {
+            let path = dir.join(format!("{}.md", name));
+            if path.exists() && !force {
+                println!("Skipping existing {}", path.display());
+                continue;
+            }
+            fs::write(&path, template)?;
+            written.push(path);
+        }
+
+        Ok(written)
+    }
+}
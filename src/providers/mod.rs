@@ -0,0 +1,157 @@
+//! Provider backends that are not covered by rig's built-in clients
+pub mod ollama;
+pub mod openai_compatible;
+
+use crate::config::{Config, LlmConfig, LlmProvider, AZURE_DEFAULT_API_VERSION};
+use crate::credentials;
+use crate::error::{Error, Result};
+use crate::llm::Agent;
+use rig::client::CompletionClient;
+use rig::providers::{anthropic, azure, deepseek, gemini, groq, mistral, openai, openrouter, xai};
+
+/// Builds an agent for the configured provider and model from a role preamble
+pub type AgentFactory = Box<dyn Fn(&str) -> Box<dyn Agent + Send + Sync> + Send + Sync>;
+
+/// Model name meaning "use whatever the server offers"
+pub const AUTO_MODEL: &str = "auto";
+
+/// Create the agent factory for the configured provider
+pub fn agent_factory(config: &Config) -> Result<AgentFactory> {
+    let llm = &config.llm;
+    let api_key = llm.api_key.as_str();
+    let base_url = llm.base_url.as_deref();
+
+    let factory = match llm.provider {
+        LlmProvider::OpenAI => match base_url {
+            Some(url) => rig_factory(openai::Client::from_url(api_key, url), llm),
+            None => rig_factory(openai::Client::new(api_key), llm),
+        },
+        LlmProvider::Anthropic => {
+            let mut builder = anthropic::ClientBuilder::new(api_key);
+            if let Some(url) = base_url {
+                builder = builder.base_url(url);
+            }
+            rig_factory(builder.build(), llm)
+        }
+        LlmProvider::OpenRouter => match base_url {
+            Some(url) => rig_factory(openrouter::Client::from_url(api_key, url), llm),
+            None => rig_factory(openrouter::Client::new(api_key), llm),
+        },
+        LlmProvider::Ollama => {
+            let client = rig::providers::ollama::Client::from_url(&ollama::base_url(llm));
+            let model = llm.model.clone();
+            let options = serde_json::json!({ "num_ctx": ollama::num_ctx(llm) });
+            Box::new(move |preamble: &str| -> Box<dyn Agent + Send + Sync> {
+                Box::new(client.agent(&model)
+                    .preamble(preamble)
+                    .additional_params(options.clone())
+                    .build())
+            })
+        }
+        LlmProvider::Gemini => match base_url {
+            Some(url) => rig_factory(gemini::Client::from_url(api_key, url), llm),
+            None => rig_factory(gemini::Client::new(api_key), llm),
+        },
+        LlmProvider::Mistral => match base_url {
+            Some(url) => rig_factory(mistral::Client::from_url(api_key, url), llm),
+            None => rig_factory(mistral::Client::new(api_key), llm),
+        },
+        LlmProvider::Groq => match base_url {
+            Some(url) => rig_factory(groq::Client::from_url(api_key, url), llm),
+            None => rig_factory(groq::Client::new(api_key), llm),
+        },
+        LlmProvider::DeepSeek => match base_url {
+            Some(url) => rig_factory(deepseek::Client::from_url(api_key, url), llm),
+            None => rig_factory(deepseek::Client::new(api_key), llm),
+        },
+        LlmProvider::XAI => match base_url {
+            // rig does not expose a custom URL for xAI, but its API is OpenAI-compatible
+            Some(url) => rig_factory(openai::Client::from_url(api_key, url), llm),
+            None => rig_factory(xai::Client::new(api_key), llm),
+        },
+        LlmProvider::AzureOpenAI => {
+            let endpoint = base_url.ok_or_else(|| {
+                Error::ConfigError("Azure OpenAI requires an endpoint".to_string())
+            })?;
+            let api_version = llm.api_version.as_deref().unwrap_or(AZURE_DEFAULT_API_VERSION);
+            rig_factory(azure::Client::from_api_key(api_key, api_version, endpoint), llm)
+        }
+        LlmProvider::OpenAICompatible => {
+            let url = base_url.unwrap_or(openai_compatible::DEFAULT_BASE_URL);
+            let headers = llm.headers.clone().unwrap_or_default();
+            let client = openai_compatible::Client::new(url, Some(api_key), &headers)?;
+            let model = llm.model.clone();
+            let supports_system_prompt = llm.supports_system_prompt.unwrap_or(true);
+            let (temperature, max_tokens) = (llm.temperature, llm.max_tokens);
+            Box::new(move |preamble: &str| -> Box<dyn Agent + Send + Sync> {
+                Box::new(client.agent(&model, preamble)
+                    .supports_system_prompt(supports_system_prompt)
+                    .temperature(temperature)
+                    .max_tokens(max_tokens))
+            })
+        }
+    };
+
+    Ok(factory)
+}
+
+/// Agent factory for any rig provider client
+fn rig_factory<C>(client: C, llm: &LlmConfig) -> AgentFactory
+where
+    C: CompletionClient + Send + Sync + 'static,
+    rig::agent::Agent<C::CompletionModel>: Agent + Send + Sync + 'static,
+{
+    let model = llm.model.clone();
+    let (temperature, max_tokens) = (llm.temperature, llm.max_tokens);
+
+    Box::new(move |preamble: &str| -> Box<dyn Agent + Send + Sync> {
+        let mut builder = client.agent(&model).preamble(preamble);
+        if let Some(temperature) = temperature {
+            builder = builder.temperature(temperature as f64);
+        }
+        if let Some(max_tokens) = max_tokens {
+            builder = builder.max_tokens(max_tokens as u64);
+        }
+        Box::new(builder.build())
+    })
+}
+
+/// Resolve the API key, then the model settings needing a round-trip to the server with it
+pub async fn resolve(llm: &mut LlmConfig) -> Result<()> {
+    credentials::resolve_api_key(llm)?;
+    resolve_model(llm).await
+}
+
+async fn resolve_model(llm: &mut LlmConfig) -> Result<()> {
+    match llm.provider {
+        LlmProvider::OpenAICompatible => resolve_openai_compatible_model(llm).await,
+        LlmProvider::Ollama => ollama::resolve_model(llm).await,
+        _ => Ok(()),
+    }
+}
+
+async fn resolve_openai_compatible_model(llm: &mut LlmConfig) -> Result<()> {
+    let base_url = llm.base_url.as_deref().unwrap_or(openai_compatible::DEFAULT_BASE_URL);
+    let headers = llm.headers.clone().unwrap_or_default();
+    let client = openai_compatible::Client::new(base_url, Some(&llm.api_key), &headers)?;
+
+    // Some servers do not implement /v1/models; only fail when we actually need the list
+    match client.list_models().await {
+        Ok(models) if llm.model.is_empty() || llm.model == AUTO_MODEL => {
+            llm.model = models.into_iter().next().ok_or_else(|| {
+                Error::ConfigError(format!("No model is served at {}", base_url))
+            })?;
+            println!("Using model served at {}: {}", base_url, llm.model);
+        }
+        Ok(models) => {
+            if !models.contains(&llm.model) {
+                println!("Warning: model '{}' is not listed by {} (available: {})",
+                    llm.model, base_url, models.join(", "));
+            }
+        }
+        Err(e) if llm.model.is_empty() || llm.model == AUTO_MODEL => return Err(e),
+        Err(e) => tracing::debug!("Could not list models at {}: {}", base_url, e),
+    }
+
+    Ok(())
+}
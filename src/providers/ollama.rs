@@ -0,0 +1,119 @@
+//! Local model discovery for the Ollama daemon.
+//!
+//! Completions go through rig's native Ollama client; this module only talks
+//! to `/api/tags` so that raidme can pick an installed model and fail early
+//! with a useful message when the daemon is not running.
+
+use crate::config::LlmConfig;
+use crate::error::{Error, Result};
+use serde::Deserialize;
+
+/// Address of a default local Ollama install
+pub const DEFAULT_BASE_URL: &str = "http://localhost:11434";
+
+/// Model families preferred for code analysis, best first
+const PREFERRED_MODELS: &[&str] = &[
+    "qwen2.5-coder",
+    "qwen3",
+    "deepseek-coder-v2",
+    "codellama",
+    "llama3.3",
+    "llama3.1",
+    "llama3.2",
+    "mistral",
+    "gemma3",
+];
+
+/// Model names that mean "pick an installed model for me"
+const AUTO_MODELS: &[&str] = &[super::AUTO_MODEL, "ollama-default", ""];
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OllamaModel {
+    pub name: String,
+    #[serde(default)]
+    pub size: u64,
+}
+
+#[derive(Deserialize)]
+struct TagsResponse {
+    #[serde(default)]
+    models: Vec<OllamaModel>,
+}
+
+/// Normalize a configured host (`OLLAMA_HOST` style or a legacy `/v1` URL) to the native API root
+pub fn base_url(llm: &LlmConfig) -> String {
+    let host = llm.base_url.as_deref().unwrap_or(DEFAULT_BASE_URL);
+    let host = host.trim_end_matches('/').trim_end_matches("/v1");
+
+    if host.starts_with("http://") || host.starts_with("https://") {
+        host.to_string()
+    } else {
+        format!("http://{}", host)
+    }
+}
+
+/// List the models installed in the local daemon
+pub async fn list_models(base_url: &str) -> Result<Vec<OllamaModel>> {
+    let response = reqwest::get(format!("{}/api/tags", base_url))
+        .await
+        .map_err(|_| Error::OllamaUnavailable(base_url.to_string()))?;
+
+    if !response.status().is_success() {
+        return Err(Error::OllamaUnavailable(base_url.to_string()));
+    }
+
+    let tags: TagsResponse = response.json().await
+        .map_err(|e| Error::Llm(format!("Invalid Ollama /api/tags response: {}", e)))?;
+
+    Ok(tags.models)
+}
+
+/// Pick the best installed model: a preferred family first, otherwise the largest one
+pub fn default_model(models: &[OllamaModel]) -> Option<&OllamaModel> {
+    PREFERRED_MODELS
+        .iter()
+        .find_map(|family| {
+            models.iter().find(|m| m.name == *family || m.name.starts_with(&format!("{}:", family)))
+        })
+        .or_else(|| models.iter().max_by_key(|m| m.size))
+}
+
+/// Check the daemon is reachable and make sure `llm.model` names an installed model
+pub async fn resolve_model(llm: &mut LlmConfig) -> Result<()> {
+    let base_url = base_url(llm);
+    let models = list_models(&base_url).await?;
+
+    if AUTO_MODELS.contains(&llm.model.as_str()) {
+        let model = default_model(&models).ok_or_else(|| {
+            Error::ConfigError(format!(
+                "No model installed in Ollama at {}. Install one with `ollama pull qwen2.5-coder`",
+                base_url
+            ))
+        })?;
+        llm.model = model.name.clone();
+        println!("Using Ollama model: {}", llm.model);
+        return Ok(());
+    }
+
+    // Ollama resolves a bare name to its `:latest` tag
+    let installed = models.iter().any(|m| {
+        m.name == llm.model || m.name == format!("{}:latest", llm.model)
+    });
+    if !installed {
+        let names: Vec<_> = models.iter().map(|m| m.name.as_str()).collect();
+        return Err(Error::ConfigError(format!(
+            "Ollama model '{}' is not installed (available: {}). Run `ollama pull {}`",
+            llm.model,
+            names.join(", "),
+            llm.model
+        )));
+    }
+
+    Ok(())
+}
+
+/// Context window to request so the prompt and the response both fit
+pub fn num_ctx(llm: &LlmConfig) -> usize {
+    llm.max_context_tokens.unwrap_or(crate::config::DEFAULT_MAX_CONTEXT_TOKENS)
+        + llm.max_tokens.unwrap_or(4096) as usize
+}
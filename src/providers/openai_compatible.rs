@@ -0,0 +1,238 @@
+//! Client for local inference servers exposing an OpenAI-compatible API
+//! (llama.cpp server, vLLM, LM Studio, ...).
+//!
+//! These servers implement the chat completions endpoint with small
+//! variations, so the client only relies on the subset they agree on: no
+//! system prompt when the server rejects it, and optional usage fields.
+
+use crate::error::{Error, Result};
+use crate::llm::Agent;
+use async_trait::async_trait;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Base URL used when none is configured (llama.cpp server default)
+pub const DEFAULT_BASE_URL: &str = "http://localhost:8080/v1";
+
+#[derive(Clone, Debug)]
+pub struct Client {
+    base_url: String,
+    http_client: reqwest::Client,
+}
+
+impl Client {
+    /// Create a client for `base_url`, with an optional bearer token and extra headers
+    pub fn new(base_url: &str, api_key: Option<&str>, headers: &BTreeMap<String, String>) -> Result<Self> {
+        let mut default_headers = HeaderMap::new();
+
+        if let Some(api_key) = api_key.filter(|k| !k.is_empty()) {
+            let value = HeaderValue::from_str(&format!("Bearer {}", api_key))
+                .map_err(|e| Error::ConfigError(format!("Invalid API key: {}", e)))?;
+            default_headers.insert(AUTHORIZATION, value);
+        }
+
+        for (name, value) in headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| Error::ConfigError(format!("Invalid header name '{}': {}", name, e)))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|e| Error::ConfigError(format!("Invalid value for header '{}': {}", name, e)))?;
+            default_headers.insert(name, value);
+        }
+
+        let http_client = reqwest::Client::builder()
+            .default_headers(default_headers)
+            .build()
+            .map_err(|e| Error::Network(e.to_string()))?;
+
+        Ok(Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            http_client,
+        })
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.base_url, path)
+    }
+
+    /// List the model ids served by `/v1/models`
+    pub async fn list_models(&self) -> Result<Vec<String>> {
+        let response = self.http_client
+            .get(self.url("models"))
+            .send()
+            .await
+            .map_err(|e| Error::Network(format!("{}: {}", self.base_url, e)))?;
+
+        let response = check_status(response).await?;
+        let models: ModelList = response.json().await
+            .map_err(|e| Error::Llm(format!("Invalid /models response: {}", e)))?;
+
+        Ok(models.data.into_iter().map(|m| m.id).collect())
+    }
+
+    /// Create an agent answering with `model` under the given system preamble
+    pub fn agent(&self, model: &str, preamble: &str) -> CompletionAgent {
+        CompletionAgent {
+            client: self.clone(),
+            model: model.to_string(),
+            preamble: preamble.to_string(),
+            supports_system_prompt: true,
+            temperature: None,
+            max_tokens: None,
+        }
+    }
+}
+
+/// Single-turn chat agent backed by an OpenAI-compatible server
+#[derive(Clone, Debug)]
+pub struct CompletionAgent {
+    client: Client,
+    model: String,
+    preamble: String,
+    supports_system_prompt: bool,
+    temperature: Option<f32>,
+    max_tokens: Option<u32>,
+}
+
+impl CompletionAgent {
+    /// Fold the preamble into the user message for servers without system prompts
+    pub fn supports_system_prompt(mut self, supported: bool) -> Self {
+        self.supports_system_prompt = supported;
+        self
+    }
+
+    pub fn temperature(mut self, temperature: Option<f32>) -> Self {
+        self.temperature = temperature;
+        self
+    }
+
+    pub fn max_tokens(mut self, max_tokens: Option<u32>) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    fn messages(&self, prompt: &str) -> Vec<ChatMessage> {
+        if self.preamble.is_empty() {
+            return vec![ChatMessage::user(prompt.to_string())];
+        }
+
+        if self.supports_system_prompt {
+            vec![
+                ChatMessage::system(self.preamble.clone()),
+                ChatMessage::user(prompt.to_string()),
+            ]
+        } else {
+            vec![ChatMessage::user(format!("{}\n\n{}", self.preamble, prompt))]
+        }
+    }
+}
+
+#[async_trait]
+impl Agent for CompletionAgent {
+    async fn prompt(&self, prompt: &str) -> Result<String> {
+        let request = ChatRequest {
+            model: &self.model,
+            messages: self.messages(prompt),
+            temperature: self.temperature,
+            max_tokens: self.max_tokens,
+        };
+
+        let response = self.client.http_client
+            .post(self.client.url("chat/completions"))
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| Error::Network(format!("{}: {}", self.client.base_url, e)))?;
+
+        let response = check_status(response).await?;
+        let completion: ChatResponse = response.json().await
+            .map_err(|e| Error::Llm(format!("Invalid chat completion response: {}", e)))?;
+
+        if let Some(usage) = &completion.usage {
+            tracing::debug!(
+                "{} usage: prompt={:?} completion={:?}",
+                self.model, usage.prompt_tokens, usage.completion_tokens
+            );
+        }
+
+        completion.choices
+            .into_iter()
+            .next()
+            .and_then(|choice| choice.message.content)
+            .ok_or_else(|| Error::Llm("Chat completion response contained no content".to_string()))
+    }
+}
+
+async fn check_status(response: reqwest::Response) -> Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let body = response.text().await.unwrap_or_default();
+    Err(match status.as_u16() {
+        401 | 403 => Error::Auth(body),
+        429 => Error::RateLimit(body),
+        _ => Error::Llm(format!("{}: {}", status, body)),
+    })
+}
+
+#[derive(Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<ChatMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+}
+
+#[derive(Serialize)]
+struct ChatMessage {
+    role: &'static str,
+    content: String,
+}
+
+impl ChatMessage {
+    fn system(content: String) -> Self {
+        Self { role: "system", content }
+    }
+
+    fn user(content: String) -> Self {
+        Self { role: "user", content }
+    }
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    #[serde(default)]
+    choices: Vec<Choice>,
+    usage: Option<Usage>,
+}
+
+#[derive(Deserialize)]
+struct Choice {
+    message: ResponseMessage,
+}
+
+#[derive(Deserialize)]
+struct ResponseMessage {
+    content: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Usage {
+    prompt_tokens: Option<u64>,
+    completion_tokens: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct ModelList {
+    #[serde(default)]
+    data: Vec<ModelInfo>,
+}
+
+#[derive(Deserialize)]
+struct ModelInfo {
+    id: String,
+}
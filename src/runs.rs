@@ -0,0 +1,73 @@
+//! Analysis runs: which profile, provider and model produced the knowledge.
+
+use crate::analyzer::StepStatus;
+use crate::config::Config;
+use crate::error::{Error, Result};
+use sqlx::{Row, SqlitePool};
+
+/// One `raidme analyze` invocation
+#[derive(Debug, Clone)]
+pub struct AnalysisRun {
+    pub id: String,
+    pub profile: Option<String>,
+    pub provider: String,
+    pub model: String,
+    pub status: StepStatus,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub completed_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Record the start of a run with the given configuration
+pub async fn start(db: &SqlitePool, id: &str, config: &Config) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO analysis_runs (id, profile, provider, model, status, started_at) VALUES ($1, $2, $3, $4, $5, $6)"
+    )
+    .bind(id)
+    .bind(&config.profile)
+    .bind(config.llm.provider.id())
+    .bind(&config.llm.model)
+    .bind(serde_json::to_string(&StepStatus::InProgress)?)
+    .bind(chrono::Utc::now())
+    .execute(db)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(())
+}
+
+/// Record the outcome of a run
+pub async fn finish(db: &SqlitePool, id: &str, status: StepStatus) -> Result<()> {
+    sqlx::query("UPDATE analysis_runs SET status = $1, completed_at = $2 WHERE id = $3")
+        .bind(serde_json::to_string(&status)?)
+        .bind(chrono::Utc::now())
+        .bind(id)
+        .execute(db)
+        .await
+        .map_err(Error::Sqlx)?;
+
+    Ok(())
+}
+
+/// Most recent runs first
+pub async fn recent(db: &SqlitePool, limit: u32) -> Result<Vec<AnalysisRun>> {
+    let rows = sqlx::query("SELECT * FROM analysis_runs ORDER BY started_at DESC LIMIT $1")
+        .bind(limit)
+        .fetch_all(db)
+        .await
+        .map_err(Error::Sqlx)?;
+
+    rows.into_iter()
+        .map(|row| {
+            let status: String = row.try_get("status").map_err(Error::Sqlx)?;
+            Ok(AnalysisRun {
+                id: row.try_get("id").map_err(Error::Sqlx)?,
+                profile: row.try_get("profile").map_err(Error::Sqlx)?,
+                provider: row.try_get("provider").map_err(Error::Sqlx)?,
+                model: row.try_get("model").map_err(Error::Sqlx)?,
+                status: serde_json::from_str(&status)?,
+                started_at: row.try_get("started_at").map_err(Error::Sqlx)?,
+                completed_at: row.try_get("completed_at").map_err(Error::Sqlx)?,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,972 @@
+//! Data models extracted from schema definitions.
+//!
+//! SQL migrations, Prisma schemas, Diesel `table!` macros, SQLAlchemy and
+//! GORM models, and OpenAPI / JSON Schema components are normalized into
+//! entities with their columns and relations. The entities are stored in the
+//! `entities` table and rendered by raidme as the Data Models section of the
+//! knowledge file: a Mermaid `erDiagram` and a table, inserted at
+//! [`DATA_MODELS_MARKER`].
+
+use crate::diagrams;
+use crate::discovery::Entry;
+use crate::error::{Error, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as Json;
+use sqlx::{Row, SqlitePool};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::path::Path;
+use std::sync::LazyLock;
+
+/// Line of the final consolidation replaced by the Data Models section
+pub const DATA_MODELS_MARKER: &str = "<!-- raidme:data-models -->";
+
+/// Heading of the Data Models section
+pub const DATA_MODELS_HEADING: &str = "## Data Models";
+
+/// Entities drawn in the ER diagram, the most related first; the table lists them all
+const MAX_DIAGRAM_ENTITIES: usize = 30;
+
+/// Columns listed per entity in the table and the summary before eliding the rest
+const SUMMARY_COLUMNS: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SchemaKind {
+    Sql,
+    Prisma,
+    Diesel,
+    SqlAlchemy,
+    Gorm,
+    OpenApi,
+    JsonSchema,
+}
+
+impl fmt::Display for SchemaKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SchemaKind::Sql => "SQL",
+            SchemaKind::Prisma => "Prisma",
+            SchemaKind::Diesel => "Diesel",
+            SchemaKind::SqlAlchemy => "SQLAlchemy",
+            SchemaKind::Gorm => "GORM",
+            SchemaKind::OpenApi => "OpenAPI",
+            SchemaKind::JsonSchema => "JSON Schema",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Column {
+    pub name: String,
+    /// Type as written in the schema, empty when untyped
+    pub ty: String,
+    pub primary_key: bool,
+    pub nullable: bool,
+    /// Entity this column is a foreign key to
+    pub references: Option<String>,
+}
+
+impl Column {
+    fn new(name: &str, ty: &str) -> Self {
+        Self { name: name.to_string(), ty: ty.to_string(), primary_key: false, nullable: true, references: None }
+    }
+}
+
+/// A relation to another entity that is not carried by a foreign key column
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Relation {
+    /// Field holding the relation
+    pub name: String,
+    pub target: String,
+    /// The entity holds many of the target, else one
+    pub many: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entity {
+    /// Table or model name
+    pub name: String,
+    /// Path of the defining file, relative to the repository root
+    pub file: String,
+    pub kind: SchemaKind,
+    pub columns: Vec<Column>,
+    pub relations: Vec<Relation>,
+}
+
+impl Entity {
+    fn new(name: &str, file: &str, kind: SchemaKind) -> Self {
+        Self { name: name.to_string(), file: file.to_string(), kind, columns: Vec::new(), relations: Vec::new() }
+    }
+
+    fn column_mut(&mut self, name: &str) -> Option<&mut Column> {
+        self.columns.iter_mut().find(|column| column.name.eq_ignore_ascii_case(name))
+    }
+}
+
+/// Extract the entities of every schema found among the discovered entries.
+///
+/// SQL files are replayed in path order, so later migrations alter the tables
+/// of earlier ones. An entity defined by several schemas is kept once, SQL
+/// first and API schemas last. Each sub-project of `project_dirs` has its own
+/// tables, so the same name in two of them is two entities.
+pub fn discover(entries: &[Entry], project_dirs: &[String]) -> Vec<Entity> {
+    let mut files: Vec<&Entry> = entries.iter().filter(|e| !e.is_dir && e.class.is_none()).collect();
+    files.sort_by(|a, b| a.relative.cmp(&b.relative));
+
+    let mut sql: BTreeMap<&str, SqlSchema> = BTreeMap::new();
+    let mut entities = Vec::new();
+    for entry in files {
+        let relative = entry.relative.to_string_lossy().replace('\\', "/");
+        let extension = entry.path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+        if !matches!(extension.as_str(), "sql" | "prisma" | "rs" | "py" | "go" | "json" | "yaml" | "yml") {
+            continue;
+        }
+        let Ok(content) = std::fs::read_to_string(&entry.path) else { continue };
+
+        match extension.as_str() {
+            "sql" => sql.entry(project_dir(&relative, project_dirs)).or_default().apply(&content, &relative),
+            "prisma" => entities.extend(parse_prisma(&content, &relative)),
+            "rs" if content.contains("table!") => entities.extend(parse_diesel(&content, &relative)),
+            "py" if content.contains("__tablename__") => entities.extend(parse_sqlalchemy(&content, &relative)),
+            "go" if content.contains("gorm") => entities.extend(parse_gorm(&content, &relative)),
+            "json" | "yaml" | "yml" => match parse_api_schema(&entry.path, &content, &relative) {
+                Ok(found) => entities.extend(found),
+                Err(e) => println!("⚠️ Skipping schema {}: {}", relative, e),
+            },
+            _ => {}
+        }
+    }
+
+    let mut all: Vec<Entity> = sql.into_values().flat_map(|schema| schema.tables).collect();
+    all.extend(entities);
+    all.sort_by_key(|entity| entity.kind);
+    let mut seen = BTreeSet::new();
+    all.retain(|entity| seen.insert((project_dir(&entity.file, project_dirs), entity.name.to_lowercase())));
+    all.sort_by(|a, b| a.file.cmp(&b.file).then_with(|| a.name.cmp(&b.name)));
+    all
+}
+
+/// Deepest sub-project directory containing a file, empty for the repository root
+fn project_dir<'a>(file: &str, project_dirs: &'a [String]) -> &'a str {
+    project_dirs
+        .iter()
+        .filter(|dir| file.strip_prefix(dir.as_str()).is_some_and(|rest| rest.starts_with('/')))
+        .max_by_key(|dir| dir.len())
+        .map_or("", String::as_str)
+}
+
+/// Tables built by replaying SQL statements
+#[derive(Debug, Default)]
+struct SqlSchema {
+    tables: Vec<Entity>,
+}
+
+static SQL_CREATE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)^create\s+(?:(?:global\s+|local\s+)?temp(?:orary)?\s+)?(?:unlogged\s+)?table\s+(?:if\s+not\s+exists\s+)?([\w."`\[\]]+)\s*\((.*)\)"#)
+        .expect("valid create table pattern")
+});
+
+static SQL_ALTER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)^alter\s+table\s+(?:if\s+exists\s+)?(?:only\s+)?([\w."`\[\]]+)\s+(.*)$"#).expect("valid alter table pattern")
+});
+
+static SQL_DROP: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)^drop\s+table\s+(?:if\s+exists\s+)?([\w."`\[\]]+)"#).expect("valid drop table pattern")
+});
+
+static SQL_REFERENCES: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)\breferences\s+([\w."`\[\]]+)"#).expect("valid references pattern")
+});
+
+static SQL_FOREIGN_KEY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)^foreign\s+key\s*\(([^)]*)\)\s*references\s+([\w."`\[\]]+)"#).expect("valid foreign key pattern")
+});
+
+static SQL_PRIMARY_KEY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)^primary\s+key\s*\(([^)]*)\)"#).expect("valid primary key pattern")
+});
+
+static SQL_ADD: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)^add\s+(?:column\s+)?(?:if\s+not\s+exists\s+)?"#).expect("valid add column pattern")
+});
+
+static SQL_CONSTRAINT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)^constraint\s+[\w"`\[\]]+\s+"#).expect("valid constraint pattern")
+});
+
+/// Words ending the type of a column definition
+const SQL_COLUMN_KEYWORDS: &[&str] = &[
+    "not", "null", "primary", "references", "default", "unique", "check", "constraint", "generated",
+    "autoincrement", "auto_increment", "collate", "on", "identity", "comment",
+];
+
+/// Words starting a table constraint rather than a column
+const SQL_TABLE_CONSTRAINTS: &[&str] = &["unique", "check", "index", "key", "exclude", "fulltext", "spatial"];
+
+impl SqlSchema {
+    fn apply(&mut self, content: &str, file: &str) {
+        for statement in split_top_level(&strip_sql_comments(content), ';') {
+            let statement = statement.trim();
+            if let Some(captures) = SQL_CREATE.captures(statement) {
+                let name = sql_name(&captures[1]);
+                let mut table = Entity::new(&name, file, SchemaKind::Sql);
+                for item in split_top_level(&captures[2], ',') {
+                    sql_table_item(&mut table, item.trim());
+                }
+                self.tables.retain(|t| !t.name.eq_ignore_ascii_case(&name));
+                self.tables.push(table);
+            } else if let Some(captures) = SQL_ALTER.captures(statement) {
+                let name = sql_name(&captures[1]);
+                let actions = captures[2].to_string();
+                self.alter(&name, &actions);
+            } else if let Some(captures) = SQL_DROP.captures(statement) {
+                let name = sql_name(&captures[1]);
+                self.tables.retain(|t| !t.name.eq_ignore_ascii_case(&name));
+            }
+        }
+    }
+
+    fn alter(&mut self, name: &str, actions: &str) {
+        let Some(table) = self.tables.iter_mut().find(|t| t.name.eq_ignore_ascii_case(name)) else { return };
+        for action in split_top_level(actions, ',') {
+            let words: Vec<&str> = action.split_whitespace().collect();
+            let keyword = |i: usize| words.get(i).map(|w| w.to_lowercase()).unwrap_or_default();
+            match (keyword(0).as_str(), keyword(1).as_str()) {
+                ("rename", "to") => table.name = sql_name(words.get(2).copied().unwrap_or(name)),
+                ("rename", _) => {
+                    let (from, to) = if keyword(1) == "column" { (2, 4) } else { (1, 3) };
+                    if let (Some(from), Some(to)) = (words.get(from), words.get(to)) {
+                        let to = sql_name(to);
+                        if let Some(column) = table.column_mut(&sql_name(from)) {
+                            column.name = to;
+                        }
+                    }
+                }
+                ("drop", "constraint") => {}
+                ("drop", _) => {
+                    let column = words.get(if keyword(1) == "column" { 2 } else { 1 }).map(|c| sql_name(c)).unwrap_or_default();
+                    table.columns.retain(|c| !c.name.eq_ignore_ascii_case(&column));
+                }
+                ("add", _) => sql_table_item(table, &SQL_ADD.replace(action.trim(), "")),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Apply a column definition or table constraint of a CREATE or ALTER TABLE
+fn sql_table_item(table: &mut Entity, item: &str) {
+    let item = SQL_CONSTRAINT.replace(item, "");
+    let item = item.trim();
+    if let Some(captures) = SQL_PRIMARY_KEY.captures(item) {
+        for name in captures[1].split(',') {
+            if let Some(column) = table.column_mut(&sql_name(name)) {
+                column.primary_key = true;
+                column.nullable = false;
+            }
+        }
+        return;
+    }
+    if let Some(captures) = SQL_FOREIGN_KEY.captures(item) {
+        let target = sql_name(&captures[2]);
+        for name in captures[1].split(',') {
+            if let Some(column) = table.column_mut(&sql_name(name)) {
+                column.references = Some(target.clone());
+            }
+        }
+        return;
+    }
+
+    let mut words = item.split_whitespace();
+    let Some(first) = words.next() else { return };
+    if SQL_TABLE_CONSTRAINTS.contains(&first.to_lowercase().as_str()) {
+        return;
+    }
+
+    let rest: Vec<&str> = words.collect();
+    let type_len = rest
+        .iter()
+        .position(|word| SQL_COLUMN_KEYWORDS.contains(&word.to_lowercase().trim_end_matches(',')))
+        .unwrap_or(rest.len());
+    let mut column = Column::new(&sql_name(first), &rest[..type_len].join(" "));
+    let constraints = rest[type_len..].join(" ").to_lowercase();
+    if constraints.contains("primary key") {
+        column.primary_key = true;
+        column.nullable = false;
+    }
+    if constraints.contains("not null") {
+        column.nullable = false;
+    }
+    column.references = SQL_REFERENCES.captures(item).map(|captures| sql_name(&captures[1]));
+
+    table.columns.retain(|c| !c.name.eq_ignore_ascii_case(&column.name));
+    table.columns.push(column);
+}
+
+/// Identifier without quotes nor schema qualification
+fn sql_name(name: &str) -> String {
+    let name = name.trim().rsplit('.').next().unwrap_or(name);
+    name.trim_matches(|c| matches!(c, '"' | '`' | '[' | ']')).to_string()
+}
+
+fn strip_sql_comments(content: &str) -> String {
+    let mut stripped = String::with_capacity(content.len());
+    let mut rest = content;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("--") {
+            rest = after.find('\n').map_or("", |end| &after[end..]);
+        } else if let Some(after) = rest.strip_prefix("/*") {
+            rest = after.find("*/").map_or("", |end| &after[end + 2..]);
+        } else {
+            let c = rest.chars().next().unwrap_or_default();
+            stripped.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    stripped
+}
+
+/// Split on a separator outside of parentheses and quotes
+fn split_top_level(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut quote, mut start) = (0i32, None, 0);
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"' | '`') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => depth -= 1,
+            (None, _) if c == separator && depth == 0 => {
+                parts.push(&text[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts.into_iter().filter(|part| !part.trim().is_empty()).collect()
+}
+
+static PRISMA_MODEL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?ms)^\s*model\s+(\w+)\s*\{(.*?)^\s*\}").expect("valid prisma model pattern")
+});
+
+static PRISMA_RELATION_FIELDS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"@relation\([^)]*fields:\s*\[([^\]]*)\]").expect("valid prisma relation pattern")
+});
+
+fn parse_prisma(content: &str, file: &str) -> Vec<Entity> {
+    let models: BTreeSet<&str> = PRISMA_MODEL.captures_iter(content).filter_map(|c| c.get(1)).map(|m| m.as_str()).collect();
+    let mut entities = Vec::new();
+
+    for captures in PRISMA_MODEL.captures_iter(content) {
+        let mut entity = Entity::new(&captures[1], file, SchemaKind::Prisma);
+        let mut foreign_keys = Vec::new();
+        for line in captures[2].lines().map(str::trim) {
+            if line.is_empty() || line.starts_with("//") || line.starts_with("@@") {
+                continue;
+            }
+            let mut words = line.split_whitespace();
+            let (Some(name), Some(ty)) = (words.next(), words.next()) else { continue };
+            let base = ty.trim_end_matches(['?', '[', ']']).trim_end_matches("[]");
+            if models.contains(base) {
+                if ty.ends_with("[]") {
+                    entity.relations.push(Relation { name: name.to_string(), target: base.to_string(), many: true });
+                } else if let Some(fields) = PRISMA_RELATION_FIELDS.captures(line) {
+                    foreign_keys.extend(fields[1].split(',').map(|f| (f.trim().to_string(), base.to_string())));
+                }
+                continue;
+            }
+            let mut column = Column::new(name, ty.trim_end_matches('?'));
+            column.nullable = ty.ends_with('?');
+            column.primary_key = line.contains("@id");
+            entity.columns.push(column);
+        }
+        for (field, target) in foreign_keys {
+            if let Some(column) = entity.column_mut(&field) {
+                column.references = Some(target);
+            }
+        }
+        entities.push(entity);
+    }
+    entities
+}
+
+static DIESEL_TABLE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)table!\s*\{\s*(?:[\w:]+\s*\.\s*)?(\w+)\s*\(([^)]*)\)\s*\{(.*?)\}\s*\}").expect("valid diesel table pattern")
+});
+
+static DIESEL_COLUMN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?m)^\s*(?:r#)?(\w+)\s*->\s*([^,\n]+),?").expect("valid diesel column pattern")
+});
+
+static DIESEL_JOINABLE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"joinable!\s*\(\s*(\w+)\s*->\s*(\w+)\s*\(\s*(\w+)\s*\)\s*\)").expect("valid diesel joinable pattern")
+});
+
+fn parse_diesel(content: &str, file: &str) -> Vec<Entity> {
+    let mut entities: Vec<Entity> = DIESEL_TABLE
+        .captures_iter(content)
+        .map(|captures| {
+            let keys: Vec<&str> = captures[2].split(',').map(str::trim).collect();
+            let mut entity = Entity::new(&captures[1], file, SchemaKind::Diesel);
+            for column in DIESEL_COLUMN.captures_iter(&captures[3]) {
+                let ty = column[2].trim();
+                let mut parsed = Column::new(&column[1], ty.strip_prefix("Nullable<").and_then(|t| t.strip_suffix('>')).unwrap_or(ty));
+                parsed.nullable = ty.starts_with("Nullable<");
+                parsed.primary_key = keys.contains(&&column[1]);
+                entity.columns.push(parsed);
+            }
+            entity
+        })
+        .collect();
+
+    for captures in DIESEL_JOINABLE.captures_iter(content) {
+        if let Some(entity) = entities.iter_mut().find(|e| e.name == captures[1]) {
+            if let Some(column) = entity.column_mut(&captures[3]) {
+                column.references = Some(captures[2].to_string());
+            }
+        }
+    }
+    entities
+}
+
+static PY_CLASS: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^class\s+(\w+)").expect("valid class pattern"));
+
+static PY_TABLENAME: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"^__tablename__\s*=\s*["']([\w.]+)["']"#).expect("valid tablename pattern")
+});
+
+static PY_COLUMN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(\w+)\s*(?::\s*Mapped\[(.+?)\]\s*)?=\s*(?:\w+\.)?(Column|mapped_column)\((.*)\)\s*$").expect("valid column pattern")
+});
+
+static PY_FOREIGN_KEY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"ForeignKey\(\s*["']([\w.]+)["']"#).expect("valid foreign key pattern")
+});
+
+fn parse_sqlalchemy(content: &str, file: &str) -> Vec<Entity> {
+    let mut entities = Vec::new();
+    let mut current: Option<(String, Option<String>, Vec<Column>)> = None;
+
+    let finish = |current: Option<(String, Option<String>, Vec<Column>)>, entities: &mut Vec<Entity>| {
+        if let Some((_, Some(table), columns)) = current {
+            let mut entity = Entity::new(&table, file, SchemaKind::SqlAlchemy);
+            entity.columns = columns;
+            entities.push(entity);
+        }
+    };
+
+    for line in content.lines() {
+        if let Some(captures) = PY_CLASS.captures(line) {
+            finish(current.take(), &mut entities);
+            current = Some((captures[1].to_string(), None, Vec::new()));
+            continue;
+        }
+        let Some((_, table, columns)) = current.as_mut() else { continue };
+        if !line.starts_with([' ', '\t']) && !line.trim().is_empty() {
+            finish(current.take(), &mut entities);
+            continue;
+        }
+
+        let trimmed = line.trim();
+        if let Some(captures) = PY_TABLENAME.captures(trimmed) {
+            *table = Some(sql_name(&captures[1]));
+        } else if let Some(captures) = PY_COLUMN.captures(trimmed) {
+            let args = &captures[4];
+            let mapped = captures.get(2).map(|m| m.as_str());
+            let ty = match mapped {
+                Some(mapped) => mapped.trim_start_matches("Optional[").trim_end_matches(']').to_string(),
+                None => split_top_level(args, ',')
+                    .first()
+                    .map(|first| first.trim())
+                    .filter(|first| !first.contains('=') && !first.starts_with(['"', '\'']) && !first.starts_with("ForeignKey"))
+                    .map(|first| first.rsplit('.').next().unwrap_or(first).trim_end_matches("()").to_string())
+                    .unwrap_or_default(),
+            };
+            let mut column = Column::new(&captures[1], &ty);
+            column.primary_key = args.contains("primary_key=True");
+            column.nullable = match mapped {
+                _ if args.contains("nullable=False") || column.primary_key => false,
+                _ if args.contains("nullable=True") => true,
+                Some(mapped) => mapped.starts_with("Optional[") || mapped.contains("None"),
+                None => true,
+            };
+            column.references = PY_FOREIGN_KEY.captures(args).map(|fk| {
+                let target = &fk[1];
+                sql_name(target.rsplit_once('.').map_or(target, |(table, _)| table))
+            });
+            columns.push(column);
+        }
+    }
+    finish(current, &mut entities);
+    entities
+}
+
+static GO_STRUCT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?ms)^type\s+(\w+)\s+struct\s*\{(.*?)^\}").expect("valid struct pattern")
+});
+
+fn parse_gorm(content: &str, file: &str) -> Vec<Entity> {
+    let structs: Vec<(String, &str)> = GO_STRUCT
+        .captures_iter(content)
+        .filter(|captures| captures[2].contains("gorm"))
+        .map(|captures| (captures[1].to_string(), captures.get(2).map_or("", |m| m.as_str())))
+        .collect();
+    let names: BTreeSet<&str> = structs.iter().map(|(name, _)| name.as_str()).collect();
+
+    let mut entities = Vec::new();
+    for (name, body) in &structs {
+        let mut entity = Entity::new(name, file, SchemaKind::Gorm);
+        for line in body.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+            let tag = line.split_once('`').map_or("", |(_, tag)| tag);
+            let declaration = line.split('`').next().unwrap_or(line);
+            let words: Vec<&str> = declaration.split_whitespace().collect();
+            match words.as_slice() {
+                ["gorm.Model"] => {
+                    let mut id = Column::new("ID", "uint");
+                    id.primary_key = true;
+                    id.nullable = false;
+                    entity.columns.push(id);
+                    entity.columns.extend(["CreatedAt", "UpdatedAt"].map(|field| Column::new(field, "time.Time")));
+                    entity.columns.push(Column::new("DeletedAt", "gorm.DeletedAt"));
+                }
+                [field, ty, ..] => {
+                    let base = ty.trim_start_matches("[]").trim_start_matches('*');
+                    if names.contains(base) {
+                        if ty.starts_with("[]") {
+                            entity.relations.push(Relation { name: field.to_string(), target: base.to_string(), many: true });
+                        }
+                        continue;
+                    }
+                    let mut column = Column::new(field, ty);
+                    column.primary_key = tag.contains("primaryKey") || tag.contains("primary_key");
+                    column.nullable = ty.starts_with('*');
+                    entity.columns.push(column);
+                }
+                _ => {}
+            }
+        }
+
+        // Foreign keys follow the GORM convention: field `UserID` for a field of type `User`
+        let targets: Vec<(String, String)> = structs
+            .iter()
+            .filter(|(other, _)| other != name)
+            .map(|(other, _)| (format!("{}ID", other), other.clone()))
+            .collect();
+        for column in &mut entity.columns {
+            if let Some((_, target)) = targets.iter().find(|(key, _)| *key == column.name) {
+                column.references = Some(target.clone());
+            }
+        }
+        entities.push(entity);
+    }
+    entities
+}
+
+/// Entities of an OpenAPI / Swagger document or a JSON Schema, none for other JSON and YAML files
+fn parse_api_schema(path: &Path, content: &str, file: &str) -> Result<Vec<Entity>> {
+    if !["openapi", "swagger", "$schema", "$defs", "definitions"].iter().any(|key| content.contains(key)) {
+        return Ok(Vec::new());
+    }
+    let document = parse_document(path, content)?;
+
+    let (kind, schemas) = if document.get("openapi").is_some() || document.get("swagger").is_some() {
+        let schemas = document.pointer("/components/schemas").or_else(|| document.get("definitions"));
+        (SchemaKind::OpenApi, schemas.and_then(Json::as_object).cloned().unwrap_or_default())
+    } else if document.get("$schema").is_some() {
+        let mut schemas = serde_json::Map::new();
+        for key in ["$defs", "definitions"] {
+            schemas.extend(document.get(key).and_then(Json::as_object).cloned().unwrap_or_default());
+        }
+        if document.get("properties").is_some() {
+            let stem = path.file_stem().map(|s| s.to_string_lossy().trim_end_matches(".schema").to_string()).unwrap_or_default();
+            let name = document.get("title").and_then(Json::as_str).map(str::to_string).unwrap_or(stem);
+            schemas.insert(name, document.clone());
+        }
+        (SchemaKind::JsonSchema, schemas)
+    } else {
+        return Ok(Vec::new());
+    };
+
+    let names: BTreeSet<String> = schemas.keys().cloned().collect();
+    Ok(schemas
+        .iter()
+        .filter(|(_, schema)| schema.get("properties").is_some())
+        .map(|(name, schema)| {
+            let mut entity = Entity::new(name, file, kind);
+            let required: BTreeSet<&str> = schema
+                .get("required")
+                .and_then(Json::as_array)
+                .map(|required| required.iter().filter_map(Json::as_str).collect())
+                .unwrap_or_default();
+            for (field, property) in schema.get("properties").and_then(Json::as_object).into_iter().flatten() {
+                if let Some(target) = schema_ref(property).filter(|target| names.contains(target)) {
+                    entity.relations.push(Relation { name: field.clone(), target, many: false });
+                    continue;
+                }
+                if let Some(target) = property.get("items").and_then(schema_ref).filter(|target| names.contains(target)) {
+                    entity.relations.push(Relation { name: field.clone(), target, many: true });
+                    continue;
+                }
+                let ty = property.get("format").or_else(|| property.get("type")).map(|ty| match ty {
+                    Json::String(ty) => ty.clone(),
+                    other => other.to_string(),
+                });
+                let mut column = Column::new(field, &ty.unwrap_or_default());
+                column.nullable = !required.contains(field.as_str());
+                column.primary_key = field == "id";
+                entity.columns.push(column);
+            }
+            entity
+        })
+        .collect())
+}
+
+/// Name of the schema a `$ref` (possibly wrapped in `allOf`) points to
+fn schema_ref(schema: &Json) -> Option<String> {
+    let reference = schema
+        .get("$ref")
+        .or_else(|| schema.get("allOf")?.as_array()?.iter().find_map(|s| s.get("$ref")))?
+        .as_str()?;
+    reference.rsplit('/').next().map(str::to_string)
+}
+
+/// JSON or YAML document as JSON
+pub(crate) fn parse_document(path: &Path, content: &str) -> Result<Json> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("json") => Ok(serde_json::from_str(content)?),
+        _ => {
+            let yaml: serde_yaml::Value =
+                serde_yaml::from_str(content).map_err(|e| Error::Generic(format!("invalid YAML: {}", e)))?;
+            serde_json::to_value(yaml).map_err(|e| Error::Generic(format!("unsupported YAML: {}", e)))
+        }
+    }
+}
+
+/// One-to-many edges between known entities, `(one, many, label)`, deduplicated
+fn relationships(entities: &[Entity]) -> Vec<(String, String, String)> {
+    let names: HashMap<String, &str> = entities.iter().map(|e| (e.name.to_lowercase(), e.name.as_str())).collect();
+    let known = |name: &str| names.get(&name.to_lowercase()).map(|name| name.to_string());
+
+    let mut seen = BTreeSet::new();
+    let mut edges = Vec::new();
+    for entity in entities {
+        let foreign_keys = entity
+            .columns
+            .iter()
+            .filter_map(|c| Some((known(c.references.as_deref()?)?, entity.name.clone(), c.name.clone())));
+        let relations = entity.relations.iter().filter_map(|r| {
+            let target = known(&r.target)?;
+            Some(match r.many {
+                true => (entity.name.clone(), target, r.name.clone()),
+                false => (target, entity.name.clone(), r.name.clone()),
+            })
+        });
+        for (one, many, label) in foreign_keys.chain(relations) {
+            if seen.insert((one.clone(), many.clone())) {
+                edges.push((one, many, label));
+            }
+        }
+    }
+    edges
+}
+
+/// Mermaid `erDiagram` of the entities, the most related ones when there are many
+pub fn er_diagram(entities: &[Entity]) -> String {
+    let edges = relationships(entities);
+    let mut degree: HashMap<&str, usize> = HashMap::new();
+    for (one, many, _) in &edges {
+        *degree.entry(one).or_default() += 1;
+        *degree.entry(many).or_default() += 1;
+    }
+    let mut drawn: Vec<&Entity> = entities.iter().collect();
+    drawn.sort_by_key(|e| std::cmp::Reverse(degree.get(e.name.as_str()).copied().unwrap_or(0)));
+    drawn.truncate(MAX_DIAGRAM_ENTITIES);
+    let drawn_names: BTreeSet<&str> = drawn.iter().map(|e| e.name.as_str()).collect();
+
+    let mut diagram = String::from("```mermaid\nerDiagram\n");
+    for entity in entities.iter().filter(|e| drawn_names.contains(e.name.as_str())) {
+        diagram.push_str(&format!("    {} {{\n", er_name(&entity.name)));
+        for column in &entity.columns {
+            let keys: Vec<&str> = [(column.primary_key, "PK"), (column.references.is_some(), "FK")]
+                .into_iter()
+                .filter_map(|(is, key)| is.then_some(key))
+                .collect();
+            let keys = if keys.is_empty() { String::new() } else { format!(" {}", keys.join(", ")) };
+            diagram.push_str(&format!("        {} {}{}\n", er_type(&column.ty), er_name(&column.name), keys));
+        }
+        diagram.push_str("    }\n");
+    }
+    for (one, many, label) in edges.iter().filter(|(one, many, _)| drawn_names.contains(one.as_str()) && drawn_names.contains(many.as_str())) {
+        diagram.push_str(&format!("    {} ||--o{{ {} : \"{}\"\n", er_name(one), er_name(many), label.replace('"', "'")));
+    }
+    diagram.push_str("```\n");
+    diagram
+}
+
+/// Data Models section of the knowledge file, empty without entities
+pub fn section(entities: &[Entity]) -> String {
+    if entities.is_empty() {
+        return String::new();
+    }
+
+    let mut section = format!("{}\n\n{}", DATA_MODELS_HEADING, er_diagram(entities));
+    if entities.len() > MAX_DIAGRAM_ENTITIES {
+        section.push_str(&format!(
+            "\n_The diagram shows the {} most related of {} entities._\n",
+            MAX_DIAGRAM_ENTITIES,
+            entities.len()
+        ));
+    }
+
+    let edges = relationships(entities);
+    section.push_str("\n| Entity | Columns | Relations | Source |\n|---|---|---|---|\n");
+    for entity in entities {
+        let relations: Vec<String> = edges
+            .iter()
+            .filter_map(|(one, many, label)| match () {
+                _ if *many == entity.name => Some(format!("→ {} ({})", one, label)),
+                _ if *one == entity.name => Some(format!("← {} ({})", many, label)),
+                _ => None,
+            })
+            .collect();
+        section.push_str(&format!(
+            "| {} | {} | {} | `{}` ({}) |\n",
+            entity.name,
+            columns_text(entity).replace('|', "\\|"),
+            relations.join(", "),
+            entity.file,
+            entity.kind
+        ));
+    }
+    section
+}
+
+/// Compact text of the entities for the LLM
+pub fn summary(entities: &[Entity]) -> String {
+    let mut summary = String::new();
+    for entity in entities {
+        summary.push_str(&format!("{} ({}, {}): {}\n", entity.name, entity.kind, entity.file, columns_text(entity)));
+        for relation in &entity.relations {
+            let arity = if relation.many { "many" } else { "one" };
+            summary.push_str(&format!("  {} → {} {}\n", relation.name, arity, relation.target));
+        }
+    }
+    summary
+}
+
+/// Insert the Data Models section into a generated document: at the marker, else at the end
+pub fn embed(document: &str, section: &str) -> String {
+    diagrams::embed_section(document, DATA_MODELS_MARKER, DATA_MODELS_HEADING, section)
+}
+
+fn columns_text(entity: &Entity) -> String {
+    let mut columns: Vec<String> = entity
+        .columns
+        .iter()
+        .take(SUMMARY_COLUMNS)
+        .map(|column| {
+            let mut text = column.name.clone();
+            if !column.ty.is_empty() {
+                text.push_str(&format!(" {}", column.ty));
+            }
+            if column.primary_key {
+                text.push_str(" PK");
+            }
+            if let Some(target) = &column.references {
+                text.push_str(&format!(" → {}", target));
+            }
+            if !column.nullable && !column.primary_key {
+                text.push_str(" not null");
+            }
+            text
+        })
+        .collect();
+    if entity.columns.len() > SUMMARY_COLUMNS {
+        columns.push(format!("… {} more", entity.columns.len() - SUMMARY_COLUMNS));
+    }
+    columns.join(", ")
+}
+
+/// Identifier valid as a Mermaid ER entity or attribute name
+fn er_name(name: &str) -> String {
+    let name: String = name.chars().map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' }).collect();
+    match name.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => name,
+        _ => format!("_{}", name),
+    }
+}
+
+/// Type valid in a Mermaid ER attribute
+fn er_type(ty: &str) -> String {
+    let ty: String = ty
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace(", ", ",")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '-' | '(' | ')' | '[' | ']') { c } else { '_' })
+        .collect();
+    match ty.chars().next() {
+        None => "any".to_string(),
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => ty,
+        Some(_) => format!("_{}", ty),
+    }
+}
+
+/// Replace the stored entities
+pub async fn store(db: &SqlitePool, entities: &[Entity]) -> Result<()> {
+    sqlx::query("DELETE FROM entities").execute(db).await.map_err(Error::Sqlx)?;
+    for entity in entities {
+        sqlx::query("INSERT OR REPLACE INTO entities (file, name, kind, facts, updated_at) VALUES ($1, $2, $3, $4, $5)")
+            .bind(&entity.file)
+            .bind(&entity.name)
+            .bind(entity.kind.to_string())
+            .bind(serde_json::to_string(entity)?)
+            .bind(chrono::Utc::now())
+            .execute(db)
+            .await
+            .map_err(Error::Sqlx)?;
+    }
+    Ok(())
+}
+
+/// Stored entities defined under a directory (`None` for the whole repository)
+pub async fn load(db: &SqlitePool, dir: Option<&str>) -> Result<Vec<Entity>> {
+    let rows = sqlx::query("SELECT facts FROM entities ORDER BY file, name")
+        .fetch_all(db)
+        .await
+        .map_err(Error::Sqlx)?;
+
+    let prefix = dir.map(|dir| format!("{}/", dir));
+    let mut entities = Vec::new();
+    for row in rows {
+        let facts: String = row.try_get("facts").map_err(Error::Sqlx)?;
+        let entity: Entity = serde_json::from_str(&facts)?;
+        if prefix.as_deref().is_none_or(|prefix| entity.file.starts_with(prefix)) {
+            entities.push(entity);
+        }
+    }
+    Ok(entities)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sql_tables(migrations: &[&str]) -> Vec<Entity> {
+        let mut schema = SqlSchema::default();
+        for (i, migration) in migrations.iter().enumerate() {
+            schema.apply(migration, &format!("migrations/{}.sql", i));
+        }
+        schema.tables
+    }
+
+    fn column<'a>(entity: &'a Entity, name: &str) -> &'a Column {
+        entity.columns.iter().find(|c| c.name == name).unwrap_or_else(|| panic!("no column {} in {:?}", name, entity))
+    }
+
+    #[test]
+    fn replays_alter_table_rename_and_drop() {
+        let tables = sql_tables(&[
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL, legacy TEXT);\n\
+             CREATE TABLE sessions (id INTEGER);",
+            "ALTER TABLE users RENAME COLUMN name TO full_name, DROP COLUMN legacy, ADD COLUMN email VARCHAR(255);\n\
+             ALTER TABLE users RENAME TO accounts;\n\
+             DROP TABLE IF EXISTS sessions;",
+        ]);
+
+        assert_eq!(tables.len(), 1);
+        let accounts = &tables[0];
+        assert_eq!(accounts.name, "accounts");
+        let names: Vec<&str> = accounts.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["id", "full_name", "email"]);
+        assert!(column(accounts, "id").primary_key);
+        assert!(!column(accounts, "full_name").nullable);
+        assert_eq!(column(accounts, "email").ty, "VARCHAR(255)");
+        assert_eq!(accounts.file, "migrations/0.sql");
+    }
+
+    #[test]
+    fn applies_composite_primary_and_foreign_keys() {
+        let tables = sql_tables(&[
+            "CREATE TABLE \"public\".\"orders\" (id BIGINT PRIMARY KEY);\n\
+             CREATE TABLE order_items (\n\
+                 order_id BIGINT,\n\
+                 line INT, -- position in the order\n\
+                 sku TEXT REFERENCES products(sku),\n\
+                 CONSTRAINT pk_items PRIMARY KEY (order_id, line),\n\
+                 CONSTRAINT fk_order FOREIGN KEY (order_id) REFERENCES public.orders (id) ON DELETE CASCADE,\n\
+                 UNIQUE (sku, line)\n\
+             );",
+        ]);
+
+        assert_eq!(tables[0].name, "orders");
+        let items = &tables[1];
+        assert_eq!(items.columns.len(), 3);
+        assert!(column(items, "order_id").primary_key && column(items, "line").primary_key);
+        assert!(!column(items, "line").nullable);
+        assert!(!column(items, "sku").primary_key);
+        assert_eq!(column(items, "order_id").references.as_deref(), Some("orders"));
+        assert_eq!(column(items, "sku").references.as_deref(), Some("products"));
+    }
+
+    #[test]
+    fn reads_diesel_tables() {
+        let entities = parse_diesel(
+            "diesel::table! {\n    posts (id) {\n        id -> Int4,\n        author_id -> Int4,\n        \
+             subtitle -> Nullable<Varchar>,\n    }\n}\n\ndiesel::joinable!(posts -> users (author_id));\n",
+            "src/schema.rs",
+        );
+
+        assert_eq!(entities.len(), 1);
+        let posts = &entities[0];
+        assert_eq!(posts.kind, SchemaKind::Diesel);
+        assert!(column(posts, "id").primary_key && !column(posts, "id").nullable);
+        assert_eq!(column(posts, "subtitle").ty, "Varchar");
+        assert!(column(posts, "subtitle").nullable);
+        assert_eq!(column(posts, "author_id").references.as_deref(), Some("users"));
+    }
+
+    #[test]
+    fn keeps_same_named_entities_of_different_projects() {
+        let root = tempfile::tempdir().unwrap();
+        let files = [
+            ("services/a/1_schema.sql", "CREATE TABLE users (id INT);"),
+            ("services/a/models.prisma", "model users {\n  id Int @id\n}\n"),
+            ("services/b/1_schema.sql", "CREATE TABLE users (id INT, email TEXT);"),
+            ("services/b/2_drop.sql", "DROP TABLE users;"),
+            ("services/bb/1_schema.sql", "CREATE TABLE users (uid INT);"),
+        ];
+        let entries: Vec<Entry> = files
+            .iter()
+            .map(|(relative, content)| {
+                let path = root.path().join(relative);
+                std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+                std::fs::write(&path, content).unwrap();
+                Entry { relative: relative.into(), path, is_dir: false, class: None }
+            })
+            .collect();
+
+        let dirs = ["services/a".to_string(), "services/b".to_string(), "services/bb".to_string()];
+        let found: Vec<(String, String)> = discover(&entries, &dirs).into_iter().map(|e| (e.file, e.name)).collect();
+        assert_eq!(
+            found,
+            [
+                ("services/a/1_schema.sql".to_string(), "users".to_string()),
+                ("services/bb/1_schema.sql".to_string(), "users".to_string()),
+            ]
+        );
+
+        // Without sub-projects the repository is one scope
+        assert_eq!(discover(&entries, &[]).len(), 1);
+    }
+}
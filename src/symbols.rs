@@ -0,0 +1,906 @@
+//! Public symbols of source files, extracted with tree-sitter.
+//!
+//! Rust, Go, Python, TypeScript/JavaScript, Java, C and C++ files are parsed
+//! and reduced to their public types, functions, traits and interfaces, with
+//! their signatures and doc comments. The symbols are stored in the `symbols`
+//! table and sent to the LLM as compact outlines instead of guessing them from
+//! raw text.
+
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use tree_sitter::{Node, Parser, Tree};
+
+/// Longest signature or doc comment kept
+const MAX_TEXT_LEN: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Rust,
+    Go,
+    Python,
+    TypeScript,
+    Tsx,
+    Java,
+    C,
+    Cpp,
+}
+
+impl Language {
+    /// Language of a source file, by extension
+    pub fn for_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_string_lossy().to_lowercase();
+        match extension.as_str() {
+            "rs" => Some(Language::Rust),
+            "go" => Some(Language::Go),
+            "py" | "pyi" => Some(Language::Python),
+            "ts" | "mts" | "cts" | "js" | "mjs" | "cjs" => Some(Language::TypeScript),
+            "tsx" | "jsx" => Some(Language::Tsx),
+            "java" => Some(Language::Java),
+            "c" | "h" => Some(Language::C),
+            "cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx" => Some(Language::Cpp),
+            _ => None,
+        }
+    }
+
+    fn grammar(&self) -> tree_sitter::Language {
+        match self {
+            Language::Rust => tree_sitter_rust::LANGUAGE.into(),
+            Language::Go => tree_sitter_go::LANGUAGE.into(),
+            Language::Python => tree_sitter_python::LANGUAGE.into(),
+            Language::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            Language::Tsx => tree_sitter_typescript::LANGUAGE_TSX.into(),
+            Language::Java => tree_sitter_java::LANGUAGE.into(),
+            Language::C => tree_sitter_c::LANGUAGE.into(),
+            Language::Cpp => tree_sitter_cpp::LANGUAGE.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolKind {
+    Function,
+    Method,
+    Struct,
+    Enum,
+    Trait,
+    Interface,
+    Class,
+    Type,
+    Constant,
+    Module,
+}
+
+impl fmt::Display for SymbolKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SymbolKind::Function => "fn",
+            SymbolKind::Method => "method",
+            SymbolKind::Struct => "struct",
+            SymbolKind::Enum => "enum",
+            SymbolKind::Trait => "trait",
+            SymbolKind::Interface => "interface",
+            SymbolKind::Class => "class",
+            SymbolKind::Type => "type",
+            SymbolKind::Constant => "const",
+            SymbolKind::Module => "mod",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A public declaration of a source file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Symbol {
+    /// Path relative to the repository root
+    pub file: String,
+    pub name: String,
+    pub kind: SymbolKind,
+    /// Type, trait, class or namespace the symbol belongs to
+    pub parent: Option<String>,
+    /// Declaration without its body, whitespace collapsed
+    pub signature: String,
+    /// First paragraph of the doc comment
+    pub doc: Option<String>,
+    /// 1-based line of the declaration
+    pub line: usize,
+}
+
+/// Public symbols of a source file, `None` when its language is not supported
+pub fn extract_file(path: &Path, relative: &str) -> Result<Option<Vec<Symbol>>> {
+    let Some(language) = Language::for_path(path) else {
+        return Ok(None);
+    };
+    let source = std::fs::read_to_string(path)?;
+    extract(language, &source, relative).map(Some)
+}
+
+/// Public symbols of source code
+pub fn extract(language: Language, source: &str, file: &str) -> Result<Vec<Symbol>> {
+    let tree = parse(language, source, file)?;
+    let mut extractor = Extractor { language, source: source.as_bytes(), file, symbols: Vec::new() };
+    extractor.visit_children(tree.root_node(), None);
+    Ok(extractor.symbols)
+}
+
+/// Syntax tree of source code
+pub(crate) fn parse(language: Language, source: &str, file: &str) -> Result<Tree> {
+    let mut parser = Parser::new();
+    parser
+        .set_language(&language.grammar())
+        .map_err(|e| Error::Analysis(format!("Cannot load the {:?} grammar: {}", language, e)))?;
+    parser
+        .parse(source, None)
+        .ok_or_else(|| Error::Analysis(format!("Cannot parse {}", file)))
+}
+
+struct Extractor<'a> {
+    language: Language,
+    source: &'a [u8],
+    file: &'a str,
+    symbols: Vec<Symbol>,
+}
+
+impl<'a> Extractor<'a> {
+    fn visit_children(&mut self, node: Node, parent: Option<&str>) {
+        let mut cursor = node.walk();
+        let children: Vec<Node> = node.named_children(&mut cursor).collect();
+        for child in children {
+            match self.language {
+                Language::Rust => self.rust(child, parent),
+                Language::Go => self.go(child),
+                Language::Python => self.python(child, parent),
+                Language::TypeScript | Language::Tsx => self.typescript(child, parent, false),
+                Language::Java => self.java(child, parent),
+                Language::C | Language::Cpp => self.c(child, parent),
+            }
+        }
+    }
+
+    fn text(&self, node: Node) -> &'a str {
+        node.utf8_text(self.source).unwrap_or_default()
+    }
+
+    fn field(&self, node: Node, field: &str) -> Option<&'a str> {
+        node.child_by_field_name(field).map(|child| self.text(child))
+    }
+
+    fn has_child(&self, node: Node, kind: &str, text: impl Fn(&str) -> bool) -> bool {
+        let mut cursor = node.walk();
+        let found = node.children(&mut cursor).any(|child| child.kind() == kind && text(self.text(child)));
+        found
+    }
+
+    /// Declaration up to its body (or the whole node), on one line
+    fn signature(&self, node: Node, body: Option<Node>) -> String {
+        let end = body.map(|body| body.start_byte()).unwrap_or(node.end_byte());
+        let text = String::from_utf8_lossy(&self.source[node.start_byte()..end]);
+        let signature = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        truncate(signature.trim_end_matches(['{', ';', ':', ' ']))
+    }
+
+    /// Comments right above a node, markers stripped
+    fn doc(&self, node: Node) -> Option<String> {
+        let mut lines = Vec::new();
+        let mut next_row = node.start_position().row;
+        let mut sibling = node.prev_named_sibling();
+        // Rust attributes sit between the doc comment and the item
+        while let Some(attribute) = sibling.filter(|s| s.kind() == "attribute_item") {
+            next_row = attribute.start_position().row;
+            sibling = attribute.prev_named_sibling();
+        }
+        while let Some(comment) = sibling.filter(|s| s.kind().contains("comment")) {
+            // Only comments directly above, without blank lines
+            if comment.end_position().row + 1 < next_row {
+                break;
+            }
+            let text = self.text(comment);
+            if self.language == Language::Rust && !(text.starts_with("///") || text.starts_with("/**")) {
+                break;
+            }
+            lines.push(text);
+            next_row = comment.start_position().row;
+            sibling = comment.prev_named_sibling();
+        }
+        lines.reverse();
+        clean_doc(&lines.join("\n"))
+    }
+
+    fn push(&mut self, node: Node, name: &str, kind: SymbolKind, parent: Option<&str>, signature: String, doc: Option<String>) {
+        if name.is_empty() {
+            return;
+        }
+        self.symbols.push(Symbol {
+            file: self.file.to_string(),
+            name: name.to_string(),
+            kind,
+            parent: parent.map(str::to_string),
+            signature,
+            doc,
+            line: node.start_position().row + 1,
+        });
+    }
+
+    fn rust(&mut self, node: Node, parent: Option<&str>) {
+        let kind = match node.kind() {
+            "function_item" | "function_signature_item" => {
+                if parent.is_some() { SymbolKind::Method } else { SymbolKind::Function }
+            }
+            "struct_item" | "union_item" => SymbolKind::Struct,
+            "enum_item" => SymbolKind::Enum,
+            "trait_item" => SymbolKind::Trait,
+            "type_item" => SymbolKind::Type,
+            "const_item" | "static_item" => SymbolKind::Constant,
+            "mod_item" => SymbolKind::Module,
+            "impl_item" => {
+                // Trait impls only repeat the trait's API
+                if node.child_by_field_name("trait").is_none() {
+                    if let (Some(ty), Some(body)) = (self.field(node, "type"), node.child_by_field_name("body")) {
+                        self.visit_children(body, Some(ty));
+                    }
+                }
+                return;
+            }
+            _ => return,
+        };
+
+        // Methods of a public trait are public; elsewhere `pub` is required (not `pub(crate)`)
+        let in_trait = parent.is_some() && node.parent().is_some_and(|p| p.parent().is_some_and(|g| g.kind() == "trait_item"));
+        if !in_trait && !self.has_child(node, "visibility_modifier", |v| v == "pub") {
+            return;
+        }
+
+        let name = self.field(node, "name").unwrap_or_default();
+        let body = node.child_by_field_name("body");
+        let signature = self.signature(node, body);
+        self.push(node, name, kind, parent, signature, self.doc(node));
+
+        match kind {
+            SymbolKind::Trait => {
+                if let Some(body) = body {
+                    self.visit_children(body, Some(name));
+                }
+            }
+            SymbolKind::Module => {
+                if let Some(body) = body {
+                    self.visit_children(body, None);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn go(&mut self, node: Node) {
+        let exported = |name: &str| name.starts_with(|c: char| c.is_uppercase());
+        match node.kind() {
+            "function_declaration" | "method_declaration" => {
+                let name = self.field(node, "name").unwrap_or_default();
+                if !exported(name) {
+                    return;
+                }
+                // Receiver `(s *Server)` gives the parent type
+                let receiver = node.child_by_field_name("receiver").map(|r| {
+                    self.text(r).trim_matches(['(', ')']).split_whitespace().last().unwrap_or_default().trim_start_matches('*').to_string()
+                });
+                let kind = if receiver.is_some() { SymbolKind::Method } else { SymbolKind::Function };
+                let signature = self.signature(node, node.child_by_field_name("body"));
+                self.push(node, name, kind, receiver.as_deref(), signature, self.doc(node));
+            }
+            "type_declaration" => {
+                let mut cursor = node.walk();
+                let specs: Vec<Node> = node.named_children(&mut cursor).filter(|c| c.kind() == "type_spec" || c.kind() == "type_alias").collect();
+                for spec in specs {
+                    let name = self.field(spec, "name").unwrap_or_default();
+                    if !exported(name) {
+                        continue;
+                    }
+                    let kind = match spec.child_by_field_name("type").map(|t| t.kind()) {
+                        Some("struct_type") => SymbolKind::Struct,
+                        Some("interface_type") => SymbolKind::Interface,
+                        _ => SymbolKind::Type,
+                    };
+                    let body = spec.child_by_field_name("type").filter(|t| t.kind() == "struct_type" || t.kind() == "interface_type");
+                    let signature = match body {
+                        Some(body) => format!("type {} {}", name, body.kind().trim_end_matches("_type")),
+                        None => format!("type {}", self.signature(spec, None)),
+                    };
+                    self.push(spec, name, kind, None, signature, self.doc(node));
+                }
+            }
+            "const_declaration" => {
+                let mut cursor = node.walk();
+                let specs: Vec<Node> = node.named_children(&mut cursor).filter(|c| c.kind() == "const_spec").collect();
+                for spec in specs {
+                    let name = self.field(spec, "name").unwrap_or_default();
+                    if exported(name) {
+                        let signature = format!("const {}", self.signature(spec, None));
+                        self.push(spec, name, SymbolKind::Constant, None, signature, self.doc(spec).or_else(|| self.doc(node)));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn python(&mut self, node: Node, parent: Option<&str>) {
+        let definition = match node.kind() {
+            "decorated_definition" => match node.child_by_field_name("definition") {
+                Some(definition) => definition,
+                None => return,
+            },
+            "function_definition" | "class_definition" => node,
+            _ => return,
+        };
+
+        let name = self.field(definition, "name").unwrap_or_default();
+        if name.starts_with('_') && name != "__init__" {
+            return;
+        }
+        let kind = match (definition.kind(), parent) {
+            ("class_definition", _) => SymbolKind::Class,
+            (_, Some(_)) => SymbolKind::Method,
+            _ => SymbolKind::Function,
+        };
+
+        let body = definition.child_by_field_name("body");
+        let signature = self.signature(definition, body);
+        let doc = body.and_then(|body| self.docstring(body)).or_else(|| self.doc(node));
+        self.push(definition, name, kind, parent, signature, doc);
+
+        // Methods of classes, not nested functions
+        if kind == SymbolKind::Class {
+            if let Some(body) = body {
+                self.visit_children(body, Some(name));
+            }
+        }
+    }
+
+    /// String literal opening a Python block
+    fn docstring(&self, body: Node) -> Option<String> {
+        let first = body.named_child(0).filter(|n| n.kind() == "expression_statement")?;
+        let string = first.named_child(0).filter(|n| n.kind() == "string")?;
+        clean_doc(self.text(string).trim_matches(['"', '\'']))
+    }
+
+    fn typescript(&mut self, node: Node, parent: Option<&str>, exported: bool) {
+        let kind = match node.kind() {
+            "export_statement" => {
+                if let Some(declaration) = node.child_by_field_name("declaration") {
+                    self.typescript(declaration, parent, true);
+                }
+                return;
+            }
+            "function_declaration" | "generator_function_declaration" | "function_signature" => SymbolKind::Function,
+            "class_declaration" | "abstract_class_declaration" => SymbolKind::Class,
+            "interface_declaration" => SymbolKind::Interface,
+            "type_alias_declaration" => SymbolKind::Type,
+            "enum_declaration" => SymbolKind::Enum,
+            "internal_module" | "module" => SymbolKind::Module,
+            "method_definition" | "abstract_method_signature" | "method_signature" if parent.is_some() => {
+                let private = self.has_child(node, "accessibility_modifier", |m| m != "public");
+                let name = self.field(node, "name").unwrap_or_default();
+                if private || name.starts_with('#') {
+                    return;
+                }
+                SymbolKind::Method
+            }
+            _ => return,
+        };
+        if parent.is_none() && !exported {
+            return;
+        }
+
+        let name = self.field(node, "name").unwrap_or_default();
+        let body = node.child_by_field_name("body");
+        let signature = self.signature(node, body);
+        // Doc comments sit above the `export` keyword
+        let doc = if exported { node.parent().and_then(|p| self.doc(p)) } else { self.doc(node) };
+        self.push(node, name, kind, parent, signature, doc);
+
+        match kind {
+            SymbolKind::Class | SymbolKind::Interface => {
+                if let Some(body) = body {
+                    self.visit_children(body, Some(name));
+                }
+            }
+            SymbolKind::Module => {
+                if let Some(body) = body {
+                    self.visit_children(body, None);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn java(&mut self, node: Node, parent: Option<&str>) {
+        let kind = match node.kind() {
+            "class_declaration" | "record_declaration" => SymbolKind::Class,
+            "interface_declaration" | "annotation_type_declaration" => SymbolKind::Interface,
+            "enum_declaration" => SymbolKind::Enum,
+            "method_declaration" | "constructor_declaration" => SymbolKind::Method,
+            _ => return,
+        };
+
+        // Interface members are implicitly public
+        let in_interface = node.parent().and_then(|p| p.parent()).is_some_and(|g| g.kind() == "interface_declaration");
+        let public = self.has_child(node, "modifiers", |m| m.split_whitespace().any(|w| w == "public"));
+        if !public && !in_interface {
+            return;
+        }
+
+        let name = self.field(node, "name").unwrap_or_default();
+        let body = node.child_by_field_name("body");
+        let signature = self.signature(node, body);
+        self.push(node, name, kind, parent, signature, self.doc(node));
+
+        if kind != SymbolKind::Method {
+            if let Some(body) = body {
+                self.visit_children(body, Some(name));
+            }
+        }
+    }
+
+    fn c(&mut self, node: Node, parent: Option<&str>) {
+        match node.kind() {
+            "function_definition" | "declaration" | "field_declaration" => {
+                let Some(declarator) = node.child_by_field_name("declarator") else { return };
+                if !contains_kind(declarator, "function_declarator") {
+                    return;
+                }
+                if self.has_child(node, "storage_class_specifier", |s| s == "static") && parent.is_none() {
+                    return;
+                }
+                let name = self.declarator_name(declarator);
+                let kind = if parent.is_some() { SymbolKind::Method } else { SymbolKind::Function };
+                let signature = self.signature(node, node.child_by_field_name("body"));
+                self.push(node, &name, kind, parent, signature, self.doc(node));
+            }
+            "struct_specifier" | "class_specifier" | "union_specifier" | "enum_specifier" => {
+                let Some(body) = node.child_by_field_name("body") else { return };
+                let name = self.field(node, "name").unwrap_or_default();
+                let kind = match node.kind() {
+                    "class_specifier" => SymbolKind::Class,
+                    "enum_specifier" => SymbolKind::Enum,
+                    _ => SymbolKind::Struct,
+                };
+                let signature = self.signature(node, Some(body));
+                self.push(node, name, kind, parent, signature, self.doc(node));
+                if self.language == Language::Cpp && kind != SymbolKind::Enum {
+                    self.cpp_members(body, name, kind == SymbolKind::Struct);
+                }
+            }
+            "type_definition" => {
+                // The typedef stands for the struct or enum it defines
+                if let Some(declarator) = node.child_by_field_name("declarator") {
+                    let name = self.declarator_name(declarator);
+                    let signature = self.signature(node, None);
+                    self.push(node, &name, SymbolKind::Type, parent, signature, self.doc(node));
+                }
+            }
+            "namespace_definition" => {
+                let name = self.field(node, "name").unwrap_or_default();
+                if let Some(body) = node.child_by_field_name("body") {
+                    self.visit_children(body, (!name.is_empty()).then_some(name));
+                }
+            }
+            "template_declaration" | "linkage_specification" | "preproc_ifdef" | "preproc_if" | "preproc_else" => {
+                self.visit_children(node, parent);
+            }
+            "declaration_list" => self.visit_children(node, parent),
+            _ => {}
+        }
+    }
+
+    /// Public members of a C++ class or struct, tracking access specifiers
+    fn cpp_members(&mut self, body: Node, owner: &str, public_by_default: bool) {
+        let mut public = public_by_default;
+        let mut cursor = body.walk();
+        let members: Vec<Node> = body.named_children(&mut cursor).collect();
+        for member in members {
+            if member.kind() == "access_specifier" {
+                public = self.text(member).trim_end_matches(':').trim() == "public";
+            } else if public {
+                self.c(member, Some(owner));
+            }
+        }
+    }
+
+    /// Identifier of a (possibly nested) C declarator
+    fn declarator_name(&self, declarator: Node) -> String {
+        let mut node = declarator;
+        loop {
+            match node.kind() {
+                "identifier" | "field_identifier" | "type_identifier" | "qualified_identifier" | "destructor_name" | "operator_name" => {
+                    return self.text(node).to_string();
+                }
+                _ => match node.child_by_field_name("declarator") {
+                    Some(inner) => node = inner,
+                    None => return String::new(),
+                },
+            }
+        }
+    }
+}
+
+fn contains_kind(node: Node, kind: &str) -> bool {
+    if node.kind() == kind {
+        return true;
+    }
+    node.child_by_field_name("declarator").is_some_and(|inner| contains_kind(inner, kind))
+}
+
+/// First paragraph of a comment, without comment markers
+fn clean_doc(comment: &str) -> Option<String> {
+    let lines: Vec<&str> = comment
+        .lines()
+        .map(|line| {
+            line.trim()
+                .trim_start_matches("/**")
+                .trim_start_matches("/*")
+                .trim_end_matches("*/")
+                .trim_start_matches("///")
+                .trim_start_matches("//!")
+                .trim_start_matches("//")
+                .trim_start_matches('*')
+                .trim_start_matches('#')
+                .trim()
+        })
+        .skip_while(|line| line.is_empty())
+        .take_while(|line| !line.is_empty())
+        .collect();
+    let doc = lines.join(" ");
+    (!doc.is_empty()).then(|| truncate(&doc))
+}
+
+fn truncate(text: &str) -> String {
+    if text.chars().count() <= MAX_TEXT_LEN {
+        return text.to_string();
+    }
+    let truncated: String = text.chars().take(MAX_TEXT_LEN - 1).collect();
+    format!("{}…", truncated)
+}
+
+/// Outline of the symbols, grouped by file, for the LLM and the knowledge file
+pub fn outline(symbols: &[Symbol]) -> String {
+    let mut by_file: BTreeMap<&str, Vec<&Symbol>> = BTreeMap::new();
+    for symbol in symbols {
+        by_file.entry(&symbol.file).or_default().push(symbol);
+    }
+
+    let mut outline = String::new();
+    for (file, symbols) in by_file {
+        outline.push_str(&format!("{}\n", file));
+        for symbol in symbols {
+            let indent = if symbol.parent.is_some() { "    " } else { "  " };
+            match &symbol.doc {
+                Some(doc) => outline.push_str(&format!("{}{} — {}\n", indent, symbol.signature, doc)),
+                None => outline.push_str(&format!("{}{}\n", indent, symbol.signature)),
+            }
+        }
+    }
+    outline
+}
+
+/// Replace the stored symbols with the given ones
+pub async fn store(db: &SqlitePool, symbols: &[Symbol]) -> Result<()> {
+    let mut tx = db.begin().await.map_err(Error::Sqlx)?;
+    sqlx::query("DELETE FROM symbols").execute(&mut *tx).await.map_err(Error::Sqlx)?;
+    for symbol in symbols {
+        sqlx::query(
+            "INSERT INTO symbols (file, name, kind, parent, signature, doc, line) VALUES ($1, $2, $3, $4, $5, $6, $7)"
+        )
+        .bind(&symbol.file)
+        .bind(&symbol.name)
+        .bind(serde_json::to_string(&symbol.kind)?)
+        .bind(&symbol.parent)
+        .bind(&symbol.signature)
+        .bind(&symbol.doc)
+        .bind(symbol.line as i64)
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::Sqlx)?;
+    }
+    tx.commit().await.map_err(Error::Sqlx)?;
+    Ok(())
+}
+
+/// Stored symbols, optionally of the files below a directory
+pub async fn load(db: &SqlitePool, dir: Option<&str>) -> Result<Vec<Symbol>> {
+    // Compared literally rather than with LIKE, whose `_` and `%` wildcards are common in directory names
+    let prefix = dir.map(|dir| format!("{}/", dir));
+    let rows = sqlx::query("SELECT * FROM symbols WHERE $1 IS NULL OR substr(file, 1, length($1)) = $1 ORDER BY file, line")
+        .bind(prefix)
+        .fetch_all(db)
+        .await
+        .map_err(Error::Sqlx)?;
+
+    rows.into_iter()
+        .map(|row| {
+            let kind: String = row.try_get("kind").map_err(Error::Sqlx)?;
+            let line: i64 = row.try_get("line").map_err(Error::Sqlx)?;
+            Ok(Symbol {
+                file: row.try_get("file").map_err(Error::Sqlx)?,
+                name: row.try_get("name").map_err(Error::Sqlx)?,
+                kind: serde_json::from_str(&kind)?,
+                parent: row.try_get("parent").map_err(Error::Sqlx)?,
+                signature: row.try_get("signature").map_err(Error::Sqlx)?,
+                doc: row.try_get("doc").map_err(Error::Sqlx)?,
+                line: line as usize,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbols(language: Language, source: &str) -> Vec<Symbol> {
+        extract(language, source, "src/file").unwrap()
+    }
+
+    fn names(symbols: &[Symbol]) -> Vec<String> {
+        symbols
+            .iter()
+            .map(|s| match &s.parent {
+                Some(parent) => format!("{}.{}", parent, s.name),
+                None => s.name.clone(),
+            })
+            .collect()
+    }
+
+    fn find<'a>(symbols: &'a [Symbol], name: &str) -> &'a Symbol {
+        symbols.iter().find(|s| s.name == name).unwrap_or_else(|| panic!("no symbol {} in {:?}", name, names(symbols)))
+    }
+
+    #[test]
+    fn extracts_public_rust_items() {
+        let found = symbols(Language::Rust, r#"
+/// A parsed config.
+///
+/// Second paragraph.
+#[derive(Debug)]
+pub struct Config {
+    pub name: String,
+}
+
+impl Config {
+    /// Load from disk
+    pub fn load(path: &Path) -> Result<Self> { todo!() }
+    fn private(&self) {}
+}
+
+impl Default for Config {
+    fn default() -> Self { todo!() }
+}
+
+pub trait Store {
+    fn get(&self, key: &str) -> Option<String>;
+}
+
+pub(crate) fn internal() {}
+fn hidden() {}
+pub const LIMIT: usize = 10;
+
+pub mod nested {
+    pub enum Kind { A, B }
+}
+"#);
+
+        assert_eq!(names(&found), ["Config", "Config.load", "Store", "Store.get", "LIMIT", "nested", "Kind"]);
+        let config = find(&found, "Config");
+        assert_eq!(config.kind, SymbolKind::Struct);
+        assert_eq!(config.doc.as_deref(), Some("A parsed config."));
+        assert_eq!(config.line, 6);
+        let load = find(&found, "load");
+        assert_eq!(load.kind, SymbolKind::Method);
+        assert_eq!(load.signature, "pub fn load(path: &Path) -> Result<Self>");
+        assert_eq!(load.doc.as_deref(), Some("Load from disk"));
+        assert_eq!(find(&found, "LIMIT").signature, "pub const LIMIT: usize = 10");
+    }
+
+    #[test]
+    fn extracts_exported_go_declarations() {
+        let found = symbols(Language::Go, r#"package server
+
+// Server handles requests.
+type Server struct {
+	addr string
+}
+
+type handler interface{ serve() }
+
+// Start listens on the address.
+func (s *Server) Start() error { return nil }
+
+func (s *Server) stop() {}
+
+func New(addr string) *Server { return nil }
+
+const (
+	// DefaultPort is used without configuration.
+	DefaultPort = 8080
+	maxConns = 10
+)
+"#);
+
+        assert_eq!(names(&found), ["Server", "Server.Start", "New", "DefaultPort"]);
+        assert_eq!(find(&found, "Server").signature, "type Server struct");
+        assert_eq!(find(&found, "Server").doc.as_deref(), Some("Server handles requests."));
+        assert_eq!(find(&found, "Start").signature, "func (s *Server) Start() error");
+        assert_eq!(find(&found, "DefaultPort").doc.as_deref(), Some("DefaultPort is used without configuration."));
+    }
+
+    #[test]
+    fn extracts_public_python_definitions() {
+        let found = symbols(Language::Python, r#"
+class Client:
+    """HTTP client.
+
+    Details.
+    """
+
+    def __init__(self, url):
+        self.url = url
+
+    @property
+    def base(self) -> str:
+        """Base URL"""
+        return self.url
+
+    def _retry(self):
+        def inner():
+            pass
+
+def connect(url: str) -> Client:
+    return Client(url)
+
+def _helper():
+    pass
+"#);
+
+        assert_eq!(names(&found), ["Client", "Client.__init__", "Client.base", "connect"]);
+        assert_eq!(find(&found, "Client").doc.as_deref(), Some("HTTP client."));
+        assert_eq!(find(&found, "base").doc.as_deref(), Some("Base URL"));
+        assert_eq!(find(&found, "connect").signature, "def connect(url: str) -> Client");
+    }
+
+    #[test]
+    fn extracts_exported_typescript_declarations() {
+        let source = r#"
+/** Options of the client. */
+export interface Options {
+  retries(): number;
+}
+
+export class Api {
+  get(path: string): Promise<string> { return fetch(path); }
+  private token(): string { return ""; }
+  #secret() {}
+}
+
+export function create(options: Options): Api { return new Api(); }
+function local() {}
+export type Id = string;
+"#;
+        for language in [Language::TypeScript, Language::Tsx] {
+            let found = symbols(language, source);
+            assert_eq!(names(&found), ["Options", "Options.retries", "Api", "Api.get", "create", "Id"]);
+            assert_eq!(find(&found, "Options").doc.as_deref(), Some("Options of the client."));
+            assert_eq!(find(&found, "create").signature, "function create(options: Options): Api");
+        }
+
+        let found = symbols(Language::Tsx, "export function App() { return <div className=\"app\" />; }\n");
+        assert_eq!(names(&found), ["App"]);
+    }
+
+    #[test]
+    fn extracts_public_java_members() {
+        let found = symbols(Language::Java, r#"
+/** Repository of users. */
+public class UserRepository {
+    /** Find by id. */
+    public User find(long id) { return null; }
+    private void evict() {}
+    void packagePrivate() {}
+}
+
+class Hidden {}
+
+public interface Cache {
+    String get(String key);
+}
+"#);
+
+        assert_eq!(names(&found), ["UserRepository", "UserRepository.find", "Cache", "Cache.get"]);
+        assert_eq!(find(&found, "UserRepository").doc.as_deref(), Some("Repository of users."));
+        assert_eq!(find(&found, "find").signature, "public User find(long id)");
+    }
+
+    #[test]
+    fn extracts_non_static_c_functions() {
+        let found = symbols(Language::C, r#"
+// Parse a buffer.
+int parse(const char *buffer, size_t len);
+
+static int helper(void) { return 0; }
+
+struct point { int x; int y; };
+
+typedef struct { int id; } record_t;
+
+char *format(struct point *p) { return 0; }
+"#);
+
+        assert_eq!(names(&found), ["parse", "point", "record_t", "format"]);
+        assert_eq!(find(&found, "parse").doc.as_deref(), Some("Parse a buffer."));
+        assert_eq!(find(&found, "parse").signature, "int parse(const char *buffer, size_t len)");
+        assert_eq!(find(&found, "record_t").kind, SymbolKind::Type);
+    }
+
+    #[test]
+    fn extracts_public_cpp_members() {
+        let found = symbols(Language::Cpp, r#"
+namespace net {
+/// A TCP socket.
+class Socket {
+public:
+    void connect(int port);
+private:
+    void reset();
+};
+
+struct Endpoint {
+    int port() const;
+};
+}
+"#);
+
+        assert_eq!(names(&found), ["net.Socket", "Socket.connect", "net.Endpoint", "Endpoint.port"]);
+        assert_eq!(find(&found, "Socket").doc.as_deref(), Some("A TCP socket."));
+        assert_eq!(find(&found, "connect").kind, SymbolKind::Method);
+    }
+
+    #[test]
+    fn maps_extensions_to_languages() {
+        for (path, language) in [
+            ("a.rs", Language::Rust),
+            ("a.pyi", Language::Python),
+            ("a.mjs", Language::TypeScript),
+            ("a.jsx", Language::Tsx),
+            ("a.h", Language::C),
+            ("a.hpp", Language::Cpp),
+            ("a.cc", Language::Cpp),
+        ] {
+            assert_eq!(Language::for_path(Path::new(path)), Some(language), "{}", path);
+        }
+        assert_eq!(Language::for_path(Path::new("a.md")), None);
+    }
+
+    #[test]
+    fn outlines_symbols_by_file() {
+        let mut found = symbols(Language::Rust, "/// Entry\npub struct A;\nimpl A {\n    pub fn new() -> Self { A }\n}\n");
+        found.extend(extract(Language::Go, "package b\nfunc Run() {}\n", "b/run.go").unwrap());
+        assert_eq!(outline(&found), "b/run.go\n  func Run()\nsrc/file\n  pub struct A — Entry\n    pub fn new() -> Self\n");
+    }
+
+    #[tokio::test]
+    async fn loads_symbols_below_a_directory_literally() {
+        let repo = tempfile::tempdir().unwrap();
+        let db = crate::open_database(repo.path()).await.unwrap();
+        let mut stored = Vec::new();
+        for file in ["my_app/a.rs", "myXapp/b.rs", "my_app2/c.rs", "100%/d.rs", "100x/e.rs"] {
+            stored.extend(extract(Language::Rust, "pub fn f() {}", file).unwrap());
+        }
+        store(&db, &stored).await.unwrap();
+
+        let files = |symbols: Vec<Symbol>| symbols.into_iter().map(|s| s.file).collect::<Vec<_>>();
+        assert_eq!(files(load(&db, Some("my_app")).await.unwrap()), ["my_app/a.rs"]);
+        assert_eq!(files(load(&db, Some("100%")).await.unwrap()), ["100%/d.rs"]);
+        assert_eq!(load(&db, None).await.unwrap().len(), 5);
+    }
+}
@@ -0,0 +1,136 @@
+//! Deterministic `tree`-style rendering of the discovered project layout.
+//!
+//! The same rendering is sent to the LLM and written verbatim into the
+//! knowledge file, so the Project Structure section never depends on the
+//! model reproducing it. Directories with more entries than the limit are
+//! collapsed into a trailing `… N more files` line.
+
+use crate::discovery::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// Line of the final consolidation replaced by the rendered tree
+pub const STRUCTURE_MARKER: &str = "<!-- raidme:project-structure -->";
+
+/// Heading of the section holding the tree
+pub const STRUCTURE_HEADING: &str = "## Project Structure";
+
+/// Knowledge category of the one-line descriptions of tree entries, keyed by repository path
+pub const DESCRIPTION_CATEGORY: &str = "file";
+
+/// Longest description kept next to an entry
+const MAX_DESCRIPTION_LEN: usize = 80;
+
+/// Renders discovered entries, optionally annotated with one-line descriptions
+pub struct TreeRenderer<'a> {
+    children: HashMap<&'a Path, Vec<&'a Entry>>,
+    max_entries: usize,
+    descriptions: BTreeMap<String, String>,
+}
+
+impl<'a> TreeRenderer<'a> {
+    /// `entries` must be sorted by path, as returned by `Discovery::entries`; 0 entries means no limit
+    pub fn new(entries: &'a [Entry], max_entries: usize) -> Self {
+        let mut children: HashMap<&Path, Vec<&Entry>> = HashMap::new();
+        for entry in entries {
+            if let Some(parent) = entry.relative.parent() {
+                children.entry(parent).or_default().push(entry);
+            }
+        }
+        Self { children, max_entries, descriptions: BTreeMap::new() }
+    }
+
+    /// Annotate entries with descriptions keyed by their relative path; only the first line is kept
+    pub fn with_descriptions(mut self, descriptions: BTreeMap<String, String>) -> Self {
+        self.descriptions = descriptions
+            .into_iter()
+            .filter_map(|(path, text)| one_line(&text).map(|line| (path, line)))
+            .collect();
+        self
+    }
+
+    pub fn render(&self) -> String {
+        let mut result = String::from(".\n");
+        self.render_dir(Path::new(""), &mut result, "");
+        result
+    }
+
+    /// Tree wrapped in the Project Structure section of the knowledge file
+    pub fn render_section(&self) -> String {
+        format!("{}\n\n```text\n{}```\n", STRUCTURE_HEADING, self.render())
+    }
+
+    fn render_dir(&self, dir: &Path, result: &mut String, prefix: &str) {
+        let Some(entries) = self.children.get(dir) else { return };
+
+        let shown = if self.max_entries > 0 && entries.len() > self.max_entries {
+            self.max_entries
+        } else {
+            entries.len()
+        };
+        let hidden = &entries[shown..];
+
+        for (i, entry) in entries[..shown].iter().enumerate() {
+            let is_last = i == shown - 1 && hidden.is_empty();
+            let entry_prefix = if is_last { "└── " } else { "├── " };
+            let next_prefix = if is_last { "    " } else { "│   " };
+
+            result.push_str(&format!("{}{}{}", prefix, entry_prefix, entry.file_name()));
+            if let Some(class) = entry.class {
+                result.push_str(&format!(" {}", class));
+            }
+            if let Some(description) = self.descriptions.get(entry.relative.to_string_lossy().as_ref()) {
+                result.push_str(&format!("  # {}", description));
+            }
+            result.push('\n');
+
+            // Vendored directories are listed but not expanded
+            if entry.is_dir && entry.class.is_none() {
+                self.render_dir(&entry.relative, result, &format!("{}{}", prefix, next_prefix));
+            }
+        }
+
+        if !hidden.is_empty() {
+            let files: usize = hidden.iter().map(|entry| self.file_count(entry)).sum();
+            result.push_str(&format!("{}└── … {} more {}\n", prefix, files, if files == 1 { "file" } else { "files" }));
+        }
+    }
+
+    /// Files at or below an entry
+    fn file_count(&self, entry: &Entry) -> usize {
+        if !entry.is_dir {
+            return 1;
+        }
+        self.children
+            .get(entry.relative.as_path())
+            .map(|children| children.iter().map(|child| self.file_count(child)).sum())
+            .unwrap_or(0)
+    }
+}
+
+/// Insert the tree section into a generated document: at the marker, else after the title
+pub fn embed(document: &str, section: &str) -> String {
+    if document.contains(STRUCTURE_MARKER) {
+        return document.replacen(STRUCTURE_MARKER, section.trim_end(), 1);
+    }
+
+    match document.split_once('\n') {
+        Some((title, rest)) if title.starts_with("# ") => format!("{}\n\n{}\n{}", title, section, rest),
+        _ => format!("{}\n{}", section, document),
+    }
+}
+
+/// First line of prose of a description, without markdown decoration
+fn one_line(text: &str) -> Option<String> {
+    let line = text
+        .lines()
+        .map(|line| line.trim().trim_start_matches(['#', '-', '*', '>']).trim())
+        .find(|line| !line.is_empty())?;
+    let line = line.replace("**", "").replace('`', "");
+
+    if line.chars().count() <= MAX_DESCRIPTION_LEN {
+        return Some(line);
+    }
+    let truncated: String = line.chars().take(MAX_DESCRIPTION_LEN - 1).collect();
+    Some(format!("{}…", truncated.trim_end()))
+}
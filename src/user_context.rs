@@ -0,0 +1,67 @@
+//! User-provided context, stored as a pinned knowledge entry.
+//!
+//! The entry is never summarized and is injected first into every LLM
+//! context, so the maintainers' instructions take precedence at each step.
+
+use crate::error::{Error, Result};
+use sqlx::{Row, SqlitePool};
+
+/// Knowledge category of the user context entry
+pub const CATEGORY: &str = "user_context";
+
+/// Context priority, above any analysis content
+pub const PRIORITY: u32 = 100;
+
+/// Title of the pinned context item
+pub const TITLE: &str = "User Context";
+
+/// Load the current user context, if any
+pub async fn load(db: &SqlitePool) -> Result<Option<String>> {
+    let row = sqlx::query("SELECT content FROM knowledge_entries WHERE category = $1 ORDER BY updated_at DESC LIMIT 1")
+        .bind(CATEGORY)
+        .fetch_optional(db)
+        .await
+        .map_err(Error::Sqlx)?;
+
+    row.map(|row| row.try_get("content"))
+        .transpose()
+        .map_err(Error::Sqlx)
+}
+
+/// Replace the user context
+pub async fn store(db: &SqlitePool, content: &str) -> Result<()> {
+    let now = chrono::Utc::now();
+    let mut tx = db.begin().await.map_err(Error::Sqlx)?;
+
+    sqlx::query("DELETE FROM knowledge_entries WHERE category = $1")
+        .bind(CATEGORY)
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::Sqlx)?;
+
+    sqlx::query(
+        "INSERT INTO knowledge_entries (id, category, subcategory, title, content, relevance_score, created_at, updated_at) VALUES ($1, $2, NULL, $3, $4, $5, $6, $7)"
+    )
+    .bind(uuid::Uuid::new_v4().to_string())
+    .bind(CATEGORY)
+    .bind(TITLE)
+    .bind(content)
+    .bind(1.0)
+    .bind(now)
+    .bind(now)
+    .execute(&mut *tx)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    tx.commit().await.map_err(Error::Sqlx)
+}
+
+/// Remove the user context
+pub async fn clear(db: &SqlitePool) -> Result<()> {
+    sqlx::query("DELETE FROM knowledge_entries WHERE category = $1")
+        .bind(CATEGORY)
+        .execute(db)
+        .await
+        .map_err(Error::Sqlx)?;
+    Ok(())
+}